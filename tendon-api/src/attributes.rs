@@ -1,10 +1,10 @@
 //! Extra data held in multiple diffferent items.
 
 use crate::identities::Identity;
-use crate::items::FunctionItem;
+use crate::items::{Abi, FunctionItem};
 use crate::paths::Ident;
 use crate::paths::UnresolvedPath;
-use crate::scopes::Scope;
+use crate::scopes::{NamespaceId, Scope};
 use crate::tokens::Tokens;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -12,7 +12,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 /// Metadata available for all items, struct fields, etc.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Metadata {
     /// The identifier of this item.
     pub name: Ident,
@@ -20,6 +20,16 @@ pub struct Metadata {
     pub visibility: Visibility,
     /// Docs for this item.
     pub docs: Option<String>,
+    /// The intra-doc links in `docs`, in order.
+    pub doc_links: Vec<DocLink>,
+    /// The standard sections of `docs`, and its doc-tests.
+    pub doc_sections: DocSections,
+    /// If this item is `#[doc(hidden)]`.
+    pub doc_hidden: bool,
+    /// `#[doc(inline)]` or `#[doc(no_inline)]`, on a re-export.
+    pub doc_inline: Option<DocInline>,
+    /// Search aliases, from `#[doc(alias = "...")]`.
+    pub doc_aliases: Vec<String>,
     /// If this item is must_use, the must_use reason.
     pub must_use: Option<String>,
     /// If this item is deprecated, the deprecation reason.
@@ -42,15 +52,25 @@ impl Metadata {
 
     pub fn fake(name: impl Into<Ident>) -> Metadata {
         // create a fake metadata. to be used only in testing.
+        Metadata::synthetic(name, Visibility::Pub, Span::fake())
+    }
 
+    /// Metadata for something that isn't written in the source, like a lifetime parameter added by
+    /// elision: no docs or attributes, and the span of whatever it was derived from.
+    pub fn synthetic(name: impl Into<Ident>, visibility: Visibility, span: Span) -> Metadata {
         Metadata {
             name: name.into(),
-            visibility: Visibility::Pub,
+            visibility,
             docs: None,
+            doc_links: vec![],
+            doc_sections: DocSections::default(),
+            doc_hidden: false,
+            doc_inline: None,
+            doc_aliases: vec![],
             must_use: None,
             deprecated: None,
             extra_attributes: vec![],
-            span: Span::fake(),
+            span,
         }
     }
 }
@@ -59,6 +79,9 @@ impl fmt::Debug for Metadata {
         if let Some(_) = &self.docs {
             write!(f, "#[docs = ...]")?;
         }
+        if self.doc_hidden {
+            write!(f, "#[doc(hidden)]")?;
+        }
         if let Some(deprecated) = &self.deprecated {
             write!(f, "#[deprecated = {:?}]", deprecated)?;
         }
@@ -75,7 +98,62 @@ impl fmt::Debug for Metadata {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// A link to an item in doc comments, written like `[Vec]`, `[`crate::x::Y`]` or
+/// `[struct@Foo]`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocLink {
+    /// The link destination, as written.
+    pub text: String,
+    /// The path it names.
+    pub path: UnresolvedPath,
+    /// The namespace it was disambiguated to, by a prefix like `fn@` or a suffix like `()` or `!`.
+    pub namespace: Option<NamespaceId>,
+    /// The item it links to, once resolved.
+    pub target: Option<Identity>,
+}
+
+/// The sections of an item's docs with standard headings, like `# Safety`, and its doc-tests.
+/// Each section runs until the next heading at the same level or above.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocSections {
+    /// `# Safety`: what callers of an `unsafe fn` have to uphold.
+    pub safety: Option<String>,
+    /// `# Panics`: when the item panics.
+    pub panics: Option<String>,
+    /// `# Errors`: when the item returns an error.
+    pub errors: Option<String>,
+    /// `# Examples`.
+    pub examples: Option<String>,
+    /// The fenced Rust code blocks anywhere in the docs, in order.
+    pub doctests: Vec<Doctest>,
+}
+
+/// Whether rustdoc documents a re-export in place, or as a link to the original item.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DocInline {
+    /// `#[doc(inline)]`
+    Inline,
+    /// `#[doc(no_inline)]`
+    NoInline,
+}
+
+/// A doc-test.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Doctest {
+    /// The code, including lines hidden with `# `.
+    pub code: String,
+    /// The attributes from the code block's info string: `ignore`, `no_run`, `should_panic`,
+    /// `compile_fail`, `edition2018`, ...
+    pub attributes: Vec<String>,
+}
+impl Doctest {
+    /// If the code block has an attribute.
+    pub fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes.iter().any(|a| a == attribute)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 /// A span in a source file.
 pub struct Span {
     /// The source file, a path in the local filesystem.
@@ -170,7 +248,7 @@ impl fmt::Display for Span {
 ///
 /// Note that most built-in attributes are already handled for you; this is for the ones
 /// tendon doesn't know about.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Attribute {
     /// An attribute in the format of the
     /// [`meta` fragment specifier](https://doc.rust-lang.org/reference/attributes.html#meta-item-attribute-syntax).
@@ -209,7 +287,7 @@ impl fmt::Debug for Attribute {
 
 /// The syntax used by most, but not all, attributes, and the
 /// [`meta` fragment specifier](https://doc.rust-lang.org/reference/attributes.html#meta-item-attribute-syntax).
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Meta {
     /// A path attribute, e.g. #[thing]
     Path(UnresolvedPath),
@@ -248,7 +326,7 @@ impl fmt::Debug for Meta {
 }
 
 /// An argument in a meta list.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum MetaInner {
     Meta(Meta),
     Literal(Tokens),
@@ -323,8 +401,28 @@ pub struct TypeMetadata {
     pub repr: Repr,
 }
 
+/// Metadata for items imported from native libraries, declared in `extern` blocks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForeignMetadata {
+    /// The abi of the containing `extern` block.
+    pub abi: Abi,
+    /// The `#[link]` attributes on the containing `extern` block.
+    pub links: Vec<Link>,
+    /// The `#[link_name]` of this item, if present.
+    pub link_name: Option<String>,
+}
+
+/// A `#[link(name = "foo", kind = "static")]` attribute.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The name of the native library.
+    pub name: String,
+    /// The kind of the native library (`static`, `dylib`, `framework`), if present.
+    pub kind: Option<String>,
+}
+
 /// Deprecation metadata.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Deprecation {
     /// Version deprecated since, if present.
     /// TODO: format?
@@ -333,27 +431,37 @@ pub struct Deprecation {
     pub note: Option<String>,
 }
 
-/// A struct representation.
+/// A type's representation, from all of its `#[repr]` attributes.
+/// The facets are independent: `#[repr(C, packed(2))]`, `#[repr(u8, align(4))]`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Repr {
+    /// How fields (or the enum tag) are laid out.
+    pub base: ReprBase,
+    /// `#[repr(packed(n))]`, the maximum alignment of fields. `#[repr(packed)]` is `packed(1)`.
+    pub packed: Option<u64>,
+    /// `#[repr(align(n))]`, the minimum alignment of the type.
+    pub align: Option<u64>,
+}
+
+/// The base of a `#[repr]`, which decides field order and enum tags.
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub enum Repr {
-    /// `#[repr(Rust)]`
+pub enum ReprBase {
+    /// `#[repr(Rust)]`, or no repr.
     Rust,
     /// `#[repr(C)]`
     C,
     /// `#[repr(transparent)]`
     Transparent,
-    /// `#[repr(packed)]`
-    Packed,
-    /// `#[repr(C, i8)]`, etc.
+    /// `#[repr(u8)]`, etc. For enums, the tag is the given integer type.
+    Int(Ident),
+    /// `#[repr(C, u8)]`, etc.
     /// See https://github.com/rust-lang/rfcs/blob/master/text/2195-really-tagged-unions.md
     IntOuterTag(Ident),
-    /// `#[repr(i8)]` or other reprs.
-    Other(Ident),
 }
 
-impl Default for Repr {
+impl Default for ReprBase {
     fn default() -> Self {
-        Repr::Rust
+        ReprBase::Rust
     }
 }
 
@@ -402,6 +510,8 @@ impl_has_metadata!(
         Const(_),
         Static(_),
         Function(_),
+        ForeignFunction(_),
+        ForeignStatic(_),
         ConstParam(_),
     }
 );
@@ -409,7 +519,10 @@ impl_has_metadata!(
     enum TypeItem {
         Struct(_),
         Enum(_),
+        Union(_),
         Trait(_),
+        Alias(_),
+        ForeignType(_),
         TypeParam(_),
         LifetimeParam(_),
     }
@@ -422,8 +535,18 @@ impl_has_metadata!(struct ConstItem);
 impl_has_metadata!(struct StaticItem);
 impl_has_metadata!(struct StructItem);
 impl_has_metadata!(struct EnumItem);
+impl_has_metadata!(struct UnionItem);
 impl_has_metadata!(struct TraitItem);
+impl_has_metadata!(struct AliasItem);
+impl_has_metadata!(struct TraitMethod);
+impl_has_metadata!(struct AssociatedType);
+impl_has_metadata!(struct AssociatedConst);
 impl_has_metadata!(struct FunctionItem);
+impl_has_metadata!(struct ForeignFunctionItem);
+impl_has_metadata!(struct ForeignStaticItem);
+impl_has_metadata!(struct ForeignTypeItem);
+impl_has_metadata!(struct ImplItem);
+impl_has_metadata!(struct ImplType);
 impl_has_metadata!(struct Scope);
 impl_has_metadata!(struct TypeParamItem);
 impl_has_metadata!(struct LifetimeParamItem);
@@ -11,6 +11,8 @@ use crate::Map;
 lazy_static! {
     /// The fake crate we store builtins in.
     pub static ref BUILTINS_CRATE: CrateId = CrateId::new("{builtin}", "0.0.0");
+    /// The fake crate holding paths that haven't been resolved yet. See `Identity::unresolved`.
+    pub static ref UNRESOLVED_CRATE: CrateId = CrateId::new("{unresolved}", "0.0.0");
     pub static ref CORE_CRATE: CrateId = CrateId::new("core", "0.0.0");
     pub static ref ALLOC_CRATE: CrateId = CrateId::new("alloc", "0.0.0");
     pub static ref TEST_CRATE: CrateId = CrateId::new("test", "0.0.0");
@@ -33,6 +35,30 @@ lazy_static! {
     pub static ref USIZE: Identity =  Identity::new(&*BUILTINS_CRATE, &["usize"]);
     pub static ref STATIC: Identity = Identity::new(&*BUILTINS_CRATE, &["'static"]);
 
+    /// `core::future::Future`, which is defined in the private module `core::future::future`.
+    /// Used to desugar `async fn`s.
+    pub static ref FUTURE: Identity = Identity::new(&*CORE_CRATE, &["future", "future", "Future"]);
+    /// `Output`, the associated type of `Future` and of the `Fn` traits (through `FnOnce`).
+    pub static ref OUTPUT: Ident = "Output".into();
+    /// The `Fn` traits, the only traits written with parenthesized sugar, `Fn(u8) -> u8`.
+    pub static ref FN: Identity = Identity::new(&*CORE_CRATE, &["ops", "function", "Fn"]);
+    pub static ref FN_MUT: Identity = Identity::new(&*CORE_CRATE, &["ops", "function", "FnMut"]);
+    pub static ref FN_ONCE: Identity = Identity::new(&*CORE_CRATE, &["ops", "function", "FnOnce"]);
+
+    /// The auto traits, and the marker types that affect them.
+    pub static ref SEND: Identity = Identity::new(&*CORE_CRATE, &["marker", "Send"]);
+    pub static ref SYNC: Identity = Identity::new(&*CORE_CRATE, &["marker", "Sync"]);
+    pub static ref UNPIN: Identity = Identity::new(&*CORE_CRATE, &["marker", "Unpin"]);
+    pub static ref PHANTOM_DATA: Identity = Identity::new(&*CORE_CRATE, &["marker", "PhantomData"]);
+    pub static ref PHANTOM_PINNED: Identity = Identity::new(&*CORE_CRATE, &["marker", "PhantomPinned"]);
+    pub static ref SIZED: Identity = Identity::new(&*CORE_CRATE, &["marker", "Sized"]);
+
+    /// The smart pointers that can be used as method receivers, `self: Rc<Self>`.
+    pub static ref BOX: Identity = Identity::new(&*ALLOC_CRATE, &["boxed", "Box"]);
+    pub static ref RC: Identity = Identity::new(&*ALLOC_CRATE, &["rc", "Rc"]);
+    pub static ref ARC: Identity = Identity::new(&*ALLOC_CRATE, &["sync", "Arc"]);
+    pub static ref PIN: Identity = Identity::new(&*CORE_CRATE, &["pin", "Pin"]);
+
     pub static ref BUILTIN_TYPES: Map<Ident, Identity> = {
         let mut result = Map::default();
         result.insert("char".into(), CHAR.clone());
@@ -53,7 +79,19 @@ lazy_static! {
     };
 
     pub static ref ROOT_SCOPE_NAME: Ident = "{root}".into();
-    pub static ref IMPL_SCOPE_NAME: Ident = "{impl}".into();
+    /// Impl blocks are named `{impl_0}`, `{impl_1}`, ..., in order within their scope, so that
+    /// each gets its own generic parameters. This is the part before the index.
+    pub static ref IMPL_SCOPE_NAME: Ident = "{impl_".into();
+}
+
+/// The name of the `index`th impl block in a scope.
+pub fn impl_scope_name(index: usize) -> Ident {
+    format!("{}{}}}", &*IMPL_SCOPE_NAME, index).into()
+}
+
+/// If a path element is the name of an impl block.
+pub fn is_impl_scope_name(name: &Ident) -> bool {
+    name.starts_with(&IMPL_SCOPE_NAME[..])
 }
 
 /// Get the builtins table for a namespace, if one exists.
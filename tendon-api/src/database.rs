@@ -15,13 +15,17 @@
 //! Invariant: If you look up a root scope,
 //! you must have inserted and completed operating on that scope.
 
-use crate::attributes::{HasMetadata, Metadata};
+use crate::attributes::{HasMetadata, Metadata, Visibility};
+use crate::builtins::is_impl_scope_name;
 use crate::crates::CrateData;
-use crate::identities::{CrateId, Identity, TEST_CRATE_A, TEST_CRATE_B, TEST_CRATE_C};
-use crate::items::{MacroItem, SymbolItem, TypeItem};
-use crate::paths::Ident;
+use crate::identities::{
+    CrateId, Identity, PathType, Substitutions, TypeId, TEST_CRATE_A, TEST_CRATE_B, TEST_CRATE_C,
+};
+use crate::items::{ImplItem, MacroItem, SymbolItem, TypeItem};
+use crate::paths::{Ident, UnresolvedPath};
+use crate::references::{impl_references, symbol_item_references, type_item_references, Surface};
 use crate::scopes::{Binding, NamespaceId, Scope};
-use crate::Map;
+use crate::{Map, Set};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use tracing::error;
@@ -94,6 +98,11 @@ impl Db {
             .expect("invariant violated: crate has not been lowered")
     }
 
+    /// All the crates that have been lowered so far, in no particular order.
+    pub fn crates(&self) -> impl Iterator<Item = &Crate> {
+        self.crates.values().filter_map(|crate_| crate_.get())
+    }
+
     /// Insert a parsed crate.
     /// Panics if the crate has already been added.
     ///
@@ -108,6 +117,219 @@ impl Db {
             panic!("crate already set: {:?}", crate_.id);
         }
     }
+
+    /// Look up an item anywhere in the database.
+    /// Returns `None` if the item doesn't exist, or its crate hasn't been lowered yet.
+    pub fn get_item<I: NamespaceLookup>(&self, identity: &Identity) -> Option<&I> {
+        self.crates.get(&identity.crate_)?.get()?.get(identity)
+    }
+
+    /// The path users of an item's crate would import it by; see `Crate::public_paths`.
+    /// Returns `None` if the item isn't public, or its crate hasn't been lowered yet.
+    pub fn public_path(&self, identity: &Identity) -> Option<&Identity> {
+        self.crates
+            .get(&identity.crate_)?
+            .get()?
+            .public_paths
+            .get(identity)
+    }
+
+    /// Drop everything that isn't needed to describe the public API of `target`, so the `Db` is
+    /// small enough to serialize.
+    ///
+    /// Starting from everything publicly bound in `target`, re-exports of other crates' items
+    /// included, we keep every item they refer to, including private fields (so layouts and auto
+    /// traits can still be worked out), and every impl on a kept type, with everything those refer
+    /// to in turn. Blanket impls are kept if their traits are. Modules containing kept items are
+    /// kept, minus bindings to dropped items.
+    ///
+    /// Panics if `target` hasn't been lowered.
+    pub fn prune_to(&mut self, target: &CrateId) {
+        assert!(
+            self.crates
+                .get(target)
+                .and_then(|crate_| crate_.get())
+                .is_some(),
+            "can't prune to {:?}, it hasn't been lowered",
+            target
+        );
+        let kept = self.referenced_from(target);
+        let lowered: Set<CrateId> = self.crates().map(|crate_| crate_.id.clone()).collect();
+        let keep_binding = |binding: &Binding| {
+            kept.contains(&binding.identity) || !lowered.contains(&binding.identity.crate_)
+        };
+
+        for cell in self.crates.values_mut() {
+            let crate_ = match cell.get_mut() {
+                Some(crate_) => crate_,
+                None => continue,
+            };
+            let id = crate_.id.clone();
+            let keep = |path: &Vec<Ident>| kept.contains(&Identity::new(&id, path));
+            crate_.types.0.retain(|path, _| keep(path));
+            crate_.symbols.0.retain(|path, _| keep(path));
+            crate_.macros.0.retain(|path, _| keep(path));
+            crate_.scopes.0.retain(|path, _| keep(path));
+            for scope in crate_.scopes.0.values_mut() {
+                scope.retain_bindings(keep_binding);
+            }
+            crate_.prelude.retain_bindings(keep_binding);
+            crate_
+                .impls
+                .retain(|impl_| impl_is_kept(impl_, &kept, &lowered));
+            crate_.public_paths.retain(|item, _| kept.contains(item));
+            crate_
+                .resolution_report
+                .unresolved
+                .retain(|reference| kept.contains(&reference.item));
+        }
+    }
+
+    /// Everything transitively referenced from `target`'s public API, and the modules
+    /// containing it.
+    fn referenced_from(&self, target: &CrateId) -> Set<Identity> {
+        let mut kept = Set::default();
+        let mut queue = self.publicly_bound(target);
+        let lowered: Set<CrateId> = self.crates().map(|crate_| crate_.id.clone()).collect();
+        let impls = self
+            .crates()
+            .flat_map(|crate_| crate_.impls.iter())
+            .collect::<Vec<_>>();
+        let mut impl_done = vec![false; impls.len()];
+
+        loop {
+            while let Some(item) = queue.pop() {
+                if !kept.insert(item.clone()) {
+                    continue;
+                }
+                let mut references = Set::default();
+                if let Some(type_) = self.get_item::<TypeItem>(&item) {
+                    type_item_references(type_, Surface::All, &mut references);
+                }
+                if let Some(symbol) = self.get_item::<SymbolItem>(&item) {
+                    symbol_item_references(symbol, &mut references);
+                }
+                queue.extend(references.into_iter().filter(|item| !kept.contains(item)));
+            }
+
+            let mut progress = false;
+            for (impl_, done) in impls.iter().zip(impl_done.iter_mut()) {
+                if *done || !impl_is_kept(impl_, &kept, &lowered) {
+                    continue;
+                }
+                *done = true;
+                progress = true;
+                let mut references = Set::default();
+                impl_references(impl_, Surface::All, &mut references);
+                queue.extend(references.into_iter().filter(|item| !kept.contains(item)));
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        let modules = kept
+            .iter()
+            .flat_map(|item| {
+                let mut parents = vec![];
+                let mut item = item.clone();
+                while let Some(parent) = item.parent() {
+                    parents.push(parent.clone());
+                    item = parent;
+                }
+                parents
+            })
+            .collect::<Vec<_>>();
+        kept.extend(modules);
+        kept
+    }
+
+    /// The targets of every public binding reachable from `target`'s root, following public
+    /// bindings to scopes. Unlike `Crate::public_paths`, this includes re-exports of other crates'
+    /// items, `pub use dep::Thing`, and looks through re-exported modules of other crates.
+    fn publicly_bound(&self, target: &CrateId) -> Vec<Identity> {
+        let root = Identity::root(target);
+        let mut result = vec![root.clone()];
+        let mut seen_scopes = Set::default();
+        seen_scopes.insert(root.clone());
+        let mut scopes = vec![root];
+
+        while let Some(scope_id) = scopes.pop() {
+            let scope = match self.get_item::<Scope>(&scope_id) {
+                Some(scope) => scope,
+                None => continue,
+            };
+            for &namespace_id in &NamespaceId::values() {
+                for (_, binding) in scope.iter_by(namespace_id) {
+                    if binding.visibility != Visibility::Pub {
+                        continue;
+                    }
+                    result.push(binding.identity.clone());
+                    if namespace_id == NamespaceId::Scope
+                        && seen_scopes.insert(binding.identity.clone())
+                    {
+                        scopes.push(binding.identity.clone());
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Expand all type aliases in a type, substituting the aliases' generic arguments.
+    /// Aliases nested in arguments (`Vec<io::Result<T>>`) are expanded too.
+    /// Aliases in crates that haven't been lowered yet are left alone.
+    pub fn expand_aliases(&self, type_: &TypeId) -> TypeId {
+        self.expand_aliases_(type_, 0)
+    }
+
+    fn expand_aliases_(&self, type_: &TypeId, depth: usize) -> TypeId {
+        if depth > MAX_ALIAS_DEPTH {
+            error!("alias expansion too deep, cycle? {:?}", type_);
+            return type_.clone();
+        }
+        if let TypeId::Path(path) = type_ {
+            if let Some(expanded) = self.expand_alias(path) {
+                return self.expand_aliases_(&expanded, depth + 1);
+            }
+        }
+        // only expansions count towards the limit, deeply nested types are fine
+        type_.map_children(
+            &mut |type_| self.expand_aliases_(type_, depth),
+            &mut |lifetime| lifetime.clone(),
+            &mut |const_| const_.clone(),
+        )
+    }
+
+    /// Expand a single alias, if `path` refers to one.
+    fn expand_alias(&self, path: &PathType) -> Option<TypeId> {
+        let alias = match self.get_item::<TypeItem>(&path.path)? {
+            TypeItem::Alias(alias) => alias,
+            _ => return None,
+        };
+        let substitutions = Substitutions::for_generics(&path.path, &alias.generics, &path.params);
+        Some(alias.target.substitute(&substitutions))
+    }
+}
+
+/// Aliases nested deeper than this are assumed to be cyclic.
+const MAX_ALIAS_DEPTH: usize = 64;
+
+/// If an impl survives pruning: its self type is kept, or, for impls on things that aren't items
+/// in lowered crates (`impl<T> Trait for T`, `impl Trait for &u8`), its trait is.
+fn impl_is_kept(impl_: &ImplItem, kept: &Set<Identity>, lowered: &Set<CrateId>) -> bool {
+    if let TypeId::Path(PathType { path, .. }) = &impl_.self_type {
+        if kept.contains(path) {
+            return true;
+        }
+        if lowered.contains(&path.crate_) && !path.path.iter().any(is_impl_scope_name) {
+            return false;
+        }
+    }
+    match &impl_.trait_ {
+        Some(trait_) => kept.contains(&trait_.id),
+        None => false,
+    }
 }
 
 /// A parsed and resolved crate.
@@ -146,6 +368,17 @@ pub struct Crate {
 
     /// All the scopes available.
     pub scopes: Namespace<Scope>,
+
+    /// Impl blocks in the crate, in no particular order.
+    pub impls: Vec<ImplItem>,
+
+    /// Paths in this crate's items that couldn't be resolved.
+    pub resolution_report: ResolutionReport,
+
+    /// The shortest path through public bindings from the crate root to each item defined in
+    /// this crate, e.g. `rand_chacha::chacha::ChaChaRng` -> `rand_chacha::ChaChaRng`.
+    /// Items that can't be named from outside the crate have no entry.
+    pub public_paths: Map<Identity, Identity>,
 }
 
 impl Crate {
@@ -159,6 +392,9 @@ impl Crate {
             symbols: Namespace::new(),
             macros: Namespace::new(),
             scopes: Namespace::new(),
+            impls: Vec::new(),
+            resolution_report: ResolutionReport::default(),
+            public_paths: Map::default(),
         }
     }
 
@@ -205,6 +441,26 @@ impl Crate {
     }
 }
 
+/// Paths that couldn't be resolved while resolving a crate's items.
+///
+/// The items that contain them are kept; the offending paths are left as
+/// `Identity::unresolved` placeholders.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ResolutionReport {
+    pub unresolved: Vec<UnresolvedReference>,
+}
+
+/// A path that couldn't be resolved.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UnresolvedReference {
+    /// The item containing the path.
+    pub item: Identity,
+    /// The namespace the path was looked up in.
+    pub namespace: NamespaceId,
+    /// The path, as written.
+    pub path: UnresolvedPath,
+}
+
 /// A namespace within a crate.
 ///
 /// Invariant: if `namespace[I] == item`, `I[-1] == item.metadata().name`, UNLESS
@@ -213,7 +469,7 @@ impl Crate {
 pub struct Namespace<I>(pub Map<Vec<Ident>, I>);
 
 impl<I: NamespaceLookup> Namespace<I> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Namespace(Map::default())
     }
 }
@@ -269,3 +525,438 @@ impl NamespaceLookup for Scope {
         &mut crate_.scopes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::{ConstArg, ConstExpr};
+    use crate::generics::Generics;
+    use crate::identities::LifetimeId;
+    use crate::items::{AliasItem, ConstParamItem, LifetimeParamItem, TypeParamItem};
+    use crate::tokens::Tokens;
+
+    fn path(id: Identity, params: crate::identities::GenericParams) -> TypeId {
+        TypeId::Path(PathType { path: id, params })
+    }
+
+    #[test]
+    fn alias_expansion() {
+        let db = Db::fake_db();
+        let mut crate_ = Crate::new(TEST_CRATE_A.clone());
+        let result = Identity::new(&*TEST_CRATE_A, &["Result"]);
+        let error = Identity::new(&*TEST_CRATE_A, &["Error"]);
+        let io_result = Identity::new(&*TEST_CRATE_A, &["io", "Result"]);
+        let t = Identity::new(&*TEST_CRATE_A, &["io", "Result", "T"]);
+
+        // type Result<T> = crate::Result<T, Error>;
+        let mut target = crate::identities::GenericParams::default();
+        target
+            .type_bindings
+            .insert(Ident::positional(0), path(t.clone(), Default::default()));
+        target.type_bindings.insert(
+            Ident::positional(1),
+            path(error.clone(), Default::default()),
+        );
+        let mut generics = Generics::default();
+        generics.types.push(TypeParamItem {
+            metadata: Metadata::fake("T"),
+            trait_bounds: vec![],
+            lifetime_bounds: vec![],
+            default: None,
+        });
+        crate_.types.0.insert(
+            io_result.path.clone(),
+            TypeItem::Alias(AliasItem {
+                metadata: Metadata::fake("Result"),
+                generics,
+                target: path(result.clone(), target),
+            }),
+        );
+        // type Ref<'a> = &'a Result<u8>;
+        let ref_ = Identity::new(&*TEST_CRATE_A, &["Ref"]);
+        let a = Identity::new(&*TEST_CRATE_A, &["Ref", "'a"]);
+        let mut u8_ = crate::identities::GenericParams::default();
+        u8_.type_bindings.insert(
+            Ident::positional(0),
+            path(crate::builtins::U8.clone(), Default::default()),
+        );
+        let mut generics = Generics::default();
+        generics.lifetimes.push(LifetimeParamItem {
+            metadata: Metadata::fake(Ident::lifetime("a")),
+            bounds: vec![],
+        });
+        crate_.types.0.insert(
+            ref_.path.clone(),
+            TypeItem::Alias(AliasItem {
+                metadata: Metadata::fake("Ref"),
+                generics,
+                target: TypeId::Reference(crate::identities::ReferenceType {
+                    type_: Box::new(path(io_result.clone(), u8_)),
+                    mut_: false,
+                    lifetime: Some(LifetimeId::new(a.clone())),
+                }),
+            }),
+        );
+        db.insert_crate(crate_);
+
+        let mut static_ = crate::identities::GenericParams::default();
+        static_.lifetimes.insert(
+            Ident::positional(0),
+            LifetimeId::new(crate::builtins::STATIC.clone()),
+        );
+        // plain nesting doesn't count as alias expansion
+        let mut nested = path(ref_.clone(), static_.clone());
+        for _ in 0..MAX_ALIAS_DEPTH * 2 {
+            nested = TypeId::Reference(crate::identities::ReferenceType {
+                type_: Box::new(nested),
+                mut_: false,
+                lifetime: None,
+            });
+        }
+        let mut innermost = db.expand_aliases(&nested);
+        while let TypeId::Reference(reference) = innermost {
+            innermost = *reference.type_;
+        }
+        match innermost {
+            TypeId::Path(PathType { path, .. }) => assert_eq!(path, result),
+            other => panic!("expected path, got {:?}", other),
+        }
+
+        let expanded = db.expand_aliases(&path(ref_, static_));
+        let (inner, lifetime) = match expanded {
+            TypeId::Reference(reference) => (*reference.type_, reference.lifetime.unwrap()),
+            other => panic!("expected reference, got {:?}", other),
+        };
+        assert_eq!(lifetime.id(), &*crate::builtins::STATIC);
+        let params = match inner {
+            TypeId::Path(PathType { path, params }) => {
+                assert_eq!(path, result);
+                params
+            }
+            other => panic!("expected path, got {:?}", other),
+        };
+        let arg = |i: usize| match &params.type_bindings[&Ident::positional(i)] {
+            TypeId::Path(PathType { path, .. }) => path.clone(),
+            other => panic!("expected path, got {:?}", other),
+        };
+        assert_eq!(arg(0), *crate::builtins::U8);
+        assert_eq!(arg(1), error);
+    }
+    #[test]
+    fn const_alias_expansion() {
+        let db = Db::fake_db();
+        let mut crate_ = Crate::new(TEST_CRATE_A.clone());
+        let buf = Identity::new(&*TEST_CRATE_A, &["Buf"]);
+        let n = Identity::new(&*TEST_CRATE_A, &["Buf", "N"]);
+
+        // type Buf<const N: usize> = [u8; N];
+        let mut generics = Generics::default();
+        generics.consts.push(ConstParamItem {
+            metadata: Metadata::fake("N"),
+            type_: path(crate::builtins::USIZE.clone(), Default::default()),
+            default: None,
+        });
+        crate_.types.0.insert(
+            buf.path.clone(),
+            TypeItem::Alias(AliasItem {
+                metadata: Metadata::fake("Buf"),
+                generics,
+                target: TypeId::Array(crate::identities::ArrayType {
+                    type_: Box::new(path(crate::builtins::U8.clone(), Default::default())),
+                    len: ConstArg::Path(n),
+                }),
+            }),
+        );
+
+        // type Wrapper<const N: usize> = Foo<Buf<N>, dyn Tr<N>>;
+        let wrapper = Identity::new(&*TEST_CRATE_A, &["Wrapper"]);
+        let wrapper_n = ConstArg::Path(Identity::new(&*TEST_CRATE_A, &["Wrapper", "N"]));
+        let mut generics = Generics::default();
+        generics.consts.push(ConstParamItem {
+            metadata: Metadata::fake("N"),
+            type_: path(crate::builtins::USIZE.clone(), Default::default()),
+            default: None,
+        });
+        let mut buf_args = crate::identities::GenericParams::default();
+        buf_args
+            .consts
+            .insert(Ident::positional(0), wrapper_n.clone());
+        let tr = crate::identities::TraitId {
+            id: Identity::new(&*TEST_CRATE_A, &["Tr"]),
+            params: buf_args.clone(),
+            is_maybe: false,
+            for_lifetimes: vec![],
+        };
+        let mut foo_args = crate::identities::GenericParams::default();
+        foo_args
+            .type_bindings
+            .insert(Ident::positional(0), path(buf.clone(), buf_args));
+        foo_args.type_bindings.insert(
+            Ident::positional(1),
+            TypeId::TraitObject(crate::identities::TraitObjectType {
+                trait_bounds: vec![tr],
+            }),
+        );
+        let foo = Identity::new(&*TEST_CRATE_A, &["Foo"]);
+        crate_.types.0.insert(
+            wrapper.path.clone(),
+            TypeItem::Alias(AliasItem {
+                metadata: Metadata::fake("Wrapper"),
+                generics,
+                target: path(foo, foo_args),
+            }),
+        );
+        db.insert_crate(crate_);
+
+        let four = ConstArg::Expr(ConstExpr(Tokens::from(4)));
+        let mut args = crate::identities::GenericParams::default();
+        args.consts.insert(Ident::positional(0), four.clone());
+        match db.expand_aliases(&path(buf, args.clone())) {
+            TypeId::Array(array) => assert_eq!(array.len, four),
+            other => panic!("expected array, got {:?}", other),
+        }
+
+        // nested arguments are substituted too, including trait arguments
+        let foo_args = match db.expand_aliases(&path(wrapper, args)) {
+            TypeId::Path(PathType { params, .. }) => params,
+            other => panic!("expected path, got {:?}", other),
+        };
+        match &foo_args.type_bindings[&Ident::positional(0)] {
+            TypeId::Array(array) => assert_eq!(array.len, four),
+            other => panic!("expected array, got {:?}", other),
+        }
+        match &foo_args.type_bindings[&Ident::positional(1)] {
+            TypeId::TraitObject(object) => assert_eq!(
+                object.trait_bounds[0].params.consts[&Ident::positional(0)],
+                four
+            ),
+            other => panic!("expected trait object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pruning() {
+        use crate::attributes::{TypeMetadata, Visibility};
+        use crate::identities::{GenericParams, TraitId};
+        use crate::items::{StructField, StructItem, StructKind, TraitItem};
+        use crate::scopes::Priority;
+
+        let a = |path: &[&str]| Identity::new(&*TEST_CRATE_A, path);
+        let b = |path: &[&str]| Identity::new(&*TEST_CRATE_B, path);
+        let struct_ = |name: &str, fields: Vec<TypeId>| {
+            TypeItem::Struct(StructItem {
+                metadata: Metadata::fake(name),
+                type_metadata: TypeMetadata::default(),
+                fields: fields
+                    .into_iter()
+                    .map(|type_| StructField {
+                        metadata: Metadata {
+                            visibility: Visibility::InScope(a(&[])),
+                            ..Metadata::fake(Ident::positional(0))
+                        },
+                        type_,
+                    })
+                    .collect(),
+                kind: StructKind::Tuple,
+                generics: Generics::default(),
+            })
+        };
+        let trait_ = |name: &str| {
+            TypeItem::Trait(TraitItem {
+                metadata: Metadata::fake(name),
+                generics: Generics::default(),
+                self_param: TypeParamItem {
+                    metadata: Metadata::fake("Self"),
+                    trait_bounds: vec![],
+                    lifetime_bounds: vec![],
+                    default: None,
+                },
+                supertraits: vec![],
+                lifetime_bounds: vec![],
+                is_unsafe: false,
+                is_auto: false,
+                methods: vec![],
+                types: vec![],
+                consts: vec![],
+            })
+        };
+        let impl_ = |scope: Identity, trait_: Identity, self_type: Identity| ImplItem {
+            metadata: Metadata::fake("{impl_0}"),
+            scope,
+            generics: Generics::default(),
+            trait_: Some(TraitId {
+                id: trait_,
+                params: GenericParams::empty(),
+                is_maybe: false,
+                for_lifetimes: vec![],
+            }),
+            self_type: path(self_type, Default::default()),
+            is_unsafe: false,
+            is_negative: false,
+            methods: vec![],
+            types: vec![],
+            consts: vec![],
+        };
+        let root_scope = |crate_: &mut Crate, bindings: &[(&str, Visibility)]| {
+            let mut scope = Scope::new(Metadata::fake("{root}"), true);
+            for (name, visibility) in bindings {
+                scope
+                    .insert::<TypeItem>(
+                        (*name).into(),
+                        Identity::new(&crate_.id, &[*name]),
+                        visibility.clone(),
+                        Priority::Explicit,
+                    )
+                    .unwrap();
+            }
+            crate_.scopes.0.insert(vec![], scope);
+        };
+
+        // the dependency
+        let mut crate_b = Crate::new(TEST_CRATE_B.clone());
+        crate_b
+            .types
+            .0
+            .insert(vec!["Used".into()], struct_("Used", vec![]));
+        crate_b
+            .types
+            .0
+            .insert(vec!["Unused".into()], struct_("Unused", vec![]));
+        crate_b
+            .types
+            .0
+            .insert(vec!["Shown".into()], trait_("Shown"));
+        crate_b
+            .types
+            .0
+            .insert(vec!["Hidden".into()], trait_("Hidden"));
+        // impl<T> Shown for T {}
+        crate_b
+            .impls
+            .push(impl_(b(&[]), b(&["Shown"]), b(&["{impl_0}", "T"])));
+        // impl Hidden for Unused {}
+        crate_b
+            .impls
+            .push(impl_(b(&[]), b(&["Hidden"]), b(&["Unused"])));
+        root_scope(
+            &mut crate_b,
+            &[
+                ("Used", Visibility::Pub),
+                ("Unused", Visibility::Pub),
+                ("Shown", Visibility::Pub),
+                ("Hidden", Visibility::Pub),
+            ],
+        );
+
+        // the target: `pub struct Api(Used); struct Private;`
+        let mut crate_a = Crate::new(TEST_CRATE_A.clone());
+        crate_a.types.0.insert(
+            vec!["Api".into()],
+            struct_("Api", vec![path(b(&["Used"]), Default::default())]),
+        );
+        crate_a
+            .types
+            .0
+            .insert(vec!["Private".into()], struct_("Private", vec![]));
+        // impl Hidden for Private {}
+        crate_a
+            .impls
+            .push(impl_(a(&[]), b(&["Hidden"]), a(&["Private"])));
+        // impl Shown for Api {}
+        crate_a
+            .impls
+            .push(impl_(a(&[]), b(&["Shown"]), a(&["Api"])));
+        root_scope(
+            &mut crate_a,
+            &[
+                ("Api", Visibility::Pub),
+                ("Private", Visibility::InScope(a(&[]))),
+            ],
+        );
+        crate_a.public_paths.insert(a(&[]), a(&[]));
+        crate_a.public_paths.insert(a(&["Api"]), a(&["Api"]));
+
+        // re-exports from the dependency: `pub use b::Reexported; pub use b::m;`
+        crate_b
+            .types
+            .0
+            .insert(vec!["Reexported".into()], struct_("Reexported", vec![]));
+        crate_b
+            .types
+            .0
+            .insert(vec!["m".into(), "Deep".into()], struct_("Deep", vec![]));
+        crate_b.types.0.insert(
+            vec!["m".into(), "Shallow".into()],
+            struct_("Shallow", vec![]),
+        );
+        let mut m = Scope::new(Metadata::fake("m"), true);
+        m.insert::<TypeItem>(
+            "Deep".into(),
+            b(&["m", "Deep"]),
+            Visibility::Pub,
+            Priority::Explicit,
+        )
+        .unwrap();
+        m.insert::<TypeItem>(
+            "Shallow".into(),
+            b(&["m", "Shallow"]),
+            Visibility::InScope(b(&["m"])),
+            Priority::Explicit,
+        )
+        .unwrap();
+        crate_b.scopes.0.insert(vec!["m".into()], m);
+        {
+            let root = crate_a.scopes.0.get_mut(&vec![]).unwrap();
+            root.insert::<TypeItem>(
+                "Reexported".into(),
+                b(&["Reexported"]),
+                Visibility::Pub,
+                Priority::Explicit,
+            )
+            .unwrap();
+            root.insert::<Scope>("m".into(), b(&["m"]), Visibility::Pub, Priority::Explicit)
+                .unwrap();
+        }
+
+        let mut db = Db::fake_db();
+        db.insert_crate(crate_a);
+        db.insert_crate(crate_b);
+        db.prune_to(&*TEST_CRATE_A);
+
+        let has = |id: Identity| db.get_item::<TypeItem>(&id).is_some();
+        assert!(has(a(&["Api"])));
+        assert!(!has(a(&["Private"])));
+        assert!(has(b(&["Used"])));
+        assert!(!has(b(&["Unused"])));
+        assert!(has(b(&["Shown"])));
+        assert!(!has(b(&["Hidden"])));
+        assert!(has(b(&["Reexported"])));
+        assert!(has(b(&["m", "Deep"])));
+        assert!(!has(b(&["m", "Shallow"])));
+        assert!(db.get_item::<Scope>(&b(&["m"])).is_some());
+
+        let crate_a = db.get_crate(&*TEST_CRATE_A);
+        let crate_b = db.get_crate(&*TEST_CRATE_B);
+        assert_eq!(crate_a.impls.len(), 1);
+        match &crate_a.impls[0].self_type {
+            TypeId::Path(PathType { path, .. }) => assert_eq!(path, &a(&["Api"])),
+            other => panic!("expected path, got {:?}", other),
+        }
+        assert_eq!(crate_b.impls.len(), 1);
+        let binding = |crate_: &Crate, name: &str| {
+            crate_
+                .get_binding::<TypeItem>(&Identity::root(&crate_.id), &name.into())
+                .is_some()
+        };
+        assert!(binding(crate_a, "Api"));
+        assert!(!binding(crate_a, "Private"));
+        assert!(binding(crate_b, "Used"));
+        assert!(binding(crate_b, "Shown"));
+        assert!(!binding(crate_b, "Unused"));
+        assert!(!binding(crate_b, "Hidden"));
+        assert!(binding(crate_a, "Reexported"));
+        assert!(crate_a
+            .get_binding::<Scope>(&Identity::root(&crate_a.id), &"m".into())
+            .is_some());
+    }
+}
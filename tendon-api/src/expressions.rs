@@ -1,5 +1,7 @@
 //! Expressions. This module is fairly emaciated since we mostly don't handle these.
 
+use crate::identities::Identity;
+use crate::paths::Ident;
 use crate::tokens::Tokens;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -14,6 +16,26 @@ impl fmt::Debug for ConstExpr {
     }
 }
 
+/// A const generic argument or array length, `[T; N]`, `Thing<{ N + 1 }>`.
+///
+/// Bare paths are stored as identities so they can be resolved and substituted like types;
+/// anything more complicated is left as an expression.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConstArg {
+    /// A reference to a const or a const parameter, `N`.
+    Path(Identity),
+    /// Any other expression, `3`, `{ N + 1 }`.
+    Expr(ConstExpr),
+}
+impl fmt::Debug for ConstArg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstArg::Path(path) => fmt::Debug::fmt(path, f),
+            ConstArg::Expr(expr) => fmt::Debug::fmt(expr, f),
+        }
+    }
+}
+
 /// A non-constant expression.
 /// Represented as uninterpreted tokens.
 #[derive(Clone, Serialize, Deserialize)]
@@ -23,3 +45,16 @@ impl fmt::Debug for Expr {
         fmt::Debug::fmt(&self.0, f)
     }
 }
+
+/// The value of a constant expression, for the simple cases we can evaluate:
+/// literals, arithmetic, casts, and references to other constants.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConstValue {
+    /// An integer, along with its type (`i32`, `usize`, ...).
+    Int(i128, Ident),
+    /// A float, along with its type (`f32` or `f64`).
+    Float(f64, Ident),
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
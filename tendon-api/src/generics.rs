@@ -1 +1,80 @@
+//! Generics as written at declaration sites, `struct S<'a, T: Clone, const N: usize> where ...`.
+//!
+//! These are distinct from the generic *arguments* at a use site, `S<'static, u8, 3>`, which are
+//! stored in `identities::GenericParams`.
 
+use crate::identities::{LifetimeId, TraitId, TypeId};
+use crate::items::{ConstParamItem, LifetimeParamItem, TypeParamItem};
+use crate::paths::Ident;
+use serde::{Deserialize, Serialize};
+
+/// The generic parameters and predicates declared on an item.
+///
+/// Used for structs, enums, unions, traits, impls, functions and aliases.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Generics {
+    /// Lifetime parameters, `'a: 'b`. Inline bounds are stored on the parameter.
+    pub lifetimes: Vec<LifetimeParamItem>,
+    /// Type parameters, `T: Clone = u8`. Inline bounds are stored on the parameter.
+    pub types: Vec<TypeParamItem>,
+    /// Const parameters, `const N: usize = 3`.
+    pub consts: Vec<ConstParamItem>,
+    /// The predicates in the `where` clause.
+    pub where_predicates: Vec<WherePredicate>,
+}
+impl Generics {
+    /// If there are no parameters or predicates.
+    pub fn is_empty(&self) -> bool {
+        self.lifetimes.is_empty()
+            && self.types.is_empty()
+            && self.consts.is_empty()
+            && self.where_predicates.is_empty()
+    }
+
+    /// Look up a type parameter by name.
+    pub fn type_param(&self, name: &Ident) -> Option<&TypeParamItem> {
+        self.types.iter().find(|param| &param.metadata.name == name)
+    }
+
+    /// Look up a lifetime parameter by name (including the apostrophe).
+    pub fn lifetime_param(&self, name: &Ident) -> Option<&LifetimeParamItem> {
+        self.lifetimes
+            .iter()
+            .find(|param| &param.metadata.name == name)
+    }
+
+    /// Look up a const parameter by name.
+    pub fn const_param(&self, name: &Ident) -> Option<&ConstParamItem> {
+        self.consts
+            .iter()
+            .find(|param| &param.metadata.name == name)
+    }
+}
+
+/// A predicate in a `where` clause.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum WherePredicate {
+    /// `Vec<T>: Debug + 'a`
+    Type(TypePredicate),
+    /// `'a: 'b + 'c`
+    Lifetime(LifetimePredicate),
+}
+
+/// A predicate on a type, `for<'a> &'a T: Trait + 'b`.
+/// The bounded type can be anything, not just a parameter.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TypePredicate {
+    /// Lifetimes introduced by a higher-ranked binder on the whole predicate.
+    pub for_lifetimes: Vec<LifetimeId>,
+    /// The bounded type.
+    pub type_: TypeId,
+    pub trait_bounds: Vec<TraitId>,
+    pub lifetime_bounds: Vec<LifetimeId>,
+}
+
+/// An outlives predicate, `'a: 'b + 'c`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LifetimePredicate {
+    pub lifetime: LifetimeId,
+    pub bounds: Vec<LifetimeId>,
+}
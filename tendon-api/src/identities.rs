@@ -1,4 +1,5 @@
-use crate::paths::Ident;
+use crate::builtins::{FN, FN_MUT, FN_ONCE, ROOT_SCOPE_NAME, UNRESOLVED_CRATE};
+use crate::paths::{Ident, UnresolvedPath};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::fmt;
@@ -45,6 +46,37 @@ impl Identity {
         }
     }
 
+    /// Create a placeholder for a path that hasn't been resolved yet.
+    ///
+    /// Lowering can't resolve anything, so it stores paths in the fake `{unresolved}` crate;
+    /// they're replaced with real identities during resolution. Rooted paths (`::a::b`) start
+    /// with a `{root}` component.
+    pub fn unresolved(path: &UnresolvedPath) -> Self {
+        let mut result = Identity::root(&*UNRESOLVED_CRATE);
+        if path.rooted {
+            result.join(ROOT_SCOPE_NAME.clone());
+        }
+        result.join_seq(&path.path);
+        result
+    }
+
+    /// If this is a placeholder created by `Identity::unresolved`, get the original path back.
+    pub fn as_unresolved(&self) -> Option<UnresolvedPath> {
+        if self.crate_ != *UNRESOLVED_CRATE {
+            return None;
+        }
+        if self.path.first() == Some(&*ROOT_SCOPE_NAME) {
+            Some(UnresolvedPath::new(true, &self.path[1..]))
+        } else {
+            Some(UnresolvedPath::new(false, &self.path))
+        }
+    }
+
+    /// Whether this is a placeholder created by `Identity::unresolved`.
+    pub fn is_unresolved(&self) -> bool {
+        self.crate_ == *UNRESOLVED_CRATE
+    }
+
     /// Add another component to the path.
     pub fn join(&mut self, elem: impl Into<Ident>) -> &mut Self {
         let elem = elem.into();
@@ -122,7 +154,7 @@ impl LifetimeId {
 }
 
 /// Uniquely identifies a symbol, with generic arguments.
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct SymbolId {
     pub id: Identity,
     pub params: GenericParams,
@@ -130,7 +162,7 @@ pub struct SymbolId {
 debug!(SymbolId, "{:?}{:?}", id, params);
 
 /// Uniquely identifies a trait, with generic arguments.
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct TraitId {
     /// The path to the trait.
     pub id: Identity,
@@ -138,12 +170,65 @@ pub struct TraitId {
     pub params: GenericParams,
     /// If the trait is prefixed with `?`
     pub is_maybe: bool,
+    /// Lifetimes introduced by a higher-ranked binder, `for<'a> Fn(&'a u8)`.
+    pub for_lifetimes: Vec<LifetimeId>,
 }
+impl TraitId {
+    /// Whether this is `Fn`, `FnMut` or `FnOnce`.
+    /// Unresolved paths are judged by their last segment, since they haven't been resolved yet.
+    pub fn is_fn_trait(&self) -> bool {
+        if self.id.is_unresolved() {
+            match self.id.path.last() {
+                Some(name) => ["Fn", "FnMut", "FnOnce"].contains(&&name[..]),
+                None => false,
+            }
+        } else {
+            self.id == *FN || self.id == *FN_MUT || self.id == *FN_ONCE
+        }
+    }
+
+    /// If this is one of the `Fn` traits and its arguments have the shape of `Fn` sugar, the
+    /// inputs and output.
+    pub fn as_fn_sugar(&self) -> Option<(&[TypeId], &TypeId)> {
+        if self.is_fn_trait() {
+            self.params.as_fn_sugar()
+        } else {
+            None
+        }
+    }
+}
+
+/// Format a higher-ranked binder, `for<'a, 'b> `, if there are any bound lifetimes.
+pub(crate) fn fmt_binder(f: &mut fmt::Formatter, lifetimes: &[LifetimeId]) -> fmt::Result {
+    if lifetimes.is_empty() {
+        return Ok(());
+    }
+    write!(f, "for<")?;
+    for (i, lifetime) in lifetimes.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{:?}", lifetime)?;
+    }
+    write!(f, "> ")
+}
+
 impl fmt::Debug for TraitId {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt_binder(f, &self.for_lifetimes)?;
         if self.is_maybe {
             write!(f, "?")?;
         }
+        if let Some((inputs, output)) = self.as_fn_sugar() {
+            write!(f, "{:?}(", self.id)?;
+            for (i, input) in inputs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{:?}", input)?;
+            }
+            return write!(f, ") -> {:?}", output);
+        }
         write!(f, "{:?}{:?}", self.id, self.params)
     }
 }
@@ -209,4 +294,27 @@ mod tests {
             "fake_crate[0.1.0-alpha1]::test::Thing"
         );
     }
+
+    #[test]
+    fn unresolved() {
+        let relative = UnresolvedPath::fake("a::B");
+        let rooted = UnresolvedPath::fake("::a::B");
+        assert!(Identity::unresolved(&relative).is_unresolved());
+        assert_eq!(
+            Identity::unresolved(&relative).as_unresolved(),
+            Some(relative.clone())
+        );
+        assert_eq!(
+            Identity::unresolved(&rooted).as_unresolved(),
+            Some(rooted.clone())
+        );
+        assert_ne!(
+            Identity::unresolved(&relative),
+            Identity::unresolved(&rooted)
+        );
+        assert_eq!(
+            Identity::new(&*TEST_CRATE_A, &["a", "B"]).as_unresolved(),
+            None
+        );
+    }
 }
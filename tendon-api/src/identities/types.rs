@@ -1,8 +1,10 @@
 //! Type identities. These are used to refer to types.
 
-use crate::expressions::ConstExpr;
-use crate::identities::{Identity, LifetimeId, TraitId};
-use crate::paths::Ident;
+use crate::builtins::OUTPUT;
+use crate::expressions::ConstArg;
+use crate::generics::Generics;
+use crate::identities::{fmt_binder, Identity, LifetimeId, TraitId};
+use crate::paths::{Ident, UnresolvedPath};
 use crate::Map;
 use std::fmt;
 
@@ -24,7 +26,7 @@ use serde::{Deserialize, Serialize};
 ///         ^ this is a Type
 /// ```
 ///
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TypeId {
     Path(PathType),
     Array(ArrayType),
@@ -46,6 +48,220 @@ impl TypeId {
             _ => false,
         }
     }
+
+    /// Rebuild this type, applying `types` to every directly contained type (including generic
+    /// arguments and the arguments of trait bounds), `lifetimes` to every directly contained
+    /// lifetime, and `consts` to every directly contained const argument and array length.
+    /// Doesn't recurse on its own; `types` is expected to do that.
+    pub fn map_children(
+        &self,
+        types: &mut dyn FnMut(&TypeId) -> TypeId,
+        lifetimes: &mut dyn FnMut(&LifetimeId) -> LifetimeId,
+        consts: &mut dyn FnMut(&ConstArg) -> ConstArg,
+    ) -> TypeId {
+        match self {
+            TypeId::Path(PathType { path, params }) => TypeId::Path(PathType {
+                path: path.clone(),
+                params: params.map_children(types, lifetimes, consts),
+            }),
+            TypeId::Array(ArrayType { type_, len }) => TypeId::Array(ArrayType {
+                type_: Box::new(types(type_)),
+                len: consts(len),
+            }),
+            TypeId::Slice(SliceType { type_ }) => TypeId::Slice(SliceType {
+                type_: Box::new(types(type_)),
+            }),
+            TypeId::Reference(ReferenceType {
+                type_,
+                mut_,
+                lifetime,
+            }) => TypeId::Reference(ReferenceType {
+                type_: Box::new(types(type_)),
+                mut_: *mut_,
+                lifetime: lifetime.as_ref().map(|lt| lifetimes(lt)),
+            }),
+            TypeId::Pointer(PointerType { type_, mut_ }) => TypeId::Pointer(PointerType {
+                type_: Box::new(types(type_)),
+                mut_: *mut_,
+            }),
+            TypeId::Tuple(TupleType { types: elems }) => TypeId::Tuple(TupleType {
+                types: elems.iter().map(|t| types(t)).collect(),
+            }),
+            TypeId::Never(NeverType) => TypeId::Never(NeverType),
+            TypeId::QSelf(QSelfType {
+                self_,
+                trait_,
+                output_,
+            }) => TypeId::QSelf(QSelfType {
+                self_: Box::new(types(self_)),
+                trait_: trait_.map_children(types, lifetimes, consts),
+                output_: output_.clone(),
+            }),
+            TypeId::BareFn(BareFnType {
+                args,
+                ret,
+                varargs,
+                unsafe_,
+                for_lifetimes,
+            }) => TypeId::BareFn(BareFnType {
+                args: args.iter().map(|t| types(t)).collect(),
+                ret: Box::new(types(ret)),
+                varargs: *varargs,
+                unsafe_: *unsafe_,
+                for_lifetimes: for_lifetimes.clone(),
+            }),
+            TypeId::ImplTrait(ImplTraitType {
+                lifetime_bounds,
+                trait_bounds,
+            }) => TypeId::ImplTrait(ImplTraitType {
+                lifetime_bounds: lifetime_bounds.iter().map(|lt| lifetimes(lt)).collect(),
+                trait_bounds: trait_bounds
+                    .iter()
+                    .map(|t| t.map_children(types, lifetimes, consts))
+                    .collect(),
+            }),
+            TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+                TypeId::TraitObject(TraitObjectType {
+                    trait_bounds: trait_bounds
+                        .iter()
+                        .map(|t| t.map_children(types, lifetimes, consts))
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    /// Replace generic parameters in this type.
+    /// A path without arguments is replaced if it's in `substitutions.types`.
+    pub fn substitute(&self, substitutions: &Substitutions) -> TypeId {
+        if let TypeId::Path(PathType { path, params }) = self {
+            if params.is_empty() {
+                if let Some(replacement) = substitutions.types.get(path) {
+                    return replacement.clone();
+                }
+            }
+        }
+        self.map_children(
+            &mut |type_| type_.substitute(substitutions),
+            &mut |lifetime| substitutions.substitute_lifetime(lifetime),
+            &mut |const_| substitutions.substitute_const(const_),
+        )
+    }
+}
+
+/// Replacements for generic parameters, keyed by the parameters' identities.
+#[derive(Default, Clone, Debug)]
+pub struct Substitutions {
+    pub types: Map<Identity, TypeId>,
+    pub lifetimes: Map<Identity, LifetimeId>,
+    pub consts: Map<Identity, ConstArg>,
+}
+impl Substitutions {
+    /// The substitutions for an item's generic parameters, given the arguments it's used with.
+    ///
+    /// Arguments are matched by name, or by position if they haven't been matched yet; missing
+    /// arguments fall back to the parameters' defaults. References to a parameter may be resolved
+    /// (`Owner::T`) or not (`T`), so both are substituted.
+    pub fn for_generics(
+        owner: &Identity,
+        generics: &Generics,
+        params: &GenericParams,
+    ) -> Substitutions {
+        let mut substitutions = Substitutions::default();
+        for (i, param) in generics.types.iter().enumerate() {
+            let name = &param.metadata.name;
+            let arg = params
+                .type_bindings
+                .get(name)
+                .or_else(|| params.type_bindings.get(&Ident::positional(i)))
+                .or_else(|| param.default.as_ref());
+            if let Some(arg) = arg {
+                substitutions.types.insert(
+                    Identity::unresolved(&UnresolvedPath::new(false, &[name.clone()])),
+                    arg.clone(),
+                );
+                substitutions
+                    .types
+                    .insert(owner.clone_join(name), arg.clone());
+            }
+        }
+        // Positional const arguments are counted after the type arguments.
+        for (i, param) in generics.consts.iter().enumerate() {
+            let name = &param.metadata.name;
+            let arg = params
+                .consts
+                .get(name)
+                .or_else(|| {
+                    params
+                        .consts
+                        .get(&Ident::positional(generics.types.len() + i))
+                })
+                .or_else(|| param.default.as_ref());
+            if let Some(arg) = arg {
+                substitutions.consts.insert(
+                    Identity::unresolved(&UnresolvedPath::new(false, &[name.clone()])),
+                    arg.clone(),
+                );
+                substitutions
+                    .consts
+                    .insert(owner.clone_join(name), arg.clone());
+            }
+        }
+        for (i, param) in generics.lifetimes.iter().enumerate() {
+            let name = &param.metadata.name;
+            let arg = params
+                .lifetimes
+                .get(name)
+                .or_else(|| params.lifetimes.get(&Ident::positional(i)));
+            if let Some(arg) = arg {
+                substitutions.lifetimes.insert(
+                    Identity::unresolved(&UnresolvedPath::new(false, &[name.clone()])),
+                    arg.clone(),
+                );
+                substitutions
+                    .lifetimes
+                    .insert(owner.clone_join(name), arg.clone());
+            }
+        }
+
+        substitutions
+    }
+
+    /// Replace a const argument, if it's a path in `self.consts`.
+    pub fn substitute_const(&self, const_: &ConstArg) -> ConstArg {
+        if let ConstArg::Path(path) = const_ {
+            if let Some(replacement) = self.consts.get(path) {
+                return replacement.clone();
+            }
+        }
+        const_.clone()
+    }
+
+    /// Replace a lifetime, if it's in `self.lifetimes`.
+    pub fn substitute_lifetime(&self, lifetime: &LifetimeId) -> LifetimeId {
+        self.lifetimes
+            .get(lifetime.id())
+            .cloned()
+            .unwrap_or_else(|| lifetime.clone())
+    }
+}
+
+impl TraitId {
+    /// Rebuild this trait reference, applying `types`, `lifetimes` and `consts` to its generic
+    /// arguments.
+    pub fn map_children(
+        &self,
+        types: &mut dyn FnMut(&TypeId) -> TypeId,
+        lifetimes: &mut dyn FnMut(&LifetimeId) -> LifetimeId,
+        consts: &mut dyn FnMut(&ConstArg) -> ConstArg,
+    ) -> TraitId {
+        TraitId {
+            id: self.id.clone(),
+            params: self.params.map_children(types, lifetimes, consts),
+            is_maybe: self.is_maybe,
+            for_lifetimes: self.for_lifetimes.clone(),
+        }
+    }
 }
 impl fmt::Debug for TypeId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -66,7 +282,7 @@ impl fmt::Debug for TypeId {
 }
 
 /// A path, possibly with generic arguments `Type<T1, T2, Assoc=T3>`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PathType {
     /// The identity of the path's target.
     pub path: Identity,
@@ -76,28 +292,31 @@ pub struct PathType {
 debug!(PathType, "{:?}{:?}", path, params);
 
 /// An array, `[i32; n]`.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ArrayType {
     pub type_: Box<TypeId>,
-    pub len: ConstExpr,
+    /// The length of the array. May refer to a const parameter, `[T; N]`.
+    pub len: ConstArg,
 }
 debug!(ArrayType, "[{:?}; {:?}]", type_, len);
 
 /// A slice, `[i32]`.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SliceType {
     pub type_: Box<TypeId>,
 }
 debug!(SliceType, "[{:?}]", type_);
 
 /// An (optionally, mutable) reference.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ReferenceType {
     /// The referenced type.
     pub type_: Box<TypeId>,
     /// Whether the reference is mutable.
     pub mut_: bool,
     /// The lifetime of this reference, if present.
+    /// In function signatures, elided lifetimes are filled in during resolution where rust's
+    /// elision rules allow.
     pub lifetime: Option<LifetimeId>,
 }
 impl fmt::Debug for ReferenceType {
@@ -112,7 +331,7 @@ impl fmt::Debug for ReferenceType {
 }
 
 /// An (optionally, mutable) pointer.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PointerType {
     /// The pointed-to type.
     pub type_: Box<TypeId>,
@@ -128,7 +347,7 @@ impl fmt::Debug for PointerType {
 
 /// A tuple, `(i32, i8, String)`.
 /// If there are 0 arguments, this is the void type, `()`.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TupleType {
     pub types: Vec<TypeId>,
 }
@@ -155,12 +374,12 @@ impl fmt::Debug for TupleType {
 }
 
 /// The never type, `!`.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NeverType;
 debug!(NeverType, "!");
 
 /// A type `<T as Trait>::Output`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct QSelfType {
     /// `T`
     pub self_: Box<TypeId>,
@@ -172,7 +391,7 @@ pub struct QSelfType {
 debug!(QSelfType, "<{:?} as {:?}>::{:?}", self_, trait_, output_);
 
 /// `fn(i32, String) -> usize`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BareFnType {
     /// The arguments to the function pointer.
     pub args: Vec<TypeId>,
@@ -183,9 +402,12 @@ pub struct BareFnType {
     pub varargs: bool,
     /// If the function pointer is unsafe.
     pub unsafe_: bool,
+    /// Lifetimes introduced by a higher-ranked binder, `for<'a> fn(&'a u8)`.
+    pub for_lifetimes: Vec<LifetimeId>,
 }
 impl fmt::Debug for BareFnType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_binder(f, &self.for_lifetimes)?;
         if self.unsafe_ {
             write!(f, "unsafe ")?;
         }
@@ -207,7 +429,7 @@ impl fmt::Debug for BareFnType {
 }
 
 /// `impl Trait`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ImplTraitType {
     /// Lifetime bounds on this type.
     pub lifetime_bounds: Vec<LifetimeId>,
@@ -235,7 +457,7 @@ impl fmt::Debug for ImplTraitType {
 }
 
 /// `dyn Trait`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TraitObjectType {
     /// Bounds on this type.
     pub trait_bounds: Vec<TraitId>,
@@ -260,7 +482,7 @@ impl fmt::Debug for TraitObjectType {
 /// https://doc.rust-lang.org/reference/paths.html#paths-in-expressions
 /// Doesn't include constraints. Those are defined at the declaration site.
 /// Note: Default arguments are always present here.
-#[derive(Default, Clone, Serialize, Deserialize)]
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GenericParams {
     /// Type bindings (e.g. `Output=T`).
     /// Maps the declaration parameters to their assignments.
@@ -272,8 +494,9 @@ pub struct GenericParams {
 
     /// Const generic bindings.
     /// https://github.com/rust-lang/rfcs/blob/master/text/2000-const-generics.md
-    /// Positional arguments are resolved before this structure is created.
-    pub consts: Map<Ident, ConstExpr>,
+    /// Bare paths (`Thing<N>`) are parsed as types; they're moved here during resolution if they
+    /// turn out to name a const.
+    pub consts: Map<Ident, ConstArg>,
 }
 impl GenericParams {
     pub fn empty() -> GenericParams {
@@ -286,6 +509,59 @@ impl GenericParams {
     pub fn is_empty(&self) -> bool {
         self.lifetimes.is_empty() && self.type_bindings.is_empty() && self.consts.is_empty()
     }
+
+    /// The arguments written with parenthesized `Fn` sugar: `Fn(A, B) -> C` means
+    /// `Fn<(A, B), Output = C>`.
+    pub fn fn_sugar(inputs: Vec<TypeId>, output: TypeId) -> GenericParams {
+        let mut result = GenericParams::empty();
+        result.type_bindings.insert(
+            Ident::positional(0),
+            TypeId::Tuple(TupleType { types: inputs }),
+        );
+        result.type_bindings.insert(OUTPUT.clone(), output);
+        result
+    }
+
+    /// If these arguments have the shape of `Fn` sugar, the inputs and output.
+    /// Only meaningful for the `Fn` traits, see `TraitId::as_fn_sugar`.
+    pub(crate) fn as_fn_sugar(&self) -> Option<(&[TypeId], &TypeId)> {
+        if !self.lifetimes.is_empty() || !self.consts.is_empty() || self.type_bindings.len() != 2 {
+            return None;
+        }
+        match (
+            self.type_bindings.get(&Ident::positional(0)),
+            self.type_bindings.get(&*OUTPUT),
+        ) {
+            (Some(TypeId::Tuple(TupleType { types })), Some(output)) => Some((types, output)),
+            _ => None,
+        }
+    }
+
+    /// Rebuild these arguments, applying `types`, `lifetimes` and `consts` to every argument.
+    pub fn map_children(
+        &self,
+        types: &mut dyn FnMut(&TypeId) -> TypeId,
+        lifetimes: &mut dyn FnMut(&LifetimeId) -> LifetimeId,
+        consts: &mut dyn FnMut(&ConstArg) -> ConstArg,
+    ) -> GenericParams {
+        GenericParams {
+            type_bindings: self
+                .type_bindings
+                .iter()
+                .map(|(name, type_)| (name.clone(), types(type_)))
+                .collect(),
+            lifetimes: self
+                .lifetimes
+                .iter()
+                .map(|(name, lifetime)| (name.clone(), lifetimes(lifetime)))
+                .collect(),
+            consts: self
+                .consts
+                .iter()
+                .map(|(name, const_)| (name.clone(), consts(const_)))
+                .collect(),
+        }
+    }
 }
 
 impl fmt::Debug for GenericParams {
@@ -330,6 +606,7 @@ impl fmt::Debug for GenericParams {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::expressions::ConstExpr;
     use crate::identities::TEST_CRATE_A;
     use crate::tokens::Tokens;
 
@@ -353,12 +630,49 @@ mod tests {
         .is_void());
     }
 
+    #[test]
+    fn substitution() {
+        let param = Identity::new(&*TEST_CRATE_A, &["Alias", "T"]);
+        let lifetime = Identity::new(&*TEST_CRATE_A, &["Alias", "'a"]);
+        let path = |id: &Identity| {
+            TypeId::Path(PathType {
+                path: id.clone(),
+                params: Default::default(),
+            })
+        };
+        let mut substitutions = Substitutions::default();
+        substitutions
+            .types
+            .insert(param.clone(), TypeId::Never(NeverType));
+        substitutions.lifetimes.insert(
+            lifetime.clone(),
+            LifetimeId::new(Identity::new(&*TEST_CRATE_A, &["'static"])),
+        );
+
+        let type_ = TypeId::Tuple(TupleType {
+            types: vec![
+                path(&param),
+                TypeId::Reference(ReferenceType {
+                    type_: Box::new(path(&param)),
+                    mut_: false,
+                    lifetime: Some(LifetimeId::new(lifetime.clone())),
+                }),
+                path(&Identity::new(&*TEST_CRATE_A, &["Other"])),
+            ],
+        });
+        assert_eq!(
+            &format!("{:?}", type_.substitute(&substitutions)),
+            "(!, &test_crate_a[0.0.0]::\'static !, test_crate_a[0.0.0]::Other)"
+        );
+    }
+
     #[test]
     fn formatting() {
         let trait_ = TraitId {
             id: Identity::new(&*TEST_CRATE_A, &["TestTrait"]),
             params: Default::default(),
             is_maybe: false,
+            for_lifetimes: vec![],
         };
         let mut type_bindings = Map::default();
         type_bindings.insert(
@@ -382,7 +696,7 @@ mod tests {
                     mut_: true,
                     type_: Box::new(TypeId::Array(ArrayType {
                         type_: Box::new(TypeId::Never(NeverType)),
-                        len: ConstExpr(Tokens::from(5)),
+                        len: ConstArg::Expr(ConstExpr(Tokens::from(5))),
                     })),
                 }),
                 TypeId::Reference(ReferenceType {
@@ -398,6 +712,7 @@ mod tests {
                 TypeId::BareFn(BareFnType {
                     unsafe_: true,
                     varargs: true,
+                    for_lifetimes: vec![LifetimeId::new(Identity::new(&*TEST_CRATE_A, &["'a"]))],
                     ret: Box::new(TypeId::TraitObject(TraitObjectType {
                         trait_bounds: vec![trait_.clone()],
                     })),
@@ -412,12 +727,13 @@ mod tests {
                         id: Identity::new(&*TEST_CRATE_A, &["TestTrait"]),
                         params: Default::default(),
                         is_maybe: false,
+                        for_lifetimes: vec![],
                     }],
                     lifetime_bounds: vec![],
                 }),
             ],
         });
         assert_eq!(&format!("{:?}", type_),
-            "(test_crate_a[0.0.0]::test::Type<A=test_crate_a[0.0.0]::other::KindaType>, *mut [!; 5i32], &test_crate_a[0.0.0]::Type::\'param [!], unsafe fn(<! as test_crate_a[0.0.0]::TestTrait>::Wow, ...) -> (dyn test_crate_a[0.0.0]::TestTrait), (impl test_crate_a[0.0.0]::TestTrait))");
+            "(test_crate_a[0.0.0]::test::Type<A=test_crate_a[0.0.0]::other::KindaType>, *mut [!; 5i32], &test_crate_a[0.0.0]::Type::\'param [!], for<test_crate_a[0.0.0]::\'a> unsafe fn(<! as test_crate_a[0.0.0]::TestTrait>::Wow, ...) -> (dyn test_crate_a[0.0.0]::TestTrait), (impl test_crate_a[0.0.0]::TestTrait))");
     }
 }
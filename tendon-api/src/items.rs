@@ -1,9 +1,11 @@
-use crate::identities::{Identity, LifetimeId, TypeId};
+use crate::builtins::{FUTURE, OUTPUT};
+use crate::identities::{GenericParams, Identity, ImplTraitType, LifetimeId, TraitId, TypeId};
+use crate::paths::{Ident, UnresolvedPath};
 use crate::tokens::Tokens;
 use crate::{
-    attributes::{Metadata, SymbolMetadata, TypeMetadata},
-    expressions::ConstExpr,
-    paths::Ident,
+    attributes::{ForeignMetadata, Metadata, SymbolMetadata, TypeMetadata},
+    expressions::{ConstArg, ConstExpr, ConstValue},
+    generics::{Generics, LifetimePredicate, WherePredicate},
 };
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +26,8 @@ pub enum SymbolItem {
     Const(ConstItem),
     Static(StaticItem),
     Function(FunctionItem),
+    ForeignFunction(ForeignFunctionItem),
+    ForeignStatic(ForeignStaticItem),
     ConstParam(ConstParamItem),
 }
 
@@ -35,26 +39,32 @@ pub enum SymbolItem {
 pub enum TypeItem {
     Struct(StructItem),
     Enum(EnumItem),
+    Union(UnionItem),
     Trait(TraitItem),
+    Alias(AliasItem),
+    ForeignType(ForeignTypeItem),
     TypeParam(TypeParamItem),
     LifetimeParam(LifetimeParamItem),
 }
 
 /// A constant `const x: T = expr`, known at compile time,
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ConstItem {
     pub metadata: Metadata,
     pub type_: Box<TypeId>,
     pub value: ConstExpr,
+    /// The evaluated value, if `value` is simple enough for us to evaluate.
+    pub evaluated: Option<ConstValue>,
 }
 
 /// A static value `static x: T = expr`, stored at a location in memory.
 #[derive(Serialize, Deserialize)]
 pub struct StaticItem {
     pub metadata: Metadata,
+    pub symbol_metadata: SymbolMetadata,
     pub mut_: bool,
     pub type_: Box<TypeId>,
-    pub value: String,
+    pub value: ConstExpr,
 }
 
 /// A module.
@@ -72,8 +82,8 @@ pub struct StructItem {
     pub fields: Vec<StructField>,
     /// How this struct is defined.
     pub kind: StructKind,
-    /// Maps to a list of
-    pub generics: Vec<Identity>,
+    /// The generic parameters of this struct.
+    pub generics: Generics,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -95,7 +105,8 @@ pub struct StructField {
 pub struct EnumItem {
     pub metadata: Metadata,
     pub type_metadata: TypeMetadata,
-    pub generic_params: GenericParams,
+    /// The generic parameters of this enum.
+    pub generics: Generics,
     /// The variants of this enum.
     pub variants: Vec<EnumVariant>,
 }
@@ -105,18 +116,127 @@ pub struct EnumVariant {
     pub metadata: Metadata,
     pub kind: StructKind,
     pub fields: Vec<StructField>,
+    /// The explicit discriminant, `A = 3`, if present.
+    pub discriminant: Option<ConstExpr>,
+    /// The value of the discriminant, explicit or implicit (one more than the previous variant's).
+    /// Typed by the enum's integer repr, or `isize` if it doesn't have one.
+    /// `None` if it (or a discriminant it counts up from) couldn't be evaluated.
+    pub evaluated_discriminant: Option<ConstValue>,
+    /// If this variant is `#[non_exhaustive]`.
+    pub non_exhaustive: bool,
 }
 
-/// A union, `union Planet { Earth, Mars, Jupiter }`
-#[derive(Serialize, Deserialize)]
+/// A union, `union IntOrFloat { i: u32, f: f32 }`
+#[derive(Debug, Serialize, Deserialize)]
 pub struct UnionItem {
     pub metadata: Metadata,
+    pub type_metadata: TypeMetadata,
+    /// The generic parameters of this union.
+    pub generics: Generics,
+    /// The fields of this union.
+    pub fields: Vec<StructField>,
+}
+
+/// A type alias, `type Result<T> = std::result::Result<T, Error>;`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AliasItem {
+    pub metadata: Metadata,
+    /// The generic parameters of this alias.
+    pub generics: Generics,
+    /// The aliased type.
+    pub target: TypeId,
 }
 
 /// A trait declaration.
 #[derive(Serialize, Deserialize)]
 pub struct TraitItem {
     pub metadata: Metadata,
+    /// The generic parameters of this trait. Doesn't include the implicit `Self` parameter.
+    /// `where Self: Bound` predicates are moved to `supertraits`.
+    pub generics: Generics,
+    /// The implicit `Self` type parameter, bounded by this trait (with its own parameters passed
+    /// through). Inside the trait, `Self` resolves to `{trait}::Self`.
+    pub self_param: TypeParamItem,
+    /// Supertraits, `trait Thing: Clone + Debug`. Also includes `where Self: Bound` predicates.
+    pub supertraits: Vec<TraitId>,
+    /// Lifetime bounds on implementors, `trait Thing: 'static`.
+    pub lifetime_bounds: Vec<LifetimeId>,
+    /// If this trait is `unsafe trait`.
+    pub is_unsafe: bool,
+    /// If this trait is an `auto trait`.
+    pub is_auto: bool,
+    /// Methods, both required and provided.
+    pub methods: Vec<TraitMethod>,
+    /// Associated types.
+    pub types: Vec<AssociatedType>,
+    /// Associated consts.
+    pub consts: Vec<AssociatedConst>,
+}
+
+/// A method declared in a trait.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraitMethod {
+    pub metadata: Metadata,
+    /// The signature of this method.
+    pub signature: Signature,
+    /// If this method has a default implementation (i.e. implementors don't have to provide it).
+    pub is_provided: bool,
+}
+
+/// An associated type declared in a trait, `type Item: Clone = u8;`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssociatedType {
+    pub metadata: Metadata,
+    /// Generic parameters of a generic associated type, `type Item<'a>`.
+    pub generics: Generics,
+    /// Trait bounds on this type.
+    pub trait_bounds: Vec<TraitId>,
+    /// Lifetime bounds on this type.
+    pub lifetime_bounds: Vec<LifetimeId>,
+    /// The default value of the type, if present.
+    pub default: Option<TypeId>,
+}
+
+/// An associated const declared in a trait, `const N: usize = 3;`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssociatedConst {
+    pub metadata: Metadata,
+    pub type_: TypeId,
+    /// The default value of the const, if present.
+    pub default: Option<ConstExpr>,
+}
+
+/// An impl block, `impl<T> Trait for Thing<T> { ... }` or `impl Thing { ... }`.
+///
+/// Impls don't have names, so they aren't stored in a namespace; see `Crate::impls`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImplItem {
+    /// Metadata for the impl block. The name is `{impl_N}` for the Nth impl in its scope, see
+    /// `builtins::impl_scope_name`.
+    pub metadata: Metadata,
+    /// The module the impl is declared in. Paths in the impl are relative to this.
+    pub scope: Identity,
+    pub generics: Generics,
+    /// The implemented trait, or `None` for inherent impls.
+    pub trait_: Option<TraitId>,
+    /// The type the impl is for.
+    pub self_type: TypeId,
+    /// If this is an `unsafe impl`.
+    pub is_unsafe: bool,
+    /// If this is a negative impl, `impl !Send for Thing {}`.
+    pub is_negative: bool,
+    pub methods: Vec<FunctionItem>,
+    pub types: Vec<ImplType>,
+    pub consts: Vec<ConstItem>,
+}
+
+/// An associated type defined in an impl, `type Item = u8;`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImplType {
+    pub metadata: Metadata,
+    /// Generic parameters of a generic associated type, `type Item<'a> = &'a u8`.
+    pub generics: Generics,
+    pub type_: TypeId,
 }
 
 /// A macro-by-example, `macro_rules!`.
@@ -148,9 +268,10 @@ pub struct DeriveMacroItem {
 }
 
 /// A function (or method).
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Signature {
-    pub generic_params: GenericParams,
+    /// The generic parameters of this function.
+    pub generics: Generics,
     /// The arguments to this function.
     /// Note: this doesn't include `self`, that'll be stored in `Method.receiver` instead
     pub args: Vec<FunctionArg>,
@@ -170,8 +291,70 @@ pub struct Signature {
     pub variadic: bool,
 }
 
+impl Signature {
+    /// A view of this signature with `async` desugared:
+    /// `async fn f<'a>(x: &'a u8) -> T` becomes
+    /// `fn f<'a, '{captured}>(x: &'a u8) -> impl Future<Output = T> + '{captured} where 'a: '{captured}`.
+    ///
+    /// The future captures every lifetime parameter, so `'{captured}` is outlived by all of them.
+    /// (Run this after lifetime elision so that elided lifetimes are included.) The synthetic
+    /// lifetime parameter borrows the visibility and span of `metadata`, which should be the
+    /// function's.
+    ///
+    /// Non-async signatures are returned unchanged.
+    pub fn desugar_async(&self, metadata: &Metadata) -> Signature {
+        let mut result = self.clone();
+        if !self.is_async {
+            return result;
+        }
+        result.is_async = false;
+
+        let lifetime_id = |name: Ident| {
+            LifetimeId::new(Identity::unresolved(&UnresolvedPath::new(false, &[name])))
+        };
+        let captured = lifetime_id(Ident::captured_lifetime());
+
+        let mut lifetime_bounds = vec![];
+        if !self.generics.lifetimes.is_empty() {
+            for param in &self.generics.lifetimes {
+                result
+                    .generics
+                    .where_predicates
+                    .push(WherePredicate::Lifetime(LifetimePredicate {
+                        lifetime: lifetime_id(param.metadata.name.clone()),
+                        bounds: vec![captured.clone()],
+                    }));
+            }
+            result.generics.lifetimes.push(LifetimeParamItem {
+                metadata: Metadata::synthetic(
+                    Ident::captured_lifetime(),
+                    metadata.visibility.clone(),
+                    metadata.span.clone(),
+                ),
+                bounds: vec![],
+            });
+            lifetime_bounds.push(captured);
+        }
+
+        let mut params = GenericParams::empty();
+        params
+            .type_bindings
+            .insert(OUTPUT.clone(), self.ret.clone());
+        result.ret = TypeId::ImplTrait(ImplTraitType {
+            trait_bounds: vec![TraitId {
+                id: FUTURE.clone(),
+                params,
+                is_maybe: false,
+                for_lifetimes: vec![],
+            }],
+            lifetime_bounds,
+        });
+        result
+    }
+}
+
 /// The abi of a function.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Abi {
     Rust,
     C,
@@ -187,7 +370,32 @@ pub struct FunctionItem {
     pub signature: Signature,
 }
 
+/// A function imported from a native library, declared in an `extern` block.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct ForeignFunctionItem {
+    pub metadata: Metadata,
+    pub foreign_metadata: ForeignMetadata,
+    /// The signature of this function. The abi is the abi of the `extern` block.
+    pub signature: Signature,
+}
+
+/// A static imported from a native library, declared in an `extern` block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForeignStaticItem {
+    pub metadata: Metadata,
+    pub foreign_metadata: ForeignMetadata,
+    pub mut_: bool,
+    pub type_: TypeId,
+}
+
+/// An opaque type declared in an `extern` block, `type Opaque;`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForeignTypeItem {
+    pub metadata: Metadata,
+    pub foreign_metadata: ForeignMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionArg {
     pub metadata: Metadata,
     /// The type of the argument.
@@ -195,14 +403,14 @@ pub struct FunctionArg {
 }
 
 /// The receiver of a method.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Receiver {
     /// Doesn't take an instance as an argument.
     /// This is always the case for non-method functions.
     None,
     /// Takes `self`.
     ConsumeSelf,
-    /// Takes `&self`.
+    /// Takes `&self`. An elided lifetime is filled in with a synthetic one during resolution.
     RefSelf {
         lifetime: Option<LifetimeId>,
         mut_: bool,
@@ -212,50 +420,30 @@ pub enum Receiver {
 }
 
 /// A type parameter item. These are stored at declaration sites.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TypeParamItem {
     pub metadata: Metadata,
-    pub type_constraints: Vec<TypeId>,
-    pub lifetime_constraints: Vec<LifetimeId>,
+    /// Trait bounds written inline, `T: Clone`.
+    pub trait_bounds: Vec<TraitId>,
+    /// Lifetime bounds written inline, `T: 'a`.
+    pub lifetime_bounds: Vec<LifetimeId>,
     /// The default value of the type parameter, if present.
     pub default: Option<TypeId>,
 }
 
 /// A lifetime parameter item. These are stored at declaration sites.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct LifetimeParamItem {
     pub metadata: Metadata,
-    pub constraints: Vec<LifetimeId>,
+    /// Lifetime bounds written inline, `'a: 'b`.
+    pub bounds: Vec<LifetimeId>,
 }
 
 /// A const parameter item. These are stored at declaration sites.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ConstParamItem {
     pub metadata: Metadata,
     pub type_: TypeId,
-    pub default: Option<ConstExpr>,
-}
-
-/// Generics embedded at a use site.
-#[derive(Serialize, Deserialize, Debug, Default)]
-pub struct GenericParams {
-    /// Lifetime parameters to a type.
-    pub lifetimes: Vec<LifetimeId>,
-    /// Type arguments to a type.
-    pub types: Vec<TypeId>,
-    /// Type bindings (e.g. `Output=T`)
-    pub type_bindings: Vec<(Ident, TypeId)>,
-    /// Const generic bindings.
-    /// https://github.com/rust-lang/rfcs/blob/master/text/2000-const-generics.md
-    /// Note: some of these may be parsed as types unfortunately, need to fix that later
-    /// in the pipeline.
-    pub consts: Vec<(Ident, ConstExpr)>,
-}
-impl GenericParams {
-    pub fn is_empty(&self) -> bool {
-        self.lifetimes.is_empty()
-            && self.types.is_empty()
-            && self.type_bindings.is_empty()
-            && self.consts.is_empty()
-    }
+    /// The default value of the const parameter, if present.
+    pub default: Option<ConstArg>,
 }
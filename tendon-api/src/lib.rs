@@ -50,5 +50,6 @@ pub mod generics;
 pub mod identities;
 pub mod items;
 pub mod paths;
+pub mod references;
 pub mod scopes;
 pub mod tokens;
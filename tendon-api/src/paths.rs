@@ -104,6 +104,8 @@ impl fmt::Debug for UnresolvedPath {
 /// Raw identifiers are represented as `r#thing`.
 /// Lifetimes are represented as `'thing`.
 /// Anonymous scopes are represented as `{anon_123}`.
+/// Positional generic arguments that haven't been matched to their parameters yet are
+/// represented as `{0}`, `{1}`, etc.
 ///
 /// TODO: do raw lifetime identifiers exist??
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -126,6 +128,41 @@ impl Ident {
             Ident(format!("'{}", name).into())
         }
     }
+    /// Create the name of the `index`th synthetic lifetime parameter introduced by lifetime
+    /// elision, `'{elided0}`.
+    pub fn elided_lifetime(index: usize) -> Ident {
+        Ident(format!("'{{elided{}}}", index).into())
+    }
+    /// If this is a lifetime introduced by elision.
+    pub fn is_elided_lifetime(&self) -> bool {
+        self.0.starts_with("'{elided")
+    }
+    /// The name of the synthetic lifetime captured by a desugared `async fn`'s future,
+    /// `'{captured}`.
+    pub fn captured_lifetime() -> Ident {
+        Ident("'{captured}".into())
+    }
+    /// Create the name of the `index`th anonymous type parameter introduced by an
+    /// argument-position `impl Trait`, `{impl_trait0}`.
+    pub fn impl_trait_param(index: usize) -> Ident {
+        Ident(format!("{{impl_trait{}}}", index).into())
+    }
+    /// If this is an anonymous type parameter introduced by an argument-position `impl Trait`.
+    pub fn is_impl_trait_param(&self) -> bool {
+        self.0.starts_with("{impl_trait")
+    }
+    /// Create a placeholder for the `index`th positional generic argument.
+    pub fn positional(index: usize) -> Ident {
+        Ident(format!("{{{}}}", index).into())
+    }
+    /// If this is a positional placeholder, get its index.
+    pub fn as_positional(&self) -> Option<usize> {
+        if self.0.starts_with("{") && self.0.ends_with("}") {
+            self.0[1..self.0.len() - 1].parse().ok()
+        } else {
+            None
+        }
+    }
     /// Check if an identifier is raw.
     pub fn is_raw(&self) -> bool {
         self.0.starts_with("r#")
@@ -220,6 +257,9 @@ mod tests {
     fn special_idents() {
         assert_eq!(Ident::raw("r#a"), Ident::raw("a"));
         assert_eq!(Ident::lifetime("'a"), Ident::lifetime("a"));
+        assert_eq!(Ident::positional(3).as_positional(), Some(3));
+        assert_eq!(Ident::from("{anon_3}").as_positional(), None);
+        assert_eq!(Ident::from("a").as_positional(), None);
     }
 
     #[test]
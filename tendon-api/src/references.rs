@@ -0,0 +1,228 @@
+//! The items an item refers to: the types of its fields, arguments and return values, the
+//! traits in its bounds, and so on.
+//!
+//! These collect every identity mentioned, without checking that it names an item; generic
+//! parameters and builtins end up in the set too, and callers look up the ones they care about.
+
+use crate::attributes::Visibility;
+use crate::expressions::ConstArg;
+use crate::generics::{Generics, WherePredicate};
+use crate::identities::{
+    ArrayType, BareFnType, GenericParams, Identity, ImplTraitType, PathType, PointerType,
+    QSelfType, ReferenceType, SliceType, TraitId, TraitObjectType, TupleType, TypeId,
+};
+use crate::items::{ImplItem, Receiver, Signature, StructField, SymbolItem, TypeItem};
+use crate::Set;
+
+/// Which parts of an item to look at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surface {
+    /// Only what users of the item can see: `pub` fields, `pub` inherent methods.
+    Public,
+    /// Everything, including private fields.
+    All,
+}
+
+/// Collect the items referred to by a type.
+pub fn type_references(type_: &TypeId, out: &mut Set<Identity>) {
+    match type_ {
+        TypeId::Path(PathType { path, params }) => {
+            out.insert(path.clone());
+            params_references(params, out);
+        }
+        TypeId::Array(ArrayType { type_, len }) => {
+            type_references(type_, out);
+            const_arg_references(len, out);
+        }
+        TypeId::Slice(SliceType { type_ })
+        | TypeId::Reference(ReferenceType { type_, .. })
+        | TypeId::Pointer(PointerType { type_, .. }) => type_references(type_, out),
+        TypeId::Tuple(TupleType { types }) => {
+            for type_ in types {
+                type_references(type_, out);
+            }
+        }
+        TypeId::Never(_) => (),
+        TypeId::QSelf(QSelfType { self_, trait_, .. }) => {
+            type_references(self_, out);
+            trait_references(trait_, out);
+        }
+        TypeId::BareFn(BareFnType { args, ret, .. }) => {
+            for arg in args {
+                type_references(arg, out);
+            }
+            type_references(ret, out);
+        }
+        TypeId::ImplTrait(ImplTraitType { trait_bounds, .. })
+        | TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+            for trait_ in trait_bounds {
+                trait_references(trait_, out);
+            }
+        }
+    }
+}
+
+/// Collect the items referred to by a trait bound.
+pub fn trait_references(trait_: &TraitId, out: &mut Set<Identity>) {
+    out.insert(trait_.id.clone());
+    params_references(&trait_.params, out);
+}
+
+fn params_references(params: &GenericParams, out: &mut Set<Identity>) {
+    for type_ in params.type_bindings.values() {
+        type_references(type_, out);
+    }
+    for const_ in params.consts.values() {
+        const_arg_references(const_, out);
+    }
+}
+
+fn const_arg_references(const_: &ConstArg, out: &mut Set<Identity>) {
+    if let ConstArg::Path(path) = const_ {
+        out.insert(path.clone());
+    }
+}
+
+/// Collect the items referred to by the bounds and defaults of generic parameters.
+pub fn generics_references(generics: &Generics, out: &mut Set<Identity>) {
+    for param in &generics.types {
+        for trait_ in &param.trait_bounds {
+            trait_references(trait_, out);
+        }
+        if let Some(default) = &param.default {
+            type_references(default, out);
+        }
+    }
+    for param in &generics.consts {
+        type_references(&param.type_, out);
+        if let Some(default) = &param.default {
+            const_arg_references(default, out);
+        }
+    }
+    for predicate in &generics.where_predicates {
+        if let WherePredicate::Type(predicate) = predicate {
+            type_references(&predicate.type_, out);
+            for trait_ in &predicate.trait_bounds {
+                trait_references(trait_, out);
+            }
+        }
+    }
+}
+
+/// Collect the items referred to by a function signature.
+pub fn signature_references(signature: &Signature, out: &mut Set<Identity>) {
+    generics_references(&signature.generics, out);
+    if let Receiver::Other(type_) = &signature.receiver {
+        type_references(type_, out);
+    }
+    for arg in &signature.args {
+        type_references(&arg.type_, out);
+    }
+    type_references(&signature.ret, out);
+}
+
+fn fields_references(fields: &[StructField], surface: Surface, out: &mut Set<Identity>) {
+    for field in fields {
+        if surface == Surface::All || field.metadata.visibility == Visibility::Pub {
+            type_references(&field.type_, out);
+        }
+    }
+}
+
+/// Collect the items referred to by a type item.
+pub fn type_item_references(item: &TypeItem, surface: Surface, out: &mut Set<Identity>) {
+    match item {
+        TypeItem::Struct(struct_) => {
+            generics_references(&struct_.generics, out);
+            fields_references(&struct_.fields, surface, out);
+        }
+        TypeItem::Enum(enum_) => {
+            generics_references(&enum_.generics, out);
+            for variant in &enum_.variants {
+                // variant fields are as public as the enum
+                fields_references(&variant.fields, Surface::All, out);
+            }
+        }
+        TypeItem::Union(union_) => {
+            generics_references(&union_.generics, out);
+            fields_references(&union_.fields, surface, out);
+        }
+        TypeItem::Trait(trait_) => {
+            generics_references(&trait_.generics, out);
+            for supertrait in &trait_.supertraits {
+                trait_references(supertrait, out);
+            }
+            for method in &trait_.methods {
+                signature_references(&method.signature, out);
+            }
+            for type_ in &trait_.types {
+                generics_references(&type_.generics, out);
+                for trait_ in &type_.trait_bounds {
+                    trait_references(trait_, out);
+                }
+                if let Some(default) = &type_.default {
+                    type_references(default, out);
+                }
+            }
+            for const_ in &trait_.consts {
+                type_references(&const_.type_, out);
+            }
+        }
+        TypeItem::Alias(alias) => {
+            generics_references(&alias.generics, out);
+            type_references(&alias.target, out);
+        }
+        TypeItem::TypeParam(param) => {
+            for trait_ in &param.trait_bounds {
+                trait_references(trait_, out);
+            }
+            if let Some(default) = &param.default {
+                type_references(default, out);
+            }
+        }
+        TypeItem::ForeignType(_) | TypeItem::LifetimeParam(_) => (),
+    }
+}
+
+/// Collect the items referred to by a symbol item.
+pub fn symbol_item_references(item: &SymbolItem, out: &mut Set<Identity>) {
+    match item {
+        SymbolItem::Const(const_) => type_references(&const_.type_, out),
+        SymbolItem::Static(static_) => type_references(&static_.type_, out),
+        SymbolItem::Function(function) => signature_references(&function.signature, out),
+        SymbolItem::ForeignFunction(function) => signature_references(&function.signature, out),
+        SymbolItem::ForeignStatic(static_) => type_references(&static_.type_, out),
+        SymbolItem::ConstParam(param) => type_references(&param.type_, out),
+    }
+}
+
+/// Collect the items referred to by an impl block.
+/// With `Surface::Public`, an inherent impl's non-`pub` members are skipped; a trait impl's
+/// members are always as public as the trait.
+pub fn impl_references(impl_: &ImplItem, surface: Surface, out: &mut Set<Identity>) {
+    generics_references(&impl_.generics, out);
+    if let Some(trait_) = &impl_.trait_ {
+        trait_references(trait_, out);
+    }
+    type_references(&impl_.self_type, out);
+
+    let visible = |visibility: &Visibility| {
+        surface == Surface::All || impl_.trait_.is_some() || *visibility == Visibility::Pub
+    };
+    for method in &impl_.methods {
+        if visible(&method.metadata.visibility) {
+            signature_references(&method.signature, out);
+        }
+    }
+    for type_ in &impl_.types {
+        if visible(&type_.metadata.visibility) {
+            generics_references(&type_.generics, out);
+            type_references(&type_.type_, out);
+        }
+    }
+    for const_ in &impl_.consts {
+        if visible(&const_.metadata.visibility) {
+            type_references(&const_.type_, out);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Scopes and bindings.
 
-use crate::attributes::{Metadata, Visibility};
+use crate::attributes::{DocInline, Metadata, Visibility};
 use crate::database::NamespaceLookup;
 use crate::identities::Identity;
 use crate::paths::Ident;
@@ -43,6 +43,11 @@ impl Scope {
         self.bindings[namespace_id as usize].get(ident)
     }
 
+    /// Get a mutable binding by namespace id. Does NOT check inherited scopes or prelude.
+    pub fn get_by_mut(&mut self, namespace_id: NamespaceId, ident: &Ident) -> Option<&mut Binding> {
+        self.bindings[namespace_id as usize].get_mut(ident)
+    }
+
     /// Insert a binding by namespace id. Returns Err if already present.
     /// Does NOT update back links!
     pub fn insert_by(
@@ -69,12 +74,21 @@ impl Scope {
                     identity: target,
                     visibility,
                     priority,
+                    doc_hidden: false,
+                    doc_inline: None,
                 });
                 Ok(())
             }
         }
     }
 
+    /// Remove the bindings, in every namespace, that `f` returns false for.
+    pub fn retain_bindings(&mut self, f: impl Fn(&Binding) -> bool) {
+        for bindings in &mut self.bindings {
+            bindings.retain(|_, binding| f(binding));
+        }
+    }
+
     /// Iterate bindings by namespace id.
     pub fn iter_by(&self, namespace_id: NamespaceId) -> impl Iterator<Item = (&Ident, &Binding)> {
         self.bindings[namespace_id as usize].iter()
@@ -137,6 +151,12 @@ pub struct Binding {
 
     /// If the binding is through a glob or explicit.
     pub priority: Priority,
+
+    /// If the binding is a re-export marked `#[doc(hidden)]`.
+    pub doc_hidden: bool,
+
+    /// If the binding is a re-export marked `#[doc(inline)]` or `#[doc(no_inline)]`.
+    pub doc_inline: Option<DocInline>,
 }
 
 /// Identifies a namespace.
@@ -2,25 +2,28 @@
 //! Implemented parser-combinator style, with code split out to helpers.
 //! Note: syn's datastructure's aren't thread-safe, so we can never include them in the output data.
 //! Style note: always prefix syn types with "syn" in this crate.
+//!
+//! Lowering doesn't resolve anything: every path is stored as an `Identity::unresolved`
+//! placeholder, and positional generic arguments are stored under `Ident::positional` keys.
 
-pub(crate) mod attributes;
-
-/*
 use std::fmt;
 use tendon_api::tokens::Tokens;
 
+pub(crate) mod attributes;
+pub(crate) mod docs;
+pub(crate) mod expressions;
 pub(crate) mod generics;
 pub(crate) mod imports;
 pub(crate) mod items;
+pub(crate) mod types;
+
+/*
 pub(crate) mod macros;
 pub(crate) mod modules;
-pub(crate) mod types;
+*/
 
 quick_error! {
     pub enum LowerError {
-        NoHRTBsYet(hrtb_: Tokens) {
-            display("HRTBs unimplemented, can't lower {:?}", hrtb_)
-        }
         UnhandledType(type_: Tokens) {
             display("i don't know how to lower the type {:?}", type_)
         }
@@ -53,4 +56,3 @@ impl fmt::Debug for LowerError {
         fmt::Display::fmt(self, f)
     }
 }
-*/
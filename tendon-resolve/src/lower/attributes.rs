@@ -1,13 +1,54 @@
 //! Attribute lowering.
 
+use super::LowerError;
+use crate::lower::docs::{lower_doc_links, lower_doc_sections};
+use crate::lower::expressions::is_int;
+use crate::walker::LocationMetadata;
+use lazy_static::lazy_static;
+use std::fs;
+use syn::punctuated::Punctuated;
 use tendon_api::{
-    attributes::{Attribute, Meta, MetaInner},
+    attributes::{
+        Attribute, Deprecation, DocInline, Link, Meta, MetaInner, Metadata, Repr, ReprBase, Span,
+        SymbolMetadata, TypeMetadata, Visibility,
+    },
+    identities::{GenericParams, Identity, TraitId},
+    paths::{Ident, UnresolvedPath},
     tokens::Tokens,
 };
-use tracing::warn;
+use tracing::{info_span, trace, warn};
 
 // mod interp_cfg;
 
+lazy_static! {
+    // the string used by `syn` for converting doc comments to attributes
+    static ref DOCS: UnresolvedPath = UnresolvedPath::fake("doc");
+    static ref MUST_USE: UnresolvedPath = UnresolvedPath::fake("must_use");
+    static ref DEPRECATED: UnresolvedPath = UnresolvedPath::fake("deprecated");
+    static ref SINCE: UnresolvedPath = UnresolvedPath::fake("since");
+    static ref NOTE: UnresolvedPath = UnresolvedPath::fake("note");
+    static ref DERIVE: UnresolvedPath = UnresolvedPath::fake("derive");
+    static ref NO_MANGLE: UnresolvedPath = UnresolvedPath::fake("no_mangle");
+    static ref EXPORT_NAME: UnresolvedPath = UnresolvedPath::fake("export_name");
+    static ref LINK_SECTION: UnresolvedPath = UnresolvedPath::fake("link_section");
+    static ref REPR: UnresolvedPath = UnresolvedPath::fake("repr");
+    static ref REPR_RUST: UnresolvedPath = UnresolvedPath::fake("Rust");
+    static ref REPR_C: UnresolvedPath = UnresolvedPath::fake("C");
+    static ref REPR_TRANSPARENT: UnresolvedPath = UnresolvedPath::fake("transparent");
+    static ref REPR_PACKED: UnresolvedPath = UnresolvedPath::fake("packed");
+    static ref REPR_ALIGN: UnresolvedPath = UnresolvedPath::fake("align");
+    static ref CFG: UnresolvedPath = UnresolvedPath::fake("cfg");
+    static ref LINK: UnresolvedPath = UnresolvedPath::fake("link");
+    static ref LINK_NAME: UnresolvedPath = UnresolvedPath::fake("link_name");
+    static ref NON_EXHAUSTIVE: UnresolvedPath = UnresolvedPath::fake("non_exhaustive");
+    static ref NAME: UnresolvedPath = UnresolvedPath::fake("name");
+    static ref KIND: UnresolvedPath = UnresolvedPath::fake("kind");
+    static ref DOC_HIDDEN: UnresolvedPath = UnresolvedPath::fake("hidden");
+    static ref DOC_INLINE: UnresolvedPath = UnresolvedPath::fake("inline");
+    static ref DOC_NO_INLINE: UnresolvedPath = UnresolvedPath::fake("no_inline");
+    static ref DOC_ALIAS: UnresolvedPath = UnresolvedPath::fake("alias");
+}
+
 /// Find an attribute within a list of syn attibutes, and lower it to our format.
 pub fn extract_attribute(attrs: &[syn::Attribute], name: &str) -> Option<Attribute> {
     let attr = attrs.iter().find(|a| a.path.is_ident(name))?;
@@ -48,6 +89,81 @@ fn lower_meta(meta: &syn::Meta) -> Meta {
     }
 }
 
+/// Expand the macro in a doc attribute with a macro for a value, given the tokens after `doc`.
+/// The only one we handle is `#[doc = include_str!("../README.md")]`; the path is relative to the
+/// file the attribute is in. The path can also be built with `concat!` and
+/// `env!("CARGO_MANIFEST_DIR")`.
+fn expand_doc_macro(loc: &LocationMetadata, input: &Tokens) -> Option<String> {
+    let mut tokens = input.get_tokens().into_iter();
+    match tokens.next() {
+        Some(proc_macro2::TokenTree::Punct(eq)) if eq.as_char() == '=' => (),
+        _ => return None,
+    }
+    let mac = match syn::parse2::<syn::Expr>(tokens.collect()).ok()? {
+        syn::Expr::Macro(expr) => expr.mac,
+        _ => return None,
+    };
+    if mac.path.segments.last()?.ident != "include_str" {
+        return None;
+    }
+    let file = match mac
+        .parse_body::<syn::Expr>()
+        .ok()
+        .and_then(|arg| expand_str_expr(loc, &arg))
+    {
+        Some(file) => file,
+        None => {
+            warn!("can't expand doc include: {:?}", input);
+            return None;
+        }
+    };
+    let path = match loc.source_file.parent() {
+        Some(dir) => dir.join(file),
+        None => file.into(),
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => Some(contents),
+        Err(err) => {
+            warn!("failed to include docs from {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Expand a string expression in a macro argument: a string literal, `concat!` of string
+/// expressions, or `env!("CARGO_MANIFEST_DIR")`. Other environment variables aren't known.
+fn expand_str_expr(loc: &LocationMetadata, expr: &syn::Expr) -> Option<String> {
+    let mac = match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => return Some(lit.value()),
+        syn::Expr::Group(group) => return expand_str_expr(loc, &group.expr),
+        syn::Expr::Macro(expr) => &expr.mac,
+        _ => return None,
+    };
+    let name = mac.path.segments.last()?.ident.to_string();
+    match &name[..] {
+        "concat" => mac
+            .parse_body_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated)
+            .ok()?
+            .iter()
+            .map(|arg| expand_str_expr(loc, arg))
+            .collect(),
+        "env" => {
+            let var = mac.parse_body::<syn::LitStr>().ok()?.value();
+            if var == "CARGO_MANIFEST_DIR" {
+                let dir = loc.crate_data.manifest_path.parent()?;
+                Some(dir.to_string_lossy().into_owned())
+            } else {
+                warn!("unknown environment variable in doc include: {}", var);
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
 /// TODO replace this w/ proper PM2 shim
 fn extract_string(lit: &Tokens) -> String {
     if let Ok(lit) = syn::parse2::<syn::LitStr>(lit.get_tokens()) {
@@ -58,36 +174,19 @@ fn extract_string(lit: &Tokens) -> String {
     }
 }
 
-/*
-
-lazy_static! {
-    // the string used by `syn` for converting doc comments to attributes
-    static ref DOCS: Path = Path::fake("doc");
-    static ref MUST_USE: Path = Path::fake("must_use");
-    static ref DEPRECATED: Path = Path::fake("deprecated");
-    static ref SINCE: Path = Path::fake("since");
-    static ref NOTE: Path = Path::fake("note");
-    static ref DERIVE: Path = Path::fake("derive");
-    static ref NO_MANGLE: Path = Path::fake("no_mangle");
-    static ref EXPORT_NAME: Path = Path::fake("export_name");
-    static ref LINK_SECTION: Path = Path::fake("link_section");
-    static ref REPR: Path = Path::fake("repr");
-    static ref REPR_RUST: Path = Path::fake("Rust");
-    static ref REPR_C: Path = Path::fake("C");
-    static ref REPR_TRANSPARENT: Path = Path::fake("transparent");
-    static ref REPR_PACKED: Path = Path::fake("packed");
-    static ref CFG: Path = Path::fake("cfg");
-}
-
 /// Lower a bunch of syn data structures to the generic `ItemMetadata`.
 pub(crate) fn lower_metadata(
     loc: &LocationMetadata,
+    name: Ident,
     visibility: &syn::Visibility,
     attributes: &[syn::Attribute],
     span: proc_macro2::Span,
 ) -> Result<Metadata, LowerError> {
-    let visibility = lower_visibility(visibility);
+    let visibility = lower_visibility(loc, visibility);
     let mut docs = None;
+    let mut doc_hidden = false;
+    let mut doc_inline = None;
+    let mut doc_aliases = vec![];
     let mut must_use = None;
     let mut deprecated = None;
     let mut extra_attributes = vec![];
@@ -104,22 +203,51 @@ pub(crate) fn lower_metadata(
     for syn_attr in attributes {
         let attr = lower_attribute(syn_attr);
         if attr.path() == &*DOCS {
-            docs = Some(
-                if let Attribute::Meta(Meta::Assign { literal, .. }) = attr {
-                    extract_string(&literal)
-                } else {
-                    trace!(
-                        "unimplemented doc attribute {:?} [{:?}]",
-                        attr,
-                        Span::new(
-                            loc.macro_invocation.clone(),
-                            loc.source_file.clone(),
-                            span.clone()
-                        )
-                    );
-                    "".into()
-                },
-            );
+            // every `///` line is a separate attribute
+            let line = match &attr {
+                Attribute::Meta(Meta::Assign { literal, .. }) => Some(extract_string(literal)),
+                Attribute::Meta(Meta::Call { args, .. }) => {
+                    for arg in args {
+                        match arg {
+                            MetaInner::Meta(Meta::Path(path)) if path == &*DOC_HIDDEN => {
+                                doc_hidden = true
+                            }
+                            MetaInner::Meta(Meta::Path(path)) if path == &*DOC_INLINE => {
+                                doc_inline = Some(DocInline::Inline)
+                            }
+                            MetaInner::Meta(Meta::Path(path)) if path == &*DOC_NO_INLINE => {
+                                doc_inline = Some(DocInline::NoInline)
+                            }
+                            MetaInner::Meta(Meta::Assign { path, literal })
+                                if path == &*DOC_ALIAS =>
+                            {
+                                doc_aliases.push(extract_string(literal))
+                            }
+                            // `#[doc(alias("a", "b"))]`
+                            MetaInner::Meta(Meta::Call { path, args }) if path == &*DOC_ALIAS => {
+                                for alias in args {
+                                    if let MetaInner::Literal(literal) = alias {
+                                        doc_aliases.push(extract_string(literal));
+                                    }
+                                }
+                            }
+                            _ => trace!("unimplemented doc attribute {:?} [{:?}]", arg, span_),
+                        }
+                    }
+                    continue;
+                }
+                Attribute::Other { input, .. } => expand_doc_macro(loc, input),
+                Attribute::Meta(Meta::Path(_)) => None,
+            };
+            match line {
+                Some(line) => {
+                    docs = Some(match docs {
+                        Some(docs) => format!("{}\n{}", docs, line),
+                        None => line,
+                    })
+                }
+                None => trace!("unimplemented doc attribute {:?} [{:?}]", attr, span_),
+            }
         } else if attr.path() == &*MUST_USE {
             must_use = Some(
                 if let Attribute::Meta(Meta::Assign { literal, .. }) = attr {
@@ -159,9 +287,24 @@ pub(crate) fn lower_metadata(
         }
     }
 
+    let doc_links = docs
+        .as_ref()
+        .map(|docs| lower_doc_links(docs))
+        .unwrap_or_default();
+    let doc_sections = docs
+        .as_ref()
+        .map(|docs| lower_doc_sections(docs))
+        .unwrap_or_default();
+
     let mut result = Metadata {
+        name,
         visibility,
         docs,
+        doc_links,
+        doc_sections,
+        doc_hidden,
+        doc_inline,
+        doc_aliases,
         must_use,
         deprecated,
         extra_attributes,
@@ -184,32 +327,52 @@ pub(crate) fn lower_metadata(
     Ok(result)
 }
 
-/// Lower a visibility. Assumes inherited visibilities aren't `pub`; you'll need to correct that
-/// by hand.
-pub fn lower_visibility(visibility: &syn::Visibility) -> Visibility {
+/// Lower a visibility, relative to the module we're currently in.
+/// Inherited visibilities are private to the current module.
+pub(crate) fn lower_visibility(loc: &LocationMetadata, visibility: &syn::Visibility) -> Visibility {
     match visibility {
         syn::Visibility::Public(_) => Visibility::Pub,
-        _ => Visibility::NonPub,
+        syn::Visibility::Crate(_) => Visibility::InScope(Identity::root(&loc.module_path.crate_)),
+        syn::Visibility::Restricted(restricted) => {
+            let path = UnresolvedPath::from(&*restricted.path);
+            let mut scope = loc.module_path.clone();
+            for (i, seg) in path.path.iter().enumerate() {
+                match &seg[..] {
+                    "crate" if i == 0 => scope = Identity::root(&scope.crate_),
+                    "self" if i == 0 => (),
+                    "super" => {
+                        if let Some(parent) = scope.parent() {
+                            scope = parent;
+                        } else {
+                            warn!("malformed visibility: {:?}", path);
+                        }
+                    }
+                    _ => {
+                        scope.join(seg.clone());
+                    }
+                }
+            }
+            Visibility::InScope(scope)
+        }
+        syn::Visibility::Inherited => Visibility::InScope(loc.module_path.clone()),
     }
 }
 
-
 /// Given a metadata, strip all the `extra_attributes` that go into a TypeMetadata.
 pub fn extract_type_metadata(metadata: &mut Metadata) -> Result<TypeMetadata, LowerError> {
-    // TODO: repr(align(n))
-
     let mut derives = vec![];
-    let mut repr = Repr::Rust;
+    let mut repr = Repr::default();
     metadata.extra_attributes.retain(|attribute| {
         if let Attribute::Meta(Meta::Call { path, args }) = attribute {
             if path == &*DERIVE {
                 for arg in args {
                     if let MetaInner::Meta(Meta::Path(path)) = arg {
                         trace!("derive({:?})", path);
-                        derives.push(Trait {
-                            path: path.clone(),
+                        derives.push(TraitId {
+                            id: Identity::unresolved(path),
                             params: GenericParams::default(),
                             is_maybe: false,
+                            for_lifetimes: vec![],
                         })
                     } else {
                         warn!("malformed #[derive]: {:?}", attribute)
@@ -217,36 +380,10 @@ pub fn extract_type_metadata(metadata: &mut Metadata) -> Result<TypeMetadata, Lo
                 }
                 return false; // remove this element
             } else if path == &*REPR {
-                if args.len() == 1 {
-                    if let MetaInner::Meta(Meta::Path(path)) = &args[0] {
-                        if path == &*REPR_RUST {
-                            // no change
-                        } else if path == &*REPR_C {
-                            repr = Repr::C;
-                        } else if path == &*REPR_TRANSPARENT {
-                            repr = Repr::Transparent;
-                        } else if path == &*REPR_PACKED {
-                            repr = Repr::Packed;
-                        } else if let Some(ident) = path.get_ident() {
-                            repr = Repr::Other(ident.clone());
-                        } else {
-                            warn!("malformed #[repr]: {:?}", attribute);
-                        }
-                    }
-                } else if args.len() == 2 {
-                    if let MetaInner::Meta(Meta::Path(path)) = &args[0] {
-                        if path == &*REPR_C {
-                            if let MetaInner::Meta(Meta::Path(path)) = &args[1] {
-                                if let Some(ident) = path.get_ident() {
-                                    repr = Repr::IntOuterTag(ident.clone());
-                                    return false;
-                                }
-                            }
-                        }
+                for arg in args {
+                    if !lower_repr_arg(&mut repr, arg) {
+                        warn!("malformed #[repr]: {:?}", attribute)
                     }
-                    warn!("malformed #[repr]: {:?}", attribute)
-                } else {
-                    warn!("malformed #[repr]: {:?}", attribute)
                 }
                 return false;
             }
@@ -256,7 +393,53 @@ pub fn extract_type_metadata(metadata: &mut Metadata) -> Result<TypeMetadata, Lo
     Ok(TypeMetadata { derives, repr })
 }
 
-/// Given a metadata, strip all the `extra_attributes` that go into a TypeMetadata.
+/// Add one argument of a `#[repr(...)]` to `repr`. Returns false if it's malformed.
+/// `packed(N)` and `align(N)` need `N` to be a power of two.
+fn lower_repr_arg(repr: &mut Repr, arg: &MetaInner) -> bool {
+    match arg {
+        MetaInner::Meta(Meta::Path(path)) => {
+            if path == &*REPR_RUST {
+                // the default
+            } else if path == &*REPR_C {
+                repr.base = match &repr.base {
+                    ReprBase::Int(int) => ReprBase::IntOuterTag(int.clone()),
+                    _ => ReprBase::C,
+                };
+            } else if path == &*REPR_TRANSPARENT {
+                repr.base = ReprBase::Transparent;
+            } else if path == &*REPR_PACKED {
+                repr.packed = Some(1);
+            } else if let Some(int) = path.get_ident().filter(|ident| is_int(ident)) {
+                repr.base = match &repr.base {
+                    ReprBase::C => ReprBase::IntOuterTag(int.clone()),
+                    _ => ReprBase::Int(int.clone()),
+                };
+            } else {
+                return false;
+            }
+            true
+        }
+        MetaInner::Meta(Meta::Call { path, args }) if args.len() == 1 => {
+            let n = match &args[0] {
+                MetaInner::Literal(literal) => literal
+                    .parse::<syn::LitInt>()
+                    .ok()
+                    .and_then(|literal| literal.base10_parse::<u64>().ok())
+                    .filter(|n| n.is_power_of_two()),
+                _ => None,
+            };
+            match n {
+                Some(n) if path == &*REPR_PACKED => repr.packed = Some(n),
+                Some(n) if path == &*REPR_ALIGN => repr.align = Some(n),
+                _ => return false,
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Given a metadata, strip all the `extra_attributes` that go into a SymbolMetadata.
 pub fn extract_symbol_metadata(metadata: &mut Metadata) -> Result<SymbolMetadata, LowerError> {
     let mut no_mangle = false;
     let mut export_name = None;
@@ -287,18 +470,77 @@ pub fn extract_symbol_metadata(metadata: &mut Metadata) -> Result<SymbolMetadata
     })
 }
 
+/// Lower the `#[link(name = "foo", kind = "static")]` attributes on an `extern` block.
+pub fn lower_links(attrs: &[syn::Attribute]) -> Vec<Link> {
+    let mut result = vec![];
+    for attribute in attrs.iter().map(lower_attribute) {
+        if attribute.path() != &*LINK {
+            continue;
+        }
+        let mut name = None;
+        let mut kind = None;
+        if let Attribute::Meta(Meta::Call { args, .. }) = &attribute {
+            for arg in args {
+                if let MetaInner::Meta(Meta::Assign { path, literal }) = arg {
+                    if path == &*NAME {
+                        name = Some(extract_string(literal));
+                    } else if path == &*KIND {
+                        kind = Some(extract_string(literal));
+                    }
+                }
+            }
+        }
+        if let Some(name) = name {
+            result.push(Link { name, kind });
+        } else {
+            warn!("malformed #[link] attribute: {:?}", attribute);
+        }
+    }
+    result
+}
+
+/// Given the metadata of a foreign item, strip its `#[link_name]` attribute, if present.
+pub fn extract_link_name(metadata: &mut Metadata) -> Option<String> {
+    let mut link_name = None;
+    metadata.extra_attributes.retain(|attribute| {
+        if attribute.path() == &*LINK_NAME {
+            if let Some(name) = attribute.get_assigned_string() {
+                link_name = Some(name);
+                return false;
+            }
+            warn!("malformed #[link_name] attribute: {:?}", attribute);
+        }
+        true
+    });
+    link_name
+}
+
+/// Strip a `#[non_exhaustive]` attribute from a metadata, returning whether it was present.
+pub fn extract_non_exhaustive(metadata: &mut Metadata) -> bool {
+    let mut non_exhaustive = false;
+    metadata.extra_attributes.retain(|attribute| {
+        if attribute.path() == &*NON_EXHAUSTIVE {
+            non_exhaustive = true;
+            return false;
+        }
+        true
+    });
+    non_exhaustive
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::walker::TEST_LOCATION_METADATA;
+    use crate::walker::{TEST_CRATE_DATA, TEST_LOCATION_METADATA};
     use quote::quote;
     use syn::{parse_quote, spanned::Spanned};
-    use tendon_api::attributes::Deprecation;
+    use tendon_api::crates::CrateData;
 
     #[test]
     fn metadata_lowering() {
         let all = lower_metadata(
             &TEST_LOCATION_METADATA,
+            "thing".into(),
             &parse_quote!(pub),
             &[
                 parse_quote!(
@@ -314,6 +556,7 @@ mod tests {
         )
         .unwrap();
         assert_match!(all, Metadata {
+            name,
             visibility: Visibility::Pub,
             docs: Some(docs),
             must_use: Some(must_use),
@@ -321,21 +564,22 @@ mod tests {
             extra_attributes,
             ..
         } => {
+            assert_eq!(name, &Ident::from("thing"));
             assert_eq!(docs, " this is an item that exists");
             assert_eq!(must_use, "use me");
             assert_eq!(since, "0.2.0");
             assert_eq!(note, "don't use me");
 
             assert_match!(extra_attributes[0], Attribute::Meta(Meta::Path(path)) => {
-                assert_eq!(path, &Path::fake("other_attribute"))
+                assert_eq!(path, &UnresolvedPath::fake("other_attribute"))
             });
 
             assert_match!(extra_attributes[1], Attribute::Meta(Meta::Call {
                 path, args
             }) => {
-                assert_eq!(path, &Path::fake("other_attribute_meta"));
+                assert_eq!(path, &UnresolvedPath::fake("other_attribute_meta"));
                 assert_match!(args[0], MetaInner::Meta(Meta::Assign { path, literal }) => {
-                    assert_eq!(path, &Path::fake("thing"));
+                    assert_eq!(path, &UnresolvedPath::fake("thing"));
                     assert_eq!(literal.get_tokens().to_string(), quote!("baz").to_string());
                 });
             });
@@ -343,7 +587,7 @@ mod tests {
             assert_match!(extra_attributes[2], Attribute::Other{
                 path, input
             } => {
-                assert_eq!(path, &Path::fake("other_attribute_weird"));
+                assert_eq!(path, &UnresolvedPath::fake("other_attribute_weird"));
                 assert_eq!(input.to_string(), quote!(2 + 2 / 3 - 4).to_string());
             });
         });
@@ -351,6 +595,7 @@ mod tests {
         // shouldn't panic
         let funky = lower_metadata(
             &TEST_LOCATION_METADATA,
+            "funky".into(),
             &parse_quote!(pub(crate)),
             &[
                 parse_quote!(#[docs(bees = "superior")]),
@@ -362,8 +607,181 @@ mod tests {
         )
         .unwrap();
 
-        assert_eq!(funky.visibility, Visibility::NonPub);
+        assert_eq!(
+            funky.visibility,
+            Visibility::InScope(Identity::root(&TEST_LOCATION_METADATA.module_path.crate_))
+        );
+    }
+
+    #[test]
+    fn doc_attribute_lowering() {
+        let temp_dir = tempdir::TempDir::new("tendon_test").unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(temp_dir.path().join("README.md"), "# Thing\n\nDoes things.").unwrap();
+        let loc = LocationMetadata {
+            source_file: src.join("lib.rs"),
+            ..TEST_LOCATION_METADATA.clone()
+        };
+
+        let metadata = lower_metadata(
+            &loc,
+            "thing".into(),
+            &parse_quote!(pub),
+            &[
+                parse_quote!(#[doc = include_str!("../README.md")]),
+                parse_quote!(
+                    /// More docs.
+                ),
+                parse_quote!(#[doc(hidden, alias = "stuff")]),
+                parse_quote!(#[doc(alias("widget", "gizmo"), no_inline)]),
+                parse_quote!(#[doc = include_str!("../MISSING.md")]),
+            ],
+            quote!(_).span(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            metadata.docs,
+            Some("# Thing\n\nDoes things.\n More docs.".into())
+        );
+        assert!(metadata.doc_hidden);
+        assert_eq!(metadata.doc_inline, Some(DocInline::NoInline));
+        assert_eq!(metadata.doc_aliases, vec!["stuff", "widget", "gizmo"]);
+        assert!(metadata.extra_attributes.is_empty());
+
+        let plain = lower_metadata(
+            &loc,
+            "plain".into(),
+            &parse_quote!(pub),
+            &[parse_quote!(#[doc(inline)])],
+            quote!(_).span(),
+        )
+        .unwrap();
+        assert_eq!(plain.docs, None);
+        assert!(!plain.doc_hidden);
+        assert_eq!(plain.doc_inline, Some(DocInline::Inline));
+
+        // `CARGO_MANIFEST_DIR` is the directory the crate's manifest is in
+        let crate_data = CrateData {
+            manifest_path: temp_dir.path().join("Cargo.toml"),
+            ..TEST_CRATE_DATA.clone()
+        };
+        let loc = LocationMetadata {
+            crate_data: &crate_data,
+            ..loc
+        };
+        let manifest = lower_metadata(
+            &loc,
+            "manifest".into(),
+            &parse_quote!(pub),
+            &[
+                parse_quote!(#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]),
+                parse_quote!(#[doc = include_str!(concat!(env!("OUT_DIR"), "/generated.md"))]),
+            ],
+            quote!(_).span(),
+        )
+        .unwrap();
+        assert_eq!(manifest.docs, Some("# Thing\n\nDoes things.".into()));
     }
-}
 
-*/
+    #[test]
+    fn repr_lowering() {
+        let repr = |attrs: &[syn::Attribute]| {
+            let mut metadata = lower_metadata(
+                &TEST_LOCATION_METADATA,
+                "thing".into(),
+                &parse_quote!(pub),
+                attrs,
+                quote!(_).span(),
+            )
+            .unwrap();
+            let repr = extract_type_metadata(&mut metadata).unwrap().repr;
+            assert!(metadata.extra_attributes.is_empty());
+            repr
+        };
+
+        assert_eq!(repr(&[]), Repr::default());
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(C, packed(2))])]),
+            Repr {
+                base: ReprBase::C,
+                packed: Some(2),
+                align: None
+            }
+        );
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(packed)])]),
+            Repr {
+                base: ReprBase::Rust,
+                packed: Some(1),
+                align: None
+            }
+        );
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(align(16))])]),
+            Repr {
+                base: ReprBase::Rust,
+                packed: None,
+                align: Some(16)
+            }
+        );
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(u8, C)])]).base,
+            ReprBase::IntOuterTag("u8".into())
+        );
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(C)]), parse_quote!(#[repr(align(8))])]),
+            Repr {
+                base: ReprBase::C,
+                packed: None,
+                align: Some(8)
+            }
+        );
+        assert_eq!(
+            repr(&[parse_quote!(#[repr(C, align(0), packed(3))])]),
+            Repr {
+                base: ReprBase::C,
+                packed: None,
+                align: None
+            }
+        );
+    }
+
+    #[test]
+    fn visibility_lowering() {
+        let loc = LocationMetadata {
+            module_path: TEST_LOCATION_METADATA
+                .module_path
+                .clone_join_seq(&["a", "b"]),
+            ..TEST_LOCATION_METADATA.clone()
+        };
+        let root = Identity::root(&loc.module_path.crate_);
+
+        assert_eq!(lower_visibility(&loc, &parse_quote!(pub)), Visibility::Pub);
+        assert_eq!(
+            lower_visibility(&loc, &parse_quote!(pub(crate))),
+            Visibility::InScope(root.clone())
+        );
+        assert_eq!(
+            lower_visibility(&loc, &parse_quote!(crate)),
+            Visibility::InScope(root.clone())
+        );
+        assert_eq!(
+            lower_visibility(&loc, &parse_quote!(pub(self))),
+            Visibility::InScope(loc.module_path.clone())
+        );
+        assert_eq!(
+            lower_visibility(&loc, &parse_quote!(pub(super))),
+            Visibility::InScope(root.clone_join("a"))
+        );
+        assert_eq!(
+            lower_visibility(&loc, &parse_quote!(pub(in crate::c))),
+            Visibility::InScope(root.clone_join("c"))
+        );
+        assert_eq!(
+            lower_visibility(&loc, &syn::Visibility::Inherited),
+            Visibility::InScope(loc.module_path.clone())
+        );
+    }
+}
@@ -0,0 +1,467 @@
+//! Doc comment lowering.
+//!
+//! We don't run a real markdown parser over docs, just enough of one to find intra-doc links,
+//! section headings and code blocks.
+//!
+//! Links are `[text](dest)`, shortcut `[text]` links, and `[label]: dest` definitions, outside of
+//! code blocks and code spans. Destinations that don't parse as paths (urls, mostly) are skipped.
+//! Links are resolved once the crate is walked, see `resolver::doc_links`.
+
+use tendon_api::{
+    attributes::{DocLink, DocSections, Doctest},
+    paths::UnresolvedPath,
+    scopes::NamespaceId,
+};
+
+/// Find the intra-doc links in some docs.
+pub(crate) fn lower_doc_links(docs: &str) -> Vec<DocLink> {
+    link_destinations(docs)
+        .into_iter()
+        .filter_map(lower_doc_link)
+        .collect()
+}
+
+/// Lower a link destination, if it names an item.
+pub(crate) fn lower_doc_link(dest: &str) -> Option<DocLink> {
+    let mut path = dest.trim().trim_matches('`');
+    let mut namespace = None;
+
+    if let Some(at) = path.find('@') {
+        namespace = Some(disambiguator_namespace(&path[..at])?);
+        path = &path[at + 1..];
+    }
+    for &(suffix, suffix_namespace) in &[
+        ("!()", NamespaceId::Macro),
+        ("![]", NamespaceId::Macro),
+        ("!{}", NamespaceId::Macro),
+        ("!", NamespaceId::Macro),
+        ("()", NamespaceId::Symbol),
+    ] {
+        if path.ends_with(suffix) {
+            path = &path[..path.len() - suffix.len()];
+            namespace = namespace.or(Some(suffix_namespace));
+            break;
+        }
+    }
+
+    // generic arguments, `[Vec<T>]`, are dropped here
+    let path = syn::parse_str::<syn::Path>(path).ok()?;
+    Some(DocLink {
+        text: dest.into(),
+        path: UnresolvedPath::from(&path),
+        namespace,
+        target: None,
+    })
+}
+
+/// The namespace named by a disambiguator, `struct` in `[struct@Foo]`.
+fn disambiguator_namespace(disambiguator: &str) -> Option<NamespaceId> {
+    match disambiguator {
+        "struct" | "enum" | "union" | "trait" | "type" | "prim" | "primitive" => {
+            Some(NamespaceId::Type)
+        }
+        "fn" | "function" | "method" | "const" | "constant" | "static" | "value" | "field"
+        | "variant" => Some(NamespaceId::Symbol),
+        "mod" | "module" => Some(NamespaceId::Scope),
+        "macro" | "derive" | "attr" => Some(NamespaceId::Macro),
+        _ => None,
+    }
+}
+
+/// The destinations of every link in some docs, in order.
+fn link_destinations(docs: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut fence = None;
+
+    for line in docs.lines() {
+        let trimmed = line.trim();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some((marker, _)) = opening_fence(trimmed) {
+            fence = Some(marker);
+            continue;
+        }
+
+        // a definition, `[label]: dest`
+        if trimmed.starts_with('[') {
+            if let Some(end) = trimmed.find("]:") {
+                if let Some(dest) = trimmed[end + 2..].split_whitespace().next() {
+                    result.push(dest);
+                }
+                continue;
+            }
+        }
+        inline_destinations(line, &mut result);
+    }
+    result
+}
+
+/// The destinations of the links in a line.
+/// Reference links, `[text][label]`, are found through their definitions instead.
+fn inline_destinations<'a>(line: &'a str, out: &mut Vec<&'a str>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let ticks = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let marker = &line[i..i + ticks];
+                i += ticks;
+                // an unclosed code span is just backticks
+                if let Some(end) = line[i..].find(marker) {
+                    i += end + ticks;
+                }
+            }
+            b'[' => {
+                let close = match closing(&line[i..], b'[', b']') {
+                    Some(close) => i + close,
+                    None => return,
+                };
+                let is_image = i > 0 && bytes[i - 1] == b'!';
+                let text = &line[i + 1..close];
+                let rest = &line[close + 1..];
+                i = close + 1;
+
+                if rest.starts_with('(') {
+                    if let Some(end) = closing(rest, b'(', b')') {
+                        // drop the title, `[text](dest "title")`
+                        if let Some(dest) = rest[1..end].split_whitespace().next() {
+                            if !is_image {
+                                out.push(dest);
+                            }
+                        }
+                        i += end + 1;
+                        continue;
+                    }
+                } else if rest.starts_with('[') {
+                    if let Some(end) = rest.find(']') {
+                        // `[text][]` uses `text` as its label
+                        if end == 1 && !is_image {
+                            out.push(text);
+                        }
+                        i += end + 1;
+                        continue;
+                    }
+                }
+                if !is_image {
+                    out.push(text);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Split docs into their standard sections, and pick out their doc-tests.
+pub(crate) fn lower_doc_sections(docs: &str) -> DocSections {
+    let lines = unindent(docs);
+    let mut result = DocSections::default();
+
+    // (the section being collected, its heading level, its lines)
+    let mut section: Option<(&'static str, usize, Vec<&str>)> = None;
+    // (the fence, the doctest being collected if the block is Rust)
+    let mut fence: Option<(&str, Option<Doctest>)> = None;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some((marker, doctest)) = &mut fence {
+            if trimmed.starts_with(*marker) {
+                if let Some(doctest) = doctest.take() {
+                    result.doctests.push(doctest);
+                }
+                fence = None;
+            } else if let Some(doctest) = doctest {
+                doctest.code.push_str(doctest_line(line));
+                doctest.code.push('\n');
+            }
+        } else if let Some((marker, info)) = opening_fence(trimmed) {
+            let doctest = doctest_attributes(info).map(|attributes| Doctest {
+                code: String::new(),
+                attributes,
+            });
+            fence = Some((marker, doctest));
+        } else if let Some((level, title)) = heading(trimmed) {
+            match &section {
+                Some((_, section_level, _)) if level > *section_level => (),
+                _ => {
+                    if let Some((name, _, lines)) = section.take() {
+                        set_section(&mut result, name, &lines);
+                    }
+                    section = section_name(title).map(|name| (name, level, vec![]));
+                    continue;
+                }
+            }
+        }
+
+        if let Some((_, _, lines)) = &mut section {
+            lines.push(line);
+        }
+    }
+    if let Some((name, _, lines)) = section {
+        set_section(&mut result, name, &lines);
+    }
+    result
+}
+
+/// The lines of some docs, with the indentation they all share removed.
+/// `///` comments almost always start with a space.
+fn unindent(docs: &str) -> Vec<&str> {
+    // counted in chars, not bytes: lines can start with multibyte whitespace
+    let indent = docs
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    docs.lines()
+        .map(|line| {
+            let start = line
+                .char_indices()
+                .take_while(|(_, c)| c.is_whitespace())
+                .nth(indent)
+                .map(|(i, _)| i);
+            match start {
+                Some(start) => &line[start..],
+                // blank, or no more whitespace than the indentation
+                None => line.trim_start(),
+            }
+        })
+        .collect()
+}
+
+/// If a line opens a fenced code block, its fence and info string.
+fn opening_fence(trimmed: &str) -> Option<(&'static str, &str)> {
+    for &marker in &["```", "~~~"] {
+        if trimmed.starts_with(marker) {
+            return Some((
+                marker,
+                trimmed.trim_start_matches(marker.as_bytes()[0] as char),
+            ));
+        }
+    }
+    None
+}
+
+/// The doc-test attributes in a code block's info string, or `None` if it isn't Rust.
+/// Like rustdoc, we take blocks with no language, or only doc-test attributes, to be Rust.
+fn doctest_attributes(info: &str) -> Option<Vec<String>> {
+    let mut attributes = vec![];
+    for token in info
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+    {
+        match token {
+            "rust" => (),
+            "ignore" | "should_panic" | "no_run" | "compile_fail" | "test_harness"
+            | "allow_fail" => attributes.push(token.to_string()),
+            _ if token.starts_with("edition") || token.starts_with("ignore-") => {
+                attributes.push(token.to_string())
+            }
+            _ => return None,
+        }
+    }
+    Some(attributes)
+}
+
+/// A line of a doc-test, as compiled: `# ` hides a line from the rendered docs, not the test.
+fn doctest_line(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    if trimmed == "#" {
+        ""
+    } else if let Some(hidden) = trimmed.strip_prefix("# ") {
+        hidden
+    } else if trimmed.starts_with("##") {
+        // an escaped `#`, for attributes and the like
+        &trimmed[1..]
+    } else {
+        line
+    }
+}
+
+/// If a line is an ATX heading, `## Title`, its level and title.
+fn heading(trimmed: &str) -> Option<(usize, &str)> {
+    let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+    let title = &trimmed[level..];
+    if level == 0 || level > 6 || !(title.is_empty() || title.starts_with(' ')) {
+        return None;
+    }
+    Some((level, title.trim().trim_end_matches('#').trim()))
+}
+
+/// The standard section a heading starts, if any.
+fn section_name(title: &str) -> Option<&'static str> {
+    match &title.to_lowercase()[..] {
+        "safety" => Some("safety"),
+        "panics" => Some("panics"),
+        "errors" => Some("errors"),
+        "examples" | "example" => Some("examples"),
+        _ => None,
+    }
+}
+
+fn set_section(result: &mut DocSections, name: &str, lines: &[&str]) {
+    let text = lines.join("\n").trim_matches('\n').to_string();
+    let section = match name {
+        "safety" => &mut result.safety,
+        "panics" => &mut result.panics,
+        "errors" => &mut result.errors,
+        "examples" => &mut result.examples,
+        _ => unreachable!(),
+    };
+    // a repeated heading continues the section
+    *section = Some(match section.take() {
+        Some(previous) => format!("{}\n\n{}", previous, text),
+        None => text,
+    });
+}
+
+/// The index of the delimiter closing the one `s` starts with, skipping code spans.
+fn closing(s: &str, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_code = false;
+    for (i, b) in s.bytes().enumerate() {
+        if b == b'`' {
+            in_code = !in_code;
+        } else if in_code {
+            continue;
+        } else if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(docs: &str) -> Vec<(String, Option<NamespaceId>)> {
+        lower_doc_links(docs)
+            .into_iter()
+            .map(|link| (format!("{:?}", link.path), link.namespace))
+            .collect()
+    }
+
+    #[test]
+    fn doc_link_lowering() {
+        assert_eq!(
+            links(" Returns a [Vec], or a [`crate::x::Y`] if you ask nicely."),
+            vec![("Vec".into(), None), ("crate::x::Y".into(), None)]
+        );
+        assert_eq!(
+            links(" See [the docs](Foo::bar()) and [`Option<T>`](::std::option::Option)."),
+            vec![
+                ("Foo::bar".into(), Some(NamespaceId::Symbol)),
+                ("::std::option::Option".into(), None)
+            ]
+        );
+        assert_eq!(
+            links(" [struct@Foo], [fn@foo], [mod@foo], [`vec!`], [derive@Debug], [bogus@Foo]"),
+            vec![
+                ("Foo".into(), Some(NamespaceId::Type)),
+                ("foo".into(), Some(NamespaceId::Symbol)),
+                ("foo".into(), Some(NamespaceId::Scope)),
+                ("vec".into(), Some(NamespaceId::Macro)),
+                ("Debug".into(), Some(NamespaceId::Macro)),
+            ]
+        );
+        assert_eq!(
+            links(" Uses [a][label] and [Bar][].\n\n [label]: crate::a  \n"),
+            vec![("Bar".into(), None), ("crate::a".into(), None)]
+        );
+    }
+
+    #[test]
+    fn doc_section_lowering() {
+        let docs = " Reads a value.\n\
+                    \n\
+                    \x20# Safety\n\
+                    \n\
+                    \x20`ptr` must be valid.\n\
+                    \n\
+                    \x20# Panics\n\
+                    \x20If `ptr` is unaligned.\n\
+                    \x20## Details\n\
+                    \x20Debug builds only.\n\
+                    \x20# Examples\n\
+                    \n\
+                    \x20```\n\
+                    \x20# use std::ptr;\n\
+                    \x20let x = 1; // # not hidden\n\
+                    \x20##[derive(Debug)] struct Y;\n\
+                    \x20```\n\
+                    \n\
+                    \x20```text\n\
+                    \x20# Not a heading\n\
+                    \x20```\n\
+                    \x20```no_run,edition2018\n\
+                    \x20loop {}\n\
+                    \x20```\n\
+                    \x20# See also\n\
+                    \x20Other things.";
+        let sections = lower_doc_sections(docs);
+
+        assert_eq!(sections.safety, Some("`ptr` must be valid.".into()));
+        assert_eq!(
+            sections.panics,
+            Some("If `ptr` is unaligned.\n## Details\nDebug builds only.".into())
+        );
+        assert_eq!(sections.errors, None);
+        assert_eq!(
+            sections.examples,
+            Some(
+                "```\n# use std::ptr;\nlet x = 1; // # not hidden\n##[derive(Debug)] struct Y;\n\
+                 ```\n\n```text\n# Not a heading\n```\n```no_run,edition2018\nloop {}\n```"
+                    .into()
+            )
+        );
+        assert_eq!(
+            sections.doctests,
+            vec![
+                Doctest {
+                    code: "use std::ptr;\nlet x = 1; // # not hidden\n#[derive(Debug)] struct Y;\n"
+                        .into(),
+                    attributes: vec![],
+                },
+                Doctest {
+                    code: "loop {}\n".into(),
+                    attributes: vec!["no_run".into(), "edition2018".into()],
+                }
+            ]
+        );
+        assert!(sections.doctests[1].has_attribute("no_run"));
+
+        assert_eq!(
+            lower_doc_sections(" Nothing to see here."),
+            DocSections::default()
+        );
+
+        // a no-break space is whitespace, and more than one byte
+        let sections = lower_doc_sections("\u{a0}Intro.\n # Panics\n\u{a0}\u{a0}Always.");
+        assert_eq!(sections.panics, Some("\u{a0}Always.".into()));
+    }
+
+    #[test]
+    fn doc_links_skip_code_and_urls() {
+        assert_eq!(
+            links(
+                " An [x](https://example.com), ![logo](Logo), `a[i]`, and [1].\n\
+                 \n\
+                 ```\n\
+                 let x = [Vec::new()];\n\
+                 ```\n\
+                 [Done]"
+            ),
+            vec![("Done".into(), None)]
+        );
+    }
+}
@@ -0,0 +1,517 @@
+//! Evaluation of simple constant expressions.
+//!
+//! We only handle what shows up in the initializers of public consts in practice: literals,
+//! arithmetic, comparisons, casts between primitives, and references to other consts.
+//! Anything else evaluates to `None`, and the const keeps only its tokens.
+//!
+//! References to other consts are looked up through a `ConstLookup`; see
+//! `resolver::const_values` for how consts referring to each other are evaluated.
+
+use tendon_api::{
+    expressions::ConstValue,
+    identities::{PathType, TypeId},
+    paths::{Ident, UnresolvedPath},
+};
+
+/// Looks up the value of another constant, referenced by path.
+pub(crate) type ConstLookup<'a> = dyn FnMut(&UnresolvedPath) -> Option<ConstValue> + 'a;
+
+/// If a type is a primitive we can evaluate (`u8`, `f32`, `bool`, ...), get its name.
+pub(crate) fn primitive_name(type_: &TypeId) -> Option<Ident> {
+    match type_ {
+        TypeId::Path(PathType { path, params }) if params.is_empty() => {
+            let path = path.as_unresolved()?;
+            let name = path.get_ident()?;
+            if is_int(name) || is_float(name) || &name[..] == "bool" || &name[..] == "char" {
+                Some(name.clone())
+            } else {
+                None
+            }
+        }
+        TypeId::Reference(reference) => match &*reference.type_ {
+            TypeId::Path(PathType { path, .. }) => {
+                let path = path.as_unresolved()?;
+                if path.get_ident().map(|name| &name[..] == "str") == Some(true) {
+                    Some(Ident::from("str"))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluate a constant expression.
+/// `expected` is the primitive type the expression should evaluate to, if known; it's used to
+/// type unsuffixed literals.
+pub(crate) fn evaluate_const(
+    expr: &syn::Expr,
+    expected: Option<&Ident>,
+    lookup: &mut ConstLookup,
+) -> Option<ConstValue> {
+    match expr {
+        syn::Expr::Lit(lit) => evaluate_lit(&lit.lit, expected),
+        syn::Expr::Paren(paren) => evaluate_const(&paren.expr, expected, lookup),
+        syn::Expr::Group(group) => evaluate_const(&group.expr, expected, lookup),
+        syn::Expr::Reference(reference) => match evaluate_const(&reference.expr, None, lookup)? {
+            ConstValue::Str(s) => Some(ConstValue::Str(s)),
+            _ => None,
+        },
+        syn::Expr::Path(path) if path.qself.is_none() => lookup(&UnresolvedPath::from(&path.path)),
+        syn::Expr::Unary(unary) => {
+            if let (
+                syn::UnOp::Neg(_),
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(int),
+                    ..
+                }),
+            ) = (&unary.op, &*unary.expr)
+            {
+                // `-128i8` is in range, `128i8` isn't
+                return evaluate_int(int, expected, true);
+            }
+            let value = evaluate_const(&unary.expr, expected, lookup)?;
+            match (&unary.op, value) {
+                (syn::UnOp::Neg(_), ConstValue::Int(i, type_)) => {
+                    check_range(i.checked_neg()?, type_)
+                }
+                (syn::UnOp::Neg(_), ConstValue::Float(f, type_)) => {
+                    Some(ConstValue::Float(-f, type_))
+                }
+                (syn::UnOp::Not(_), ConstValue::Int(i, type_)) => {
+                    Some(ConstValue::Int(truncate(!i, &type_)?, type_))
+                }
+                (syn::UnOp::Not(_), ConstValue::Bool(b)) => Some(ConstValue::Bool(!b)),
+                _ => None,
+            }
+        }
+        syn::Expr::Binary(binary) => evaluate_binary(binary, expected, lookup),
+        syn::Expr::Cast(cast) => {
+            let target = primitive_name(&super::types::lower_type(&cast.ty).ok()?)?;
+            let value = evaluate_const(&cast.expr, None, lookup)?;
+            evaluate_cast(value, target)
+        }
+        _ => None,
+    }
+}
+
+fn evaluate_lit(lit: &syn::Lit, expected: Option<&Ident>) -> Option<ConstValue> {
+    match lit {
+        syn::Lit::Int(int) => evaluate_int(int, expected, false),
+        syn::Lit::Float(float) => {
+            let type_ = if float.suffix() != "" {
+                Ident::from(float.suffix())
+            } else {
+                expected
+                    .filter(|e| is_float(e))
+                    .cloned()
+                    .unwrap_or_else(|| Ident::from("f64"))
+            };
+            let value = if &type_[..] == "f32" {
+                float.base10_parse::<f32>().ok()? as f64
+            } else {
+                float.base10_parse::<f64>().ok()?
+            };
+            Some(ConstValue::Float(value, type_))
+        }
+        syn::Lit::Bool(bool_) => Some(ConstValue::Bool(bool_.value)),
+        syn::Lit::Char(char_) => Some(ConstValue::Char(char_.value())),
+        syn::Lit::Str(str_) => Some(ConstValue::Str(str_.value())),
+        syn::Lit::Byte(byte) => Some(ConstValue::Int(byte.value() as i128, Ident::from("u8"))),
+        _ => None,
+    }
+}
+
+fn evaluate_int(int: &syn::LitInt, expected: Option<&Ident>, negate: bool) -> Option<ConstValue> {
+    let type_ = if int.suffix() != "" {
+        Ident::from(int.suffix())
+    } else {
+        expected
+            .filter(|e| is_int(e))
+            .cloned()
+            .unwrap_or_else(|| Ident::from("i32"))
+    };
+    let value = int.base10_parse::<i128>().ok()?;
+    check_range(if negate { -value } else { value }, type_)
+}
+
+fn evaluate_binary(
+    binary: &syn::ExprBinary,
+    expected: Option<&Ident>,
+    lookup: &mut ConstLookup,
+) -> Option<ConstValue> {
+    use syn::BinOp::*;
+
+    let is_comparison = match binary.op {
+        Eq(_) | Ne(_) | Lt(_) | Le(_) | Gt(_) | Ge(_) => true,
+        _ => false,
+    };
+    let is_shift = match binary.op {
+        Shl(_) | Shr(_) => true,
+        _ => false,
+    };
+    let expected = if is_comparison { None } else { expected };
+    let left = evaluate_const(&binary.left, expected, lookup)?;
+    // shift amounts don't have to match the shifted type
+    let right_expected = match (&left, is_shift) {
+        (_, true) => None,
+        (ConstValue::Int(_, type_), _) | (ConstValue::Float(_, type_), _) => Some(type_.clone()),
+        _ => None,
+    };
+    let right = evaluate_const(&binary.right, right_expected.as_ref(), lookup)?;
+
+    match (left, right) {
+        (ConstValue::Int(l, type_), ConstValue::Int(r, _)) => {
+            let result = match binary.op {
+                Add(_) => l.checked_add(r)?,
+                Sub(_) => l.checked_sub(r)?,
+                Mul(_) => l.checked_mul(r)?,
+                Div(_) => l.checked_div(r)?,
+                Rem(_) => l.checked_rem(r)?,
+                BitAnd(_) => l & r,
+                BitOr(_) => l | r,
+                BitXor(_) => l ^ r,
+                // shifting by the type's width or more is an error, not zero
+                Shl(_) | Shr(_) if r < 0 || r >= shift_bits(&type_)? as i128 => return None,
+                Shl(_) => truncate(l << r, &type_)?,
+                Shr(_) => l >> r,
+                Eq(_) => return Some(ConstValue::Bool(l == r)),
+                Ne(_) => return Some(ConstValue::Bool(l != r)),
+                Lt(_) => return Some(ConstValue::Bool(l < r)),
+                Le(_) => return Some(ConstValue::Bool(l <= r)),
+                Gt(_) => return Some(ConstValue::Bool(l > r)),
+                Ge(_) => return Some(ConstValue::Bool(l >= r)),
+                _ => return None,
+            };
+            check_range(result, type_)
+        }
+        (ConstValue::Float(l, type_), ConstValue::Float(r, _)) => {
+            let result = match binary.op {
+                Add(_) => l + r,
+                Sub(_) => l - r,
+                Mul(_) => l * r,
+                Div(_) => l / r,
+                Rem(_) => l % r,
+                Eq(_) => return Some(ConstValue::Bool(l == r)),
+                Ne(_) => return Some(ConstValue::Bool(l != r)),
+                Lt(_) => return Some(ConstValue::Bool(l < r)),
+                Le(_) => return Some(ConstValue::Bool(l <= r)),
+                Gt(_) => return Some(ConstValue::Bool(l > r)),
+                Ge(_) => return Some(ConstValue::Bool(l >= r)),
+                _ => return None,
+            };
+            Some(ConstValue::Float(round_float(result, &type_), type_))
+        }
+        (ConstValue::Bool(l), ConstValue::Bool(r)) => Some(ConstValue::Bool(match binary.op {
+            And(_) | BitAnd(_) => l && r,
+            Or(_) | BitOr(_) => l || r,
+            BitXor(_) | Ne(_) => l != r,
+            Eq(_) => l == r,
+            _ => return None,
+        })),
+        _ => None,
+    }
+}
+
+/// Evaluate `value as target`.
+fn evaluate_cast(value: ConstValue, target: Ident) -> Option<ConstValue> {
+    let as_int = |i: i128| -> Option<ConstValue> {
+        if is_int(&target) {
+            Some(ConstValue::Int(truncate(i, &target)?, target.clone()))
+        } else if is_float(&target) {
+            Some(ConstValue::Float(
+                round_float(i as f64, &target),
+                target.clone(),
+            ))
+        } else {
+            None
+        }
+    };
+    match value {
+        // only `u8 as char` is legal
+        ConstValue::Int(i, type_) if &target[..] == "char" => {
+            if &type_[..] == "u8" {
+                Some(ConstValue::Char(i as u8 as char))
+            } else {
+                None
+            }
+        }
+        ConstValue::Int(i, _) => as_int(i),
+        ConstValue::Bool(b) => as_int(b as i128),
+        ConstValue::Char(c) if &target[..] == "char" => Some(ConstValue::Char(c)),
+        ConstValue::Char(c) => as_int(c as i128),
+        ConstValue::Float(f, _) => {
+            if is_float(&target) {
+                Some(ConstValue::Float(round_float(f, &target), target))
+            } else if is_int(&target) {
+                // float -> int casts saturate
+                let (min, max) = cast_range(&target)?;
+                let i = if f.is_nan() {
+                    0
+                } else if f <= min as f64 || f >= max as f64 {
+                    if !is_full_range(&target) {
+                        return None;
+                    }
+                    if f <= min as f64 {
+                        min
+                    } else {
+                        max
+                    }
+                } else {
+                    f as i128
+                };
+                Some(ConstValue::Int(i, target))
+            } else {
+                None
+            }
+        }
+        ConstValue::Str(_) => None,
+    }
+}
+
+pub(crate) fn is_int(name: &str) -> bool {
+    int_range(name).is_some()
+}
+
+fn is_float(name: &str) -> bool {
+    name == "f32" || name == "f64"
+}
+
+/// Round a float to its type. Floats are stored as `f64`, so `f32` results have to be rounded
+/// after each operation to match what rustc computes.
+fn round_float(value: f64, type_: &str) -> f64 {
+    if type_ == "f32" {
+        value as f32 as f64
+    } else {
+        value
+    }
+}
+
+/// The number of bits in a fixed-size integer type, and whether it's signed.
+/// `None` for pointer-sized integers, whose width depends on the target.
+fn int_bits(name: &str) -> Option<(u32, bool)> {
+    Some(match name {
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        "i64" => (64, true),
+        "i128" => (128, true),
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "u64" => (64, false),
+        "u128" => (128, false),
+        _ => return None,
+    })
+}
+
+/// The number of bits a value of an integer type can be shifted by.
+/// For pointer-sized integers, that's the widest pointer width; narrower targets reject the
+/// shift outright.
+fn shift_bits(name: &str) -> Option<u32> {
+    match name {
+        "usize" | "isize" => Some(64),
+        _ => int_bits(name).map(|(bits, _)| bits),
+    }
+}
+
+/// The minimum and maximum values of an integer type that we can evaluate.
+///
+/// We don't know the target's pointer width, so `usize` and `isize` are checked against the
+/// widest, 64 bits: a value that doesn't fit on a narrower target is a compile error there, not
+/// a different value. Values are stored as `i128`, so `u128` values above `i128::MAX` are out of
+/// range too. Anything out of range evaluates to `None` instead of a guess.
+fn int_range(name: &str) -> Option<(i128, i128)> {
+    let (bits, signed) = match name {
+        "usize" => (64, false),
+        "isize" => (64, true),
+        _ => int_bits(name)?,
+    };
+    Some(bits_range(bits, signed))
+}
+
+/// Like `int_range`, but for the results of `as` casts, which wrap or saturate at the target's
+/// width instead of failing. For `usize` and `isize` that's limited to values that fit in 32 bits,
+/// which cast the same on every target we care about.
+fn cast_range(name: &str) -> Option<(i128, i128)> {
+    match name {
+        "usize" => Some(bits_range(32, false)),
+        "isize" => Some(bits_range(32, true)),
+        _ => int_range(name),
+    }
+}
+
+fn bits_range(bits: u32, signed: bool) -> (i128, i128) {
+    match (bits, signed) {
+        (128, true) => (i128::min_value(), i128::max_value()),
+        (128, false) => (0, i128::max_value()),
+        (bits, true) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        (bits, false) => (0, (1 << bits) - 1),
+    }
+}
+
+/// Whether `int_range` is the full range of an integer type, so that saturating to its ends is
+/// exact.
+fn is_full_range(name: &str) -> bool {
+    match int_bits(name) {
+        Some((bits, signed)) => bits < 128 || signed,
+        None => false,
+    }
+}
+
+/// An integer value, if it fits in `type_`.
+pub(crate) fn check_range(value: i128, type_: Ident) -> Option<ConstValue> {
+    let (min, max) = int_range(&type_)?;
+    if value >= min && value <= max {
+        Some(ConstValue::Int(value, type_))
+    } else {
+        None
+    }
+}
+
+/// Truncate an integer to its type, like `as` does.
+/// `None` if the result depends on the pointer width, or doesn't fit in an `i128`.
+fn truncate(value: i128, type_: &str) -> Option<i128> {
+    let (bits, signed) = match int_bits(type_) {
+        Some(bits) => bits,
+        None => {
+            // pointer-sized: only known if nothing gets cut off
+            let (min, max) = cast_range(type_)?;
+            return if value >= min && value <= max {
+                Some(value)
+            } else {
+                None
+            };
+        }
+    };
+    if bits == 128 {
+        return if signed || value >= 0 {
+            Some(value)
+        } else {
+            None
+        };
+    }
+    let mask = (1i128 << bits) - 1;
+    let truncated = value & mask;
+    if signed && truncated >> (bits - 1) == 1 {
+        Some(truncated - (1 << bits))
+    } else {
+        Some(truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str, expected: &str) -> Option<ConstValue> {
+        let mut lookup = |path: &UnresolvedPath| {
+            if path == &UnresolvedPath::fake("OTHER") {
+                Some(ConstValue::Int(7, Ident::from("u8")))
+            } else {
+                None
+            }
+        };
+        evaluate_const(
+            &syn::parse_str(expr).unwrap(),
+            Some(&Ident::from(expected)),
+            &mut lookup,
+        )
+    }
+
+    fn int(i: i128, type_: &str) -> Option<ConstValue> {
+        Some(ConstValue::Int(i, Ident::from(type_)))
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(eval("3", "u8"), int(3, "u8"));
+        assert_eq!(eval("3i64", "u8"), int(3, "i64"));
+        assert_eq!(eval("0x10", "u32"), int(16, "u32"));
+        assert_eq!(eval("-128", "i8"), int(-128, "i8"));
+        assert_eq!(eval("256", "u8"), None);
+        assert_eq!(
+            eval("1.5", "f32"),
+            Some(ConstValue::Float(1.5, "f32".into()))
+        );
+        assert_eq!(eval("true", "bool"), Some(ConstValue::Bool(true)));
+        assert_eq!(eval("'x'", "char"), Some(ConstValue::Char('x')));
+        assert_eq!(eval("\"hi\"", "str"), Some(ConstValue::Str("hi".into())));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(eval("1 + 2 * 3", "u32"), int(7, "u32"));
+        assert_eq!(eval("(1 << 4) | 1", "u16"), int(17, "u16"));
+        assert_eq!(eval("!0", "u8"), int(255, "u8"));
+        assert_eq!(eval("!0", "i8"), int(-1, "i8"));
+        assert_eq!(eval("1 - 2", "u8"), None);
+        assert_eq!(eval("1 / 0", "u8"), None);
+        assert_eq!(eval("1 << 7", "i8"), int(-128, "i8"));
+        assert_eq!(eval("1 << 8", "u8"), None);
+        assert_eq!(eval("1 >> 32", "u32"), None);
+        assert_eq!(eval("1 << -1", "u32"), None);
+        assert_eq!(
+            eval("2.0 * 1.5", "f64"),
+            Some(ConstValue::Float(3.0, "f64".into()))
+        );
+        assert_eq!(
+            eval("0.1 + 0.2", "f32"),
+            Some(ConstValue::Float((0.1f32 + 0.2f32) as f64, "f32".into()))
+        );
+        assert_eq!(
+            eval("16777217 as f32", "f32"),
+            Some(ConstValue::Float(16777216.0, "f32".into()))
+        );
+        assert_eq!(eval("1 < 2 && true", "bool"), Some(ConstValue::Bool(true)));
+    }
+
+    #[test]
+    fn casts_and_references() {
+        assert_eq!(eval("300 as u8", "u8"), int(44, "u8"));
+        assert_eq!(eval("-1i32 as u16", "u16"), int(65535, "u16"));
+        assert_eq!(
+            eval("1e10 as i32", "i32"),
+            int(i32::max_value() as i128, "i32")
+        );
+        assert_eq!(eval("65u8 as char", "char"), Some(ConstValue::Char('A')));
+        assert_eq!(eval("65u32 as char", "char"), None);
+        assert_eq!(eval("true as char", "char"), None);
+        assert_eq!(eval("'é' as char", "char"), Some(ConstValue::Char('é')));
+        assert_eq!(eval("OTHER * 2", "u8"), int(14, "u8"));
+        assert_eq!(eval("OTHER as u32 + 1", "u32"), int(8, "u32"));
+        assert_eq!(eval("MISSING + 1", "u32"), None);
+        assert_eq!(eval("f(1)", "u32"), None);
+    }
+
+    #[test]
+    fn wide_and_pointer_sized() {
+        assert_eq!(eval("300 as usize", "usize"), int(300, "usize"));
+        assert_eq!(eval("-5", "isize"), int(-5, "isize"));
+        assert_eq!(eval("4294967296", "usize"), int(4294967296, "usize"));
+        assert_eq!(
+            eval("1_000_000 * 1_000_000", "usize"),
+            int(1_000_000_000_000, "usize")
+        );
+        assert_eq!(eval("18446744073709551616", "usize"), None);
+        // these depend on the pointer width
+        assert_eq!(eval("!0", "usize"), None);
+        assert_eq!(eval("5000000000i64 as usize", "usize"), None);
+        assert_eq!(eval("-1i32 as usize", "usize"), None);
+        assert_eq!(eval("1e10 as usize", "usize"), None);
+
+        assert_eq!(eval("1 << 100", "u128"), int(1 << 100, "u128"));
+        assert_eq!(eval("!0", "i128"), int(-1, "i128"));
+        assert_eq!(
+            eval("170141183460469231731687303715884105727", "u128"),
+            int(i128::max_value(), "u128")
+        );
+        // these don't fit in an i128
+        assert_eq!(eval("!0", "u128"), None);
+        assert_eq!(eval("-1i8 as u128", "u128"), None);
+        assert_eq!(eval("1e40 as u128", "u128"), None);
+        assert_eq!(eval("1e40 as i128", "i128"), int(i128::max_value(), "i128"));
+    }
+}
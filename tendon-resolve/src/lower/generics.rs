@@ -1,116 +1,232 @@
 //! Helpers for lowering generics.
 
-use super::{types::lower_type, LowerError};
+use super::{
+    attributes::lower_metadata,
+    types::{lower_const_arg, lower_type},
+    LowerError,
+};
 use crate::lower::types::lower_type_bounds;
+use crate::walker::LocationMetadata;
+use syn::spanned::Spanned;
 use tendon_api::{
-    expressions::ConstExpr,
-    generics::{ConstParameter, Generics, Lifetime, LifetimeBounds, TypeParameter},
-    paths::Ident,
-    paths::Path,
+    attributes::Metadata,
+    expressions::ConstArg,
+    generics::{Generics, LifetimePredicate, TypePredicate, WherePredicate},
+    identities::{GenericParams, Identity, LifetimeId, PathType, TypeId},
+    items::{ConstParamItem, LifetimeParamItem, TypeParamItem},
+    paths::{Ident, UnresolvedPath},
     tokens::Tokens,
-    types::Type,
 };
 
-/// Lower the generics on a declaration to a clean set of definitions and bounds.
-pub fn lower_generics(generics: &syn::Generics) -> Result<Generics, LowerError> {
-    let mut type_params = vec![];
-    let mut lifetime_params = vec![];
-    let mut const_params = vec![];
-    let mut type_bounds = vec![];
-    let mut lifetime_bounds = vec![];
+/// Lower the generic parameters and `where` clause on a declaration.
+///
+/// Bounds written inline on a parameter are stored on that parameter; everything in the `where`
+/// clause goes in `where_predicates`. Parameters that are cfg'd out are dropped.
+pub fn lower_generics(
+    loc: &LocationMetadata,
+    generics: &syn::Generics,
+) -> Result<Generics, LowerError> {
+    let mut result = Generics::default();
 
     for param in &generics.params {
-        match param {
+        let lowered = match param {
             syn::GenericParam::Type(type_) => {
-                let name = Ident::from(&type_.ident);
-                type_params.push(TypeParameter {
-                    name: name.clone(),
-                    default: type_.default.as_ref().map(lower_type).transpose()?,
-                });
-                if !type_.bounds.is_empty() {
-                    type_bounds.push((
-                        Type::from(Path::generic(name)),
-                        lower_type_bounds(&type_.bounds)?,
-                    ))
-                }
+                lower_param_metadata(loc, Ident::from(&type_.ident), &type_.attrs, type_.span())
+                    .and_then(|metadata| {
+                        let (trait_bounds, lifetime_bounds) = lower_type_bounds(&type_.bounds)?;
+                        result.types.push(TypeParamItem {
+                            metadata,
+                            trait_bounds,
+                            lifetime_bounds,
+                            default: type_.default.as_ref().map(lower_type).transpose()?,
+                        });
+                        Ok(())
+                    })
             }
             syn::GenericParam::Lifetime(def) => {
-                let lifetime = lower_lifetime(&def.lifetime);
-                lifetime_params.push(lifetime.clone());
-                if !def.bounds.is_empty() {
-                    lifetime_bounds.push((lifetime, lower_lifetime_bounds(&def.bounds)));
-                }
+                let name = Ident::lifetime(&def.lifetime.ident.to_string());
+                lower_param_metadata(loc, name, &def.attrs, def.span()).map(|metadata| {
+                    result.lifetimes.push(LifetimeParamItem {
+                        metadata,
+                        bounds: def.bounds.iter().map(lower_lifetime).collect(),
+                    });
+                })
             }
-            syn::GenericParam::Const(const_) => const_params.push(ConstParameter {
-                name: Ident::from(&const_.ident),
-                type_: lower_type(&const_.ty)?,
-                default: const_.default.as_ref().map(Tokens::from).map(ConstExpr),
+            syn::GenericParam::Const(const_) => lower_param_metadata(
+                loc,
+                Ident::from(&const_.ident),
+                &const_.attrs,
+                const_.span(),
+            )
+            .and_then(|metadata| {
+                result.consts.push(ConstParamItem {
+                    metadata,
+                    type_: lower_type(&const_.ty)?,
+                    default: const_.default.as_ref().map(lower_const_arg),
+                });
+                Ok(())
             }),
+        };
+        match lowered {
+            Ok(()) | Err(LowerError::CfgdOut) => (),
+            Err(err) => return Err(err),
         }
     }
 
     if let Some(where_clause) = &generics.where_clause {
         for predicate in where_clause.predicates.iter() {
-            match predicate {
-                syn::WherePredicate::Lifetime(predicate) => {
-                    lifetime_bounds.push((
-                        lower_lifetime(&predicate.lifetime),
-                        lower_lifetime_bounds(&predicate.bounds),
-                    ));
-                }
-                syn::WherePredicate::Type(predicate) => {
-                    if predicate.lifetimes.is_some() {
-                        return Err(LowerError::NoHRTBsYet(Tokens::from(predicate)));
-                    }
-                    type_bounds.push((
-                        lower_type(&predicate.bounded_ty)?,
-                        lower_type_bounds(&predicate.bounds)?,
-                    ));
-                }
-                _ => return Err(LowerError::MalformedPredicate(Tokens::from(predicate))),
-            }
+            result
+                .where_predicates
+                .push(lower_where_predicate(predicate)?);
         }
     }
 
-    Ok(Generics {
-        type_params,
-        lifetime_params,
-        const_params,
-        type_bounds,
-        lifetime_bounds,
-    })
+    Ok(result)
 }
 
-/// Lower a lifetime.
-pub fn lower_lifetime(lifetime: &syn::Lifetime) -> Lifetime {
-    Lifetime(Ident::from(&lifetime.ident))
+/// Generic parameters have no visibility of their own.
+fn lower_param_metadata(
+    loc: &LocationMetadata,
+    name: Ident,
+    attrs: &[syn::Attribute],
+    span: proc_macro2::Span,
+) -> Result<Metadata, LowerError> {
+    lower_metadata(loc, name, &syn::Visibility::Inherited, attrs, span)
+}
+
+/// The generic arguments that pass a declaration's parameters through unchanged, e.g.
+/// `<'a, T, N>` for `<'a, T, const N: usize>`. Keys are positional, like those produced by
+/// `types::path_to_parts`.
+///
+/// `param` gives the identity to use for each type and const parameter; lifetimes are left
+/// unresolved.
+pub(crate) fn own_params(generics: &Generics, param: impl Fn(&Ident) -> Identity) -> GenericParams {
+    let mut params = GenericParams::empty();
+    for (i, lifetime) in generics.lifetimes.iter().enumerate() {
+        let name = lifetime.metadata.name.clone();
+        params.lifetimes.insert(
+            Ident::positional(i),
+            LifetimeId::new(Identity::unresolved(&UnresolvedPath::new(false, &[name]))),
+        );
+    }
+    for (i, type_) in generics.types.iter().enumerate() {
+        params.type_bindings.insert(
+            Ident::positional(i),
+            TypeId::Path(PathType {
+                path: param(&type_.metadata.name),
+                params: GenericParams::empty(),
+            }),
+        );
+    }
+    for (i, const_) in generics.consts.iter().enumerate() {
+        params.consts.insert(
+            Ident::positional(generics.types.len() + i),
+            ConstArg::Path(param(&const_.metadata.name)),
+        );
+    }
+    params
 }
 
-pub fn lower_lifetime_bounds(
-    bounds: &syn::punctuated::Punctuated<syn::Lifetime, syn::token::Add>,
-) -> LifetimeBounds {
-    LifetimeBounds {
-        lifetimes: bounds.iter().map(lower_lifetime).collect(),
+/// Lower a single `where` clause predicate.
+fn lower_where_predicate(predicate: &syn::WherePredicate) -> Result<WherePredicate, LowerError> {
+    match predicate {
+        syn::WherePredicate::Type(predicate) => {
+            let (trait_bounds, lifetime_bounds) = lower_type_bounds(&predicate.bounds)?;
+            Ok(WherePredicate::Type(TypePredicate {
+                for_lifetimes: lower_bound_lifetimes(predicate.lifetimes.as_ref()),
+                type_: lower_type(&predicate.bounded_ty)?,
+                trait_bounds,
+                lifetime_bounds,
+            }))
+        }
+        syn::WherePredicate::Lifetime(predicate) => {
+            Ok(WherePredicate::Lifetime(LifetimePredicate {
+                lifetime: lower_lifetime(&predicate.lifetime),
+                bounds: predicate.bounds.iter().map(lower_lifetime).collect(),
+            }))
+        }
+        _ => Err(LowerError::MalformedPredicate(Tokens::from(predicate))),
     }
 }
 
+/// Lower a lifetime.
+pub fn lower_lifetime(lifetime: &syn::Lifetime) -> LifetimeId {
+    let name = Ident::lifetime(&lifetime.ident.to_string());
+    LifetimeId::new(Identity::unresolved(&UnresolvedPath::new(false, &[name])))
+}
+
+/// Lower the lifetimes introduced by a higher-ranked binder, `for<'a, 'b>`.
+/// Bounds on binder lifetimes aren't legal rust, so they're ignored.
+pub fn lower_bound_lifetimes(binder: Option<&syn::BoundLifetimes>) -> Vec<LifetimeId> {
+    binder
+        .map(|binder| {
+            binder
+                .lifetimes
+                .iter()
+                .map(|def| lower_lifetime(&def.lifetime))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::walker::TEST_LOCATION_METADATA;
+    use tendon_api::identities::{PathType, TypeId};
 
     #[test]
     fn generics() {
         let type_: syn::ItemType = syn::parse_quote! {
             type T<'a: 'b, 'b, 'c, T, S: Copy, V=U, const WIDTH: usize = 3, const HEIGHT: usize>
-                where 'b: 'c, T: Q, F: M<T>, S: Clone = !;
+                where 'b: 'c, T: Q, Vec<T>: M<T>, S: Clone, for<'x> &'x T: Into<F> = !;
+        };
+        let generics = lower_generics(&TEST_LOCATION_METADATA, &type_.generics).unwrap();
+        assert_eq!(generics.types.len(), 3);
+        assert_eq!(generics.lifetimes.len(), 3);
+        assert_eq!(generics.consts.len(), 2);
+        assert_eq!(generics.lifetimes[0].metadata.name, Ident::lifetime("a"));
+        assert_eq!(generics.lifetimes[0].bounds.len(), 1);
+        assert_eq!(generics.types[1].trait_bounds.len(), 1);
+        assert!(generics
+            .type_param(&Ident::from("V"))
+            .unwrap()
+            .default
+            .is_some());
+        assert!(generics
+            .const_param(&Ident::from("WIDTH"))
+            .unwrap()
+            .default
+            .is_some());
+        assert!(generics
+            .const_param(&Ident::from("HEIGHT"))
+            .unwrap()
+            .default
+            .is_none());
+
+        assert_eq!(generics.where_predicates.len(), 5);
+        assert_match!(generics.where_predicates[0], WherePredicate::Lifetime(LifetimePredicate { bounds, .. }) => {
+            assert_eq!(bounds.len(), 1);
+        });
+        assert_match!(generics.where_predicates[2], WherePredicate::Type(TypePredicate { type_, trait_bounds, .. }) => {
+            assert_match!(type_, TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &Identity::unresolved(&UnresolvedPath::fake("Vec")));
+            });
+            assert_eq!(trait_bounds.len(), 1);
+        });
+        assert_match!(generics.where_predicates[4], WherePredicate::Type(TypePredicate { for_lifetimes, type_, .. }) => {
+            assert_eq!(for_lifetimes.len(), 1);
+            assert_match!(type_, TypeId::Reference(..));
+        });
+    }
+
+    #[test]
+    fn cfgd_out_params() {
+        let fn_: syn::ItemFn = syn::parse_quote! {
+            fn f<#[cfg(any())] T, U>() {}
         };
-        let generics = lower_generics(&type_.generics).unwrap();
-        assert_eq!(generics.type_params.len(), 3);
-        assert_eq!(generics.lifetime_params.len(), 3);
-        assert_eq!(generics.const_params.len(), 2);
-        assert_eq!(generics.lifetime_bounds.len(), 2);
-        assert_eq!(generics.type_bounds.len(), 4);
-        assert!(generics.type_params[2].default.is_some());
-        assert!(generics.const_params[0].default.is_some());
+        let generics = lower_generics(&TEST_LOCATION_METADATA, &fn_.sig.generics).unwrap();
+        assert_eq!(generics.types.len(), 1);
+        assert_eq!(generics.types[0].metadata.name, Ident::from("U"));
     }
 }
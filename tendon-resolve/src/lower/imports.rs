@@ -1,112 +1,147 @@
-use crate::lower::attributes::lower_visibility;
-use crate::walker::ModuleScope;
-use tendon_api::attributes::Visibility;
-use tendon_api::paths::Ident;
-use tendon_api::paths::UnresolvedPath;
+use super::LowerError;
+use crate::lower::attributes::lower_metadata;
+use crate::walker::LocationMetadata;
+use syn::spanned::Spanned;
+use tendon_api::attributes::Metadata;
+use tendon_api::paths::{Ident, UnresolvedPath};
 
-/// Lower a use tree into a set of globs and imports.
-pub(crate) fn lower_use(scope: &mut ModuleScope, use_: &syn::ItemUse) {
-    // TODO: do we need to care about metadata here?
+/// A lowered `use` item.
+pub(crate) struct LoweredUse {
+    /// The metadata of the whole item: its visibility, and `#[doc(hidden)]` / `#[doc(inline)]`.
+    /// The name is a placeholder.
+    pub(crate) metadata: Metadata,
+
+    /// Non-glob imports.
+    /// Maps the imported-as ident to a path,
+    /// i.e. `use x::Y;` is stored as `Y => x::Y`,
+    /// `use x::z as w` is stored as `w => x::z`
+    pub(crate) imports: Vec<(Ident, UnresolvedPath)>,
+
+    /// Glob imports.
+    /// `use x::y::z::*` is stored as `x::y::z`.
+    pub(crate) glob_imports: Vec<UnresolvedPath>,
+}
 
-    let vis = lower_visibility(&use_.vis);
+/// Lower a use tree into a set of globs and imports.
+pub(crate) fn lower_use(
+    loc: &LocationMetadata,
+    use_: &syn::ItemUse,
+) -> Result<LoweredUse, LowerError> {
+    let metadata = lower_metadata(loc, "{use}".into(), &use_.vis, &use_.attrs, use_.span())?;
+    let mut lowered = LoweredUse {
+        metadata,
+        imports: vec![],
+        glob_imports: vec![],
+    };
     lower_use_tree(
-        scope,
+        &mut lowered,
         &use_.tree,
-        &vis,
         UnresolvedPath {
             rooted: use_.leading_colon.is_some(),
             path: vec![],
         },
     );
+    Ok(lowered)
 }
 
-fn lower_use_tree(
-    scope: &mut ModuleScope,
-    use_: &syn::UseTree,
-    vis: &Visibility,
-    current: UnresolvedPath,
-) {
+fn lower_use_tree(lowered: &mut LoweredUse, use_: &syn::UseTree, current: UnresolvedPath) {
     match use_ {
-        syn::UseTree::Path(path) => lower_use_tree(
-            scope,
-            &*path.tree,
-            vis,
-            current.join(Ident::from(&path.ident)),
-        ),
+        syn::UseTree::Path(path) => {
+            lower_use_tree(lowered, &path.tree, current.join(Ident::from(&path.ident)))
+        }
         syn::UseTree::Group(group) => {
             for path in group.items.iter() {
-                lower_use_tree(scope, path, vis, current.clone());
+                lower_use_tree(lowered, path, current.clone());
             }
         }
-        syn::UseTree::Glob(_) => {
-            let globs = match vis {
-                Visibility::Pub => &mut scope.pub_glob_imports,
-                Visibility::NonPub => &mut scope.glob_imports,
-            };
-            globs.push(current.into())
-        }
+        syn::UseTree::Glob(_) => lowered.glob_imports.push(current),
         syn::UseTree::Name(name) => {
-            let imports = match vis {
-                Visibility::Pub => &mut scope.pub_imports,
-                Visibility::NonPub => &mut scope.imports,
-            };
-
-            imports.insert(
-                Ident::from(&name.ident),
-                current.join(Ident::from(&name.ident)).into(),
-            );
+            let ident = Ident::from(&name.ident);
+            lowered.imports.push(import(current, ident.clone(), ident));
         }
         syn::UseTree::Rename(rename) => {
-            let imports = match vis {
-                Visibility::Pub => &mut scope.pub_imports,
-                Visibility::NonPub => &mut scope.imports,
-            };
-
-            imports.insert(
+            lowered.imports.push(import(
+                current,
                 Ident::from(&rename.rename),
-                current.join(Ident::from(&rename.ident)).into(),
-            );
+                Ident::from(&rename.ident),
+            ));
         }
     }
 }
 
+/// `use x::y as name;`, or `use x::{self as name};`, which imports `x` itself.
+fn import(current: UnresolvedPath, name: Ident, ident: Ident) -> (Ident, UnresolvedPath) {
+    if &ident[..] == "self" {
+        let name = if &name[..] == "self" {
+            current.path.last().cloned().unwrap_or(name)
+        } else {
+            name
+        };
+        (name, current)
+    } else {
+        (name, current.join(ident))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tendon_api::paths::Path;
+    use crate::walker::TEST_LOCATION_METADATA;
+    use tendon_api::attributes::{DocInline, Visibility};
 
     #[test]
     fn lowering() {
-        let mut scope = ModuleScope::default();
-
-        lower_use(
-            &mut scope,
+        let lowered = lower_use(
+            &TEST_LOCATION_METADATA,
             &syn::parse_quote! {
-                use ::x::y::{z::W, f as p, l::*};
+                use ::x::y::{z::W, f as p, l::*, self};
             },
-        );
+        )
+        .unwrap();
 
-        assert_eq!(scope.glob_imports[0], Path::fake("::x::y::l"));
-        assert_eq!(scope.imports[&Ident::from("W")], Path::fake("::x::y::z::W"));
-        assert_eq!(scope.imports[&Ident::from("p")], Path::fake("::x::y::f"));
+        assert_eq!(lowered.glob_imports[0], UnresolvedPath::fake("::x::y::l"));
+        assert_eq!(
+            lowered.imports,
+            vec![
+                ("W".into(), UnresolvedPath::fake("::x::y::z::W")),
+                ("p".into(), UnresolvedPath::fake("::x::y::f")),
+                ("y".into(), UnresolvedPath::fake("::x::y")),
+            ]
+        );
+        assert_eq!(
+            lowered.metadata.visibility,
+            Visibility::InScope(TEST_LOCATION_METADATA.module_path.clone())
+        );
 
-        let mut scope = ModuleScope::default();
-        lower_use(
-            &mut scope,
+        let lowered = lower_use(
+            &TEST_LOCATION_METADATA,
             &syn::parse_quote! {
+                #[doc(hidden)]
                 pub use x::y::{z::{W, V}, f as p, l::*};
             },
-        );
+        )
+        .unwrap();
 
-        assert_eq!(scope.pub_glob_imports[0], Path::fake("x::y::l"));
-        assert_eq!(
-            scope.pub_imports[&Ident::from("W")],
-            Path::fake("x::y::z::W")
-        );
+        assert_eq!(lowered.glob_imports[0], UnresolvedPath::fake("x::y::l"));
         assert_eq!(
-            scope.pub_imports[&Ident::from("V")],
-            Path::fake("x::y::z::V")
+            lowered.imports,
+            vec![
+                ("W".into(), UnresolvedPath::fake("x::y::z::W")),
+                ("V".into(), UnresolvedPath::fake("x::y::z::V")),
+                ("p".into(), UnresolvedPath::fake("x::y::f")),
+            ]
         );
-        assert_eq!(scope.pub_imports[&Ident::from("p")], Path::fake("x::y::f"));
+        assert_eq!(lowered.metadata.visibility, Visibility::Pub);
+        assert!(lowered.metadata.doc_hidden);
+
+        let lowered = lower_use(
+            &TEST_LOCATION_METADATA,
+            &syn::parse_quote! {
+                #[doc(inline)]
+                pub use x::Y;
+            },
+        )
+        .unwrap();
+        assert_eq!(lowered.metadata.doc_inline, Some(DocInline::Inline));
     }
 }
@@ -1,107 +1,208 @@
+//! Lowering for item declarations.
+
 use super::LowerError;
-use crate::lower::attributes::extract_symbol_metadata;
+use crate::lower::attributes::{
+    extract_link_name, extract_non_exhaustive, extract_symbol_metadata, lower_links,
+};
+use crate::lower::expressions::{check_range, evaluate_const, primitive_name, ConstLookup};
 use crate::lower::generics::lower_lifetime;
-use crate::lower::types::lower_return_type;
+use crate::lower::types::{lower_return_type, lower_type_bounds, path_to_parts};
 use crate::lower::{
     attributes::{extract_type_metadata, lower_metadata},
-    generics::lower_generics,
+    generics::{lower_generics, own_params},
     types::lower_type,
 };
 use crate::walker::LocationMetadata;
+use lazy_static::lazy_static;
 use syn::spanned::Spanned;
-use tendon_api::items::{FunctionArg, FunctionItem, Receiver, Signature};
+use tendon_api::attributes::{ForeignMetadata, Repr, ReprBase, Visibility};
+use tendon_api::builtins::impl_scope_name;
+use tendon_api::items::{
+    AliasItem, AssociatedConst, AssociatedType, ConstItem, ForeignFunctionItem, ForeignStaticItem,
+    ForeignTypeItem, FunctionArg, FunctionItem, ImplItem, ImplType, Receiver, Signature,
+    StaticItem, SymbolItem, TraitItem, TraitMethod, TypeItem, TypeParamItem,
+};
 use tendon_api::{
-    items::{Abi, EnumItem, EnumVariant, InherentImpl, StructField, StructItem, StructKind},
-    paths::Ident,
+    attributes::Metadata,
+    expressions::{ConstExpr, ConstValue},
+    generics::{TypePredicate, WherePredicate},
+    identities::{Identity, PathType, TraitId, TypeId},
+    items::{Abi, EnumItem, EnumVariant, StructField, StructItem, StructKind, UnionItem},
+    paths::{Ident, UnresolvedPath},
     tokens::Tokens,
 };
+use tracing::warn;
+
+lazy_static! {
+    static ref SELF_TYPE: Identity = Identity::unresolved(&UnresolvedPath::fake("Self"));
+}
 
 /// Lower a struct.
 pub(crate) fn lower_struct(
     loc: &LocationMetadata,
     struct_: &syn::ItemStruct,
 ) -> Result<StructItem, LowerError> {
-    let mut metadata =
-        super::attributes::lower_metadata(loc, &struct_.vis, &struct_.attrs, struct_.span())?;
+    let mut metadata = lower_metadata(
+        loc,
+        Ident::from(&struct_.ident),
+        &struct_.vis,
+        &struct_.attrs,
+        struct_.span(),
+    )?;
 
     let type_metadata = extract_type_metadata(&mut metadata)?;
 
-    let generics = lower_generics(&struct_.generics)?;
-
-    let kind = match struct_.fields {
-        syn::Fields::Named(..) => StructKind::Named,
-        syn::Fields::Unnamed(..) => StructKind::Tuple,
-        syn::Fields::Unit => StructKind::Unit,
-    };
-
+    let generics = lower_generics(loc, &struct_.generics)?;
+    let kind = lower_struct_kind(&struct_.fields);
     let fields = lower_fields(loc, &struct_.fields)?;
 
-    let inherent_impl = InherentImpl {};
-
-    let name = Ident::from(&struct_.ident);
-
     Ok(StructItem {
-        fields,
-        generics,
-        inherent_impl,
-        kind,
         metadata,
-        name,
         type_metadata,
+        fields,
+        kind,
+        generics,
     })
 }
 
-/// Lower an enum.
+/// Lower an enum, evaluating its discriminants if possible.
+/// `lookup` is used to find the values of consts referenced by explicit discriminants.
 pub(crate) fn lower_enum(
     loc: &LocationMetadata,
     enum_: &syn::ItemEnum,
+    lookup: &mut ConstLookup,
 ) -> Result<EnumItem, LowerError> {
-    let mut metadata =
-        super::attributes::lower_metadata(loc, &enum_.vis, &enum_.attrs, enum_.span())?;
+    let mut metadata = lower_metadata(
+        loc,
+        Ident::from(&enum_.ident),
+        &enum_.vis,
+        &enum_.attrs,
+        enum_.span(),
+    )?;
     let type_metadata = extract_type_metadata(&mut metadata)?;
 
-    let generics = lower_generics(&enum_.generics)?;
-
+    let generics = lower_generics(loc, &enum_.generics)?;
     let variants = enum_
         .variants
         .iter()
         .map(|variant| {
             // Note: we copy the parent's visibility:
-            let metadata =
-                super::attributes::lower_metadata(loc, &enum_.vis, &variant.attrs, variant.span())?;
-
-            let kind = match variant.fields {
-                syn::Fields::Named(..) => StructKind::Named,
-                syn::Fields::Unnamed(..) => StructKind::Tuple,
-                syn::Fields::Unit => StructKind::Unit,
-            };
-
+            let mut metadata = lower_metadata(
+                loc,
+                Ident::from(&variant.ident),
+                &enum_.vis,
+                &variant.attrs,
+                variant.span(),
+            )?;
+            let non_exhaustive = extract_non_exhaustive(&mut metadata);
+            let kind = lower_struct_kind(&variant.fields);
             let fields = lower_fields(loc, &variant.fields)?;
 
-            let name = Ident::from(&variant.ident);
+            let discriminant = variant
+                .discriminant
+                .as_ref()
+                .map(|(_, expr)| ConstExpr(Tokens::from(expr)));
+
             Ok(EnumVariant {
                 metadata,
                 kind,
                 fields,
-                name,
+                discriminant,
+                evaluated_discriminant: None,
+                non_exhaustive,
             })
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let inherent_impl = InherentImpl {};
+    let mut enum_ = EnumItem {
+        metadata,
+        type_metadata,
+        generics,
+        variants,
+    };
+    let discriminants = evaluate_discriminants(&enum_, lookup);
+    for (variant, discriminant) in enum_.variants.iter_mut().zip(discriminants) {
+        variant.evaluated_discriminant = discriminant;
+    }
+    Ok(enum_)
+}
+
+/// Evaluate an enum's discriminants, explicit or implicit, in order.
+/// `lookup` is used to find the values of consts referenced by explicit discriminants.
+pub(crate) fn evaluate_discriminants(
+    enum_: &EnumItem,
+    lookup: &mut ConstLookup,
+) -> Vec<Option<ConstValue>> {
+    let discriminant_type = discriminant_type(&enum_.type_metadata.repr);
 
-    let name = Ident::from(&enum_.ident);
+    let mut previous: Option<Option<i128>> = None;
+    let mut discriminants = vec![];
+    for variant in &enum_.variants {
+        let expr = variant
+            .discriminant
+            .as_ref()
+            .map(|expr| expr.0.parse::<syn::Expr>());
+        let evaluated_discriminant = match (expr, previous) {
+            (Some(Ok(expr)), _) => match evaluate_const(&expr, Some(&discriminant_type), lookup) {
+                Some(ConstValue::Int(value, _)) => check_range(value, discriminant_type.clone()),
+                _ => None,
+            },
+            (Some(Err(_)), _) => None,
+            (None, None) => check_range(0, discriminant_type.clone()),
+            (None, Some(previous)) => previous
+                .and_then(|value| value.checked_add(1))
+                .and_then(|value| check_range(value, discriminant_type.clone())),
+        };
+        previous = Some(match &evaluated_discriminant {
+            Some(ConstValue::Int(value, _)) => Some(*value),
+            _ => None,
+        });
+        discriminants.push(evaluated_discriminant);
+    }
+    discriminants
+}
 
-    Ok(EnumItem {
-        generics,
-        inherent_impl,
+/// The type of an enum's discriminants: its integer repr, `#[repr(u8)]` or `#[repr(C, u8)]`,
+/// or `isize` by default.
+fn discriminant_type(repr: &Repr) -> Ident {
+    match &repr.base {
+        ReprBase::Int(int) | ReprBase::IntOuterTag(int) => int.clone(),
+        _ => Ident::from("isize"),
+    }
+}
+
+/// Lower a union.
+pub(crate) fn lower_union(
+    loc: &LocationMetadata,
+    union_: &syn::ItemUnion,
+) -> Result<UnionItem, LowerError> {
+    let mut metadata = lower_metadata(
+        loc,
+        Ident::from(&union_.ident),
+        &union_.vis,
+        &union_.attrs,
+        union_.span(),
+    )?;
+    let type_metadata = extract_type_metadata(&mut metadata)?;
+    let generics = lower_generics(loc, &union_.generics)?;
+    let fields = lower_fields(loc, &syn::Fields::Named(union_.fields.clone()))?;
+
+    Ok(UnionItem {
         metadata,
-        name,
         type_metadata,
-        variants,
+        generics,
+        fields,
     })
 }
 
+fn lower_struct_kind(fields: &syn::Fields) -> StructKind {
+    match fields {
+        syn::Fields::Named(..) => StructKind::Named,
+        syn::Fields::Unnamed(..) => StructKind::Tuple,
+        syn::Fields::Unit => StructKind::Unit,
+    }
+}
+
 fn lower_fields(
     loc: &LocationMetadata,
     fields: &syn::Fields,
@@ -110,37 +211,39 @@ fn lower_fields(
         .iter()
         .enumerate()
         .map(|(i, field)| {
-            let metadata = lower_metadata(loc, &field.vis, &field.attrs, field.span())?;
             let name = field
                 .ident
                 .as_ref()
                 .map(|ident| Ident::from(ident))
                 .unwrap_or_else(|| Ident::from(&format!("{}", i)[..]));
+            let metadata = lower_metadata(loc, name, &field.vis, &field.attrs, field.span())?;
             let type_ = lower_type(&field.ty)?;
 
-            Ok(StructField {
-                metadata,
-                name,
-                type_,
-            })
+            Ok(StructField { metadata, type_ })
         })
         .collect()
 }
 
-/// Lower a function.
-/// Annoyingly, the data for this is stored in different places for functions / methods
-/// so you just have to pass in a bunch of junk lol.
-pub fn lower_signature(sig: &syn::Signature) -> Result<Signature, LowerError> {
+/// Lower a function signature.
+/// Shared between free functions, inherent methods and trait methods.
+pub(crate) fn lower_signature(
+    loc: &LocationMetadata,
+    sig: &syn::Signature,
+) -> Result<Signature, LowerError> {
     let mut receiver = Receiver::None;
     let variadic = sig.variadic.is_some();
 
-    // this is hairy. idgaf
-    let args = sig
-        .inputs
-        .iter()
-        .enumerate()
-        // walk through arguments, pulling out receiver if present
-        .filter(|(i, arg)| match arg {
+    let mut args = vec![];
+    for (i, arg) in sig.inputs.iter().enumerate() {
+        if variadic && i == sig.inputs.len() - 1 {
+            if let syn::FnArg::Typed(syn::PatType { ty, .. }) = arg {
+                if let syn::Type::Verbatim(_) = &**ty {
+                    // skip named variadic arg `rest: ...`, can't be parsed
+                    break;
+                }
+            }
+        }
+        match arg {
             syn::FnArg::Receiver(rec) => {
                 if let Some((_, lifetime)) = &rec.reference {
                     let lifetime = lifetime.as_ref().map(lower_lifetime);
@@ -149,135 +252,507 @@ pub fn lower_signature(sig: &syn::Signature) -> Result<Signature, LowerError> {
                 } else {
                     receiver = Receiver::ConsumeSelf
                 }
-                false
-            }
-            _ => {
-                if variadic {
-                    // skip last arg for variadics, can't be parsed
-                    *i < sig.inputs.len() - 1
-                } else {
-                    true
-                }
             }
-        })
-        .map(|(_, arg)| match arg {
             syn::FnArg::Typed(typed) => {
-                let type_ = lower_type(&typed.ty)?;
                 let name = if let syn::Pat::Ident(pat_ident) = &*typed.pat {
                     Ident::from(&pat_ident.ident)
                 } else {
                     Ident::from("_")
                 };
-                Ok(FunctionArg { type_, name })
+                let type_ = lower_type(&typed.ty)?;
+                if &name[..] == "self" && args.is_empty() {
+                    // `self: Pin<&mut Self>`
+                    receiver = Receiver::Other(type_);
+                    continue;
+                }
+                let metadata = lower_metadata(
+                    loc,
+                    name,
+                    &syn::Visibility::Inherited,
+                    &typed.attrs,
+                    typed.span(),
+                )?;
+                args.push(FunctionArg { metadata, type_ });
             }
-            _ => Err(LowerError::MalformedFunctionArg(Tokens::from(arg))),
-        })
-        .collect::<Result<Vec<FunctionArg>, LowerError>>()?;
+        }
+    }
 
     let ret = lower_return_type(&sig.output)?;
     let is_unsafe = sig.unsafety.is_some();
     let is_async = sig.asyncness.is_some();
     let is_const = sig.constness.is_some();
-    let abi = sig
-        .abi
-        .as_ref()
-        .map(|abi| {
-            if let Some(name) = &abi.name {
-                // if there is an abi string:
-                match &name.value()[..] {
-                    "Rust" => Abi::Rust,
-                    "C" => Abi::C,
-                    other => Abi::Other(other.to_string()),
-                }
-            } else {
-                // only an extern token
-                Abi::C
-            }
-        })
-        .unwrap_or(
-            // no extern at all
-            Abi::Rust,
-        );
-    let generics = lower_generics(&sig.generics)?;
+    let abi = lower_abi(sig.abi.as_ref());
+    let generics = lower_generics(loc, &sig.generics)?;
 
     Ok(Signature {
-        abi,
-        args,
         generics,
+        args,
+        ret,
+        is_unsafe,
         is_async,
         is_const,
-        is_unsafe,
+        abi,
         receiver,
-        ret,
         variadic,
     })
 }
 
+/// Lower an abi, `extern "C"`.
+pub(crate) fn lower_abi(abi: Option<&syn::Abi>) -> Abi {
+    abi.map(|abi| {
+        if let Some(name) = &abi.name {
+            // if there is an abi string:
+            match &name.value()[..] {
+                "Rust" => Abi::Rust,
+                "C" => Abi::C,
+                other => Abi::Other(other.to_string()),
+            }
+        } else {
+            // only an extern token
+            Abi::C
+        }
+    })
+    .unwrap_or(
+        // no extern at all
+        Abi::Rust,
+    )
+}
+
 /// Lower a function item.
 pub(crate) fn lower_function_item(
     loc: &LocationMetadata,
     item: &syn::ItemFn,
 ) -> Result<FunctionItem, LowerError> {
-    let mut metadata = lower_metadata(loc, &item.vis, &item.attrs, item.span())?;
+    let mut metadata = lower_metadata(
+        loc,
+        Ident::from(&item.sig.ident),
+        &item.vis,
+        &item.attrs,
+        item.span(),
+    )?;
     let symbol_metadata = extract_symbol_metadata(&mut metadata)?;
-    let name = Ident::from(&item.sig.ident);
-    let signature = lower_signature(&item.sig)?;
+    let signature = lower_signature(loc, &item.sig)?;
     Ok(FunctionItem {
         metadata,
         symbol_metadata,
-        name,
         signature,
     })
 }
 
-/*
-/// Lower a method.
-pub fn lower_impl_method(
+/// Lower a type alias.
+pub(crate) fn lower_type_alias(
     loc: &LocationMetadata,
-    item: &syn::ImplItemMethod,
-) -> Result<Signature, LowerError> {
-    Ok(lower_signature(
+    alias: &syn::ItemType,
+) -> Result<AliasItem, LowerError> {
+    let metadata = lower_metadata(
         loc,
-        &item.sig.sig,
-        &item.attrs,
-        &item.vis,
-        &item.sig.ident,
-        &item.sig.abi,
-        &item.sig.constness,
-        &item.sig.asyncness,
-        &item.sig.unsafety,
-        item.span(),
-    )?)
+        Ident::from(&alias.ident),
+        &alias.vis,
+        &alias.attrs,
+        alias.span(),
+    )?;
+    Ok(AliasItem {
+        metadata,
+        generics: lower_generics(loc, &alias.generics)?,
+        target: lower_type(&alias.ty)?,
+    })
 }
 
-/// Lower a method.
-pub fn lower_trait_method(
+/// Lower a const, evaluating its value if possible.
+/// `lookup` is used to find the values of other consts referenced by the expression.
+pub(crate) fn lower_const(
     loc: &LocationMetadata,
-    item: &syn::TraitItemMethod,
-    trait_vis: &syn::Visibility,
-) -> Result<Signature, LowerError> {
-    Ok(lower_signature(
+    const_: &syn::ItemConst,
+    lookup: &mut ConstLookup,
+) -> Result<ConstItem, LowerError> {
+    let metadata = lower_metadata(
         loc,
-        &item.sig.sig,
-        &item.attrs,
-        trait_vis,
-        &item.sig.ident,
-        &item.sig.abi,
-        &item.sig.constness,
-        &item.sig.asyncness,
-        &item.sig.unsafety,
-        item.span(),
-    )?)
+        Ident::from(&const_.ident),
+        &const_.vis,
+        &const_.attrs,
+        const_.span(),
+    )?;
+    let mut const_ = ConstItem {
+        metadata,
+        type_: Box::new(lower_type(&const_.ty)?),
+        value: ConstExpr(Tokens::from(&const_.expr)),
+        evaluated: None,
+    };
+    const_.evaluated = evaluate_const_item(&const_, lookup);
+    Ok(const_)
+}
+
+/// Evaluate a const's value, if possible.
+/// `lookup` is used to find the values of other consts referenced by the expression.
+pub(crate) fn evaluate_const_item(
+    const_: &ConstItem,
+    lookup: &mut ConstLookup,
+) -> Option<ConstValue> {
+    let expr = const_.value.0.parse::<syn::Expr>().ok()?;
+    evaluate_const(&expr, primitive_name(&const_.type_).as_ref(), lookup)
+}
+
+/// Lower a static.
+pub(crate) fn lower_static(
+    loc: &LocationMetadata,
+    static_: &syn::ItemStatic,
+) -> Result<StaticItem, LowerError> {
+    let mut metadata = lower_metadata(
+        loc,
+        Ident::from(&static_.ident),
+        &static_.vis,
+        &static_.attrs,
+        static_.span(),
+    )?;
+    let symbol_metadata = extract_symbol_metadata(&mut metadata)?;
+    Ok(StaticItem {
+        metadata,
+        symbol_metadata,
+        mut_: static_.mutability.is_some(),
+        type_: Box::new(lower_type(&static_.ty)?),
+        value: ConstExpr(Tokens::from(&static_.expr)),
+    })
+}
+
+/// Lower the contents of an `extern` block.
+///
+/// Every item in the block gets the block's abi and `#[link]` attributes. Items that are
+/// cfg'd out are dropped; macro invocations in the block are skipped.
+pub(crate) fn lower_foreign_mod(
+    loc: &LocationMetadata,
+    foreign_mod: &syn::ItemForeignMod,
+) -> Result<(Vec<SymbolItem>, Vec<TypeItem>), LowerError> {
+    let abi = lower_abi(Some(&foreign_mod.abi));
+    let links = lower_links(&foreign_mod.attrs);
+    let foreign_metadata = |metadata: &mut Metadata| ForeignMetadata {
+        abi: abi.clone(),
+        links: links.clone(),
+        link_name: extract_link_name(metadata),
+    };
+
+    let mut symbols = vec![];
+    let mut types = vec![];
+    for item in &foreign_mod.items {
+        let result = match item {
+            syn::ForeignItem::Fn(fn_) => lower_metadata(
+                loc,
+                Ident::from(&fn_.sig.ident),
+                &fn_.vis,
+                &fn_.attrs,
+                fn_.span(),
+            )
+            .and_then(|mut metadata| {
+                let foreign_metadata = foreign_metadata(&mut metadata);
+                let mut signature = lower_signature(loc, &fn_.sig)?;
+                signature.abi = abi.clone();
+                symbols.push(SymbolItem::ForeignFunction(ForeignFunctionItem {
+                    metadata,
+                    foreign_metadata,
+                    signature,
+                }));
+                Ok(())
+            }),
+            syn::ForeignItem::Static(static_) => lower_metadata(
+                loc,
+                Ident::from(&static_.ident),
+                &static_.vis,
+                &static_.attrs,
+                static_.span(),
+            )
+            .and_then(|mut metadata| {
+                let foreign_metadata = foreign_metadata(&mut metadata);
+                symbols.push(SymbolItem::ForeignStatic(ForeignStaticItem {
+                    metadata,
+                    foreign_metadata,
+                    mut_: static_.mutability.is_some(),
+                    type_: lower_type(&static_.ty)?,
+                }));
+                Ok(())
+            }),
+            syn::ForeignItem::Type(type_) => lower_metadata(
+                loc,
+                Ident::from(&type_.ident),
+                &type_.vis,
+                &type_.attrs,
+                type_.span(),
+            )
+            .map(|mut metadata| {
+                let foreign_metadata = foreign_metadata(&mut metadata);
+                types.push(TypeItem::ForeignType(ForeignTypeItem {
+                    metadata,
+                    foreign_metadata,
+                }));
+            }),
+            other => {
+                warn!("skipping unhandled foreign item: {:?}", Tokens::from(other));
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) | Err(LowerError::CfgdOut) => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((symbols, types))
+}
+
+/// Lower a trait declaration.
+///
+/// Trait items inherit the trait's visibility. `where Self: Bound` predicates are folded into
+/// the supertraits, since that's what they mean.
+pub(crate) fn lower_trait(
+    loc: &LocationMetadata,
+    trait_: &syn::ItemTrait,
+) -> Result<TraitItem, LowerError> {
+    let metadata = lower_metadata(
+        loc,
+        Ident::from(&trait_.ident),
+        &trait_.vis,
+        &trait_.attrs,
+        trait_.span(),
+    )?;
+
+    let mut generics = lower_generics(loc, &trait_.generics)?;
+    let (mut supertraits, mut lifetime_bounds) = lower_type_bounds(&trait_.supertraits)?;
+    let where_predicates = std::mem::replace(&mut generics.where_predicates, vec![]);
+    for predicate in where_predicates {
+        match predicate {
+            WherePredicate::Type(TypePredicate {
+                type_: TypeId::Path(PathType { path, params }),
+                trait_bounds,
+                lifetime_bounds: lifetimes,
+                for_lifetimes,
+            }) if path == *SELF_TYPE && params.is_empty() && for_lifetimes.is_empty() => {
+                supertraits.extend(trait_bounds);
+                lifetime_bounds.extend(lifetimes);
+            }
+            other => generics.where_predicates.push(other),
+        }
+    }
+
+    let unresolved =
+        |name: &Ident| Identity::unresolved(&UnresolvedPath::new(false, &[name.clone()]));
+    let self_param = TypeParamItem {
+        metadata: lower_metadata(
+            loc,
+            Ident::from("Self"),
+            &syn::Visibility::Inherited,
+            &[],
+            trait_.span(),
+        )?,
+        trait_bounds: vec![TraitId {
+            id: unresolved(&metadata.name),
+            params: own_params(&generics, unresolved),
+            is_maybe: false,
+            for_lifetimes: vec![],
+        }],
+        lifetime_bounds: vec![],
+        default: None,
+    };
+
+    let mut methods = vec![];
+    let mut types = vec![];
+    let mut consts = vec![];
+
+    for item in &trait_.items {
+        match item {
+            syn::TraitItem::Method(method) => {
+                let metadata = lower_metadata(
+                    loc,
+                    Ident::from(&method.sig.ident),
+                    &trait_.vis,
+                    &method.attrs,
+                    method.span(),
+                )?;
+                methods.push(TraitMethod {
+                    metadata,
+                    signature: lower_signature(loc, &method.sig)?,
+                    is_provided: method.default.is_some(),
+                });
+            }
+            syn::TraitItem::Type(type_) => {
+                let metadata = lower_metadata(
+                    loc,
+                    Ident::from(&type_.ident),
+                    &trait_.vis,
+                    &type_.attrs,
+                    type_.span(),
+                )?;
+                let (trait_bounds, lifetime_bounds) = lower_type_bounds(&type_.bounds)?;
+                types.push(AssociatedType {
+                    metadata,
+                    generics: lower_generics(loc, &type_.generics)?,
+                    trait_bounds,
+                    lifetime_bounds,
+                    default: type_
+                        .default
+                        .as_ref()
+                        .map(|(_, type_)| lower_type(type_))
+                        .transpose()?,
+                });
+            }
+            syn::TraitItem::Const(const_) => {
+                let metadata = lower_metadata(
+                    loc,
+                    Ident::from(&const_.ident),
+                    &trait_.vis,
+                    &const_.attrs,
+                    const_.span(),
+                )?;
+                consts.push(AssociatedConst {
+                    metadata,
+                    type_: lower_type(&const_.ty)?,
+                    default: const_
+                        .default
+                        .as_ref()
+                        .map(|(_, expr)| ConstExpr(Tokens::from(expr))),
+                });
+            }
+            other => warn!("skipping unhandled trait item: {:?}", Tokens::from(other)),
+        }
+    }
+
+    Ok(TraitItem {
+        metadata,
+        generics,
+        self_param,
+        supertraits,
+        lifetime_bounds,
+        is_unsafe: trait_.unsafety.is_some(),
+        is_auto: trait_.auto_token.is_some(),
+        methods,
+        types,
+        consts,
+    })
+}
+
+/// Lower an impl block.
+///
+/// Items in trait impls are as public as the trait, so they're marked `pub`. Items that are
+/// cfg'd out are dropped. `index` is the number of impls already lowered in the same scope.
+pub(crate) fn lower_impl(
+    loc: &LocationMetadata,
+    impl_: &syn::ItemImpl,
+    index: usize,
+    lookup: &mut ConstLookup,
+) -> Result<ImplItem, LowerError> {
+    let metadata = lower_metadata(
+        loc,
+        impl_scope_name(index),
+        &syn::Visibility::Inherited,
+        &impl_.attrs,
+        impl_.span(),
+    )?;
+    let generics = lower_generics(loc, &impl_.generics)?;
+    let (trait_, is_negative) = if let Some((bang, path, _)) = &impl_.trait_ {
+        let (id, params) = path_to_parts(path)?;
+        let trait_ = TraitId {
+            id,
+            params,
+            is_maybe: false,
+            for_lifetimes: vec![],
+        };
+        (Some(trait_), bang.is_some())
+    } else {
+        (None, false)
+    };
+    let self_type = lower_type(&impl_.self_ty)?;
+
+    let item_metadata =
+        |vis: &syn::Visibility, name: Ident, attrs: &[syn::Attribute], span: proc_macro2::Span| {
+            let mut metadata = lower_metadata(loc, name, vis, attrs, span)?;
+            if trait_.is_some() {
+                metadata.visibility = Visibility::Pub;
+            }
+            Ok(metadata)
+        };
+
+    let mut methods = vec![];
+    let mut types = vec![];
+    let mut consts = vec![];
+    for item in &impl_.items {
+        let result = match item {
+            syn::ImplItem::Method(method) => item_metadata(
+                &method.vis,
+                Ident::from(&method.sig.ident),
+                &method.attrs,
+                method.span(),
+            )
+            .and_then(|mut metadata| {
+                let symbol_metadata = extract_symbol_metadata(&mut metadata)?;
+                methods.push(FunctionItem {
+                    metadata,
+                    symbol_metadata,
+                    signature: lower_signature(loc, &method.sig)?,
+                });
+                Ok(())
+            }),
+            syn::ImplItem::Type(type_) => item_metadata(
+                &type_.vis,
+                Ident::from(&type_.ident),
+                &type_.attrs,
+                type_.span(),
+            )
+            .and_then(|metadata| {
+                types.push(ImplType {
+                    metadata,
+                    generics: lower_generics(loc, &type_.generics)?,
+                    type_: lower_type(&type_.ty)?,
+                });
+                Ok(())
+            }),
+            syn::ImplItem::Const(const_) => item_metadata(
+                &const_.vis,
+                Ident::from(&const_.ident),
+                &const_.attrs,
+                const_.span(),
+            )
+            .and_then(|metadata| {
+                let mut const_ = ConstItem {
+                    metadata,
+                    type_: Box::new(lower_type(&const_.ty)?),
+                    value: ConstExpr(Tokens::from(&const_.expr)),
+                    evaluated: None,
+                };
+                const_.evaluated = evaluate_const_item(&const_, lookup);
+                consts.push(const_);
+                Ok(())
+            }),
+            other => {
+                warn!("skipping unhandled impl item: {:?}", Tokens::from(other));
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) | Err(LowerError::CfgdOut) => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(ImplItem {
+        metadata,
+        scope: loc.module_path.clone(),
+        generics,
+        trait_,
+        self_type,
+        is_unsafe: impl_.unsafety.is_some(),
+        is_negative,
+        methods,
+        types,
+        consts,
+    })
 }
-*/
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::walker::TEST_LOCATION_METADATA;
-    use tendon_api::attributes::{Repr, Visibility};
-    use tendon_api::paths::Path;
-    use tendon_api::types::{PathType, Type};
+    use tendon_api::expressions::ConstValue;
+
+    fn fake(s: &str) -> Identity {
+        Identity::unresolved(&UnresolvedPath::fake(s))
+    }
 
     #[test]
     fn struct_lowering() {
@@ -296,27 +771,36 @@ mod tests {
         };
         let struct_ = lower_struct(&TEST_LOCATION_METADATA, &struct_).unwrap();
 
-        assert_eq!(struct_.name, Ident::from("Thing"));
+        assert_eq!(struct_.metadata.name, Ident::from("Thing"));
 
         assert_eq!(struct_.metadata.visibility, Visibility::Pub);
-        assert_eq!(struct_.type_metadata.repr, Repr::C);
-        assert_eq!(struct_.type_metadata.derives[0].path, Path::fake("Clone"));
+        assert_eq!(struct_.type_metadata.repr.base, ReprBase::C);
+        assert_eq!(struct_.type_metadata.derives[0].id, fake("Clone"));
         assert_eq!(struct_.kind, StructKind::Named);
+        assert_eq!(struct_.generics.lifetimes.len(), 1);
+        assert_eq!(
+            struct_.generics.lifetimes[0].metadata.name,
+            Ident::lifetime("a")
+        );
+        assert_eq!(struct_.generics.types.len(), 1);
+        assert_eq!(struct_.generics.types[0].metadata.name, Ident::from("T"));
+        assert_eq!(struct_.generics.where_predicates.len(), 1);
         assert_eq!(struct_.fields.len(), 4);
-        assert_eq!(struct_.fields[0].name, Ident::from("reference"));
-        assert_eq!(struct_.fields[1].name, Ident::from("others"));
-        assert_eq!(struct_.fields[2].name, Ident::from("count"));
-        assert_eq!(struct_.fields[3].name, Ident::from("path"));
+        assert_eq!(struct_.fields[0].metadata.name, Ident::from("reference"));
+        assert_eq!(struct_.fields[1].metadata.name, Ident::from("others"));
+        assert_eq!(struct_.fields[2].metadata.name, Ident::from("count"));
+        assert_eq!(struct_.fields[3].metadata.name, Ident::from("path"));
 
-        assert_match!(struct_.fields[2].type_, Type::Path(PathType { path, params }) => {
-            assert_eq!(path, &Path::fake("i32"));
+        assert_match!(struct_.fields[2].type_, TypeId::Path(PathType { path, params }) => {
+            assert_eq!(path, &fake("i32"));
             assert!(params.is_empty());
         });
 
+        let private = Visibility::InScope(TEST_LOCATION_METADATA.module_path.clone());
         assert_eq!(struct_.fields[0].metadata.visibility, Visibility::Pub);
-        assert_eq!(struct_.fields[1].metadata.visibility, Visibility::NonPub);
-        assert_eq!(struct_.fields[2].metadata.visibility, Visibility::NonPub);
-        assert_eq!(struct_.fields[3].metadata.visibility, Visibility::NonPub);
+        assert_eq!(struct_.fields[1].metadata.visibility, private);
+        assert_eq!(struct_.fields[2].metadata.visibility, private);
+        assert_eq!(struct_.fields[3].metadata.visibility, private);
         assert_eq!(
             struct_.fields[0].metadata.docs,
             Some(" This is a reference to a different thing.".into())
@@ -336,18 +820,18 @@ mod tests {
                 Variant3 { val: i32 }
             }
         };
-        let enum_ = lower_enum(&TEST_LOCATION_METADATA, &enum_).unwrap();
+        let enum_ = lower_enum(&TEST_LOCATION_METADATA, &enum_, &mut |_| None).unwrap();
 
-        assert_eq!(enum_.name, Ident::from("Thing2"));
+        assert_eq!(enum_.metadata.name, Ident::from("Thing2"));
         assert_eq!(
-            enum_.type_metadata.repr,
-            Repr::IntOuterTag(Ident::from("i8"))
+            enum_.type_metadata.repr.base,
+            ReprBase::IntOuterTag(Ident::from("i8"))
         );
 
         assert_eq!(enum_.variants.len(), 3);
-        assert_eq!(enum_.variants[0].name, Ident::from("Variant1"));
-        assert_eq!(enum_.variants[1].name, Ident::from("Variant2"));
-        assert_eq!(enum_.variants[2].name, Ident::from("Variant3"));
+        assert_eq!(enum_.variants[0].metadata.name, Ident::from("Variant1"));
+        assert_eq!(enum_.variants[1].metadata.name, Ident::from("Variant2"));
+        assert_eq!(enum_.variants[2].metadata.name, Ident::from("Variant3"));
         assert_eq!(enum_.variants[0].kind, StructKind::Unit);
         assert_eq!(enum_.variants[1].kind, StructKind::Tuple);
         assert_eq!(enum_.variants[2].kind, StructKind::Named);
@@ -359,7 +843,7 @@ mod tests {
         assert_eq!(enum_.variants[0].metadata.extra_attributes.len(), 1);
         assert_eq!(
             enum_.variants[0].metadata.extra_attributes[0].path(),
-            &Path::fake("attribute")
+            &UnresolvedPath::fake("attribute")
         );
         assert_eq!(
             enum_.variants[0].metadata.extra_attributes[0].get_assigned_string(),
@@ -370,8 +854,91 @@ mod tests {
         assert_eq!(enum_.variants[1].fields.len(), 1);
         assert_eq!(enum_.variants[2].fields.len(), 1);
 
-        assert_eq!(enum_.variants[1].fields[0].name, Ident::from("0"));
-        assert_eq!(enum_.variants[2].fields[0].name, Ident::from("val"));
+        assert_eq!(enum_.variants[1].fields[0].metadata.name, Ident::from("0"));
+        assert_eq!(
+            enum_.variants[2].fields[0].metadata.name,
+            Ident::from("val")
+        );
+        assert_eq!(
+            enum_.variants[2].evaluated_discriminant,
+            Some(ConstValue::Int(2, Ident::from("i8")))
+        );
+    }
+
+    #[test]
+    fn enum_discriminants() {
+        spoor::init();
+        let int = |value, type_| Some(ConstValue::Int(value, Ident::from(type_)));
+        let mut lookup = |path: &UnresolvedPath| {
+            if path == &UnresolvedPath::fake("BASE") {
+                Some(ConstValue::Int(16, Ident::from("u32")))
+            } else {
+                None
+            }
+        };
+
+        let enum_: syn::ItemEnum = syn::parse_quote! {
+            pub enum Plain {
+                A,
+                B = 5,
+                #[non_exhaustive]
+                C,
+                D = -1,
+            }
+        };
+        let enum_ = lower_enum(&TEST_LOCATION_METADATA, &enum_, &mut lookup).unwrap();
+        let values: Vec<_> = enum_
+            .variants
+            .iter()
+            .map(|variant| variant.evaluated_discriminant.clone())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                int(0, "isize"),
+                int(5, "isize"),
+                int(6, "isize"),
+                int(-1, "isize")
+            ]
+        );
+        assert!(enum_.variants[0].discriminant.is_none());
+        assert_eq!(
+            enum_.variants[1].discriminant,
+            Some(ConstExpr(Tokens::new("5").unwrap()))
+        );
+        assert!(enum_.variants[2].non_exhaustive);
+        assert!(enum_.variants[2].metadata.extra_attributes.is_empty());
+        assert!(!enum_.variants[3].non_exhaustive);
+
+        let enum_: syn::ItemEnum = syn::parse_quote! {
+            #[repr(u8)]
+            pub enum Flags {
+                A = 1 << 2,
+                B = BASE as u8,
+                C = 255,
+                D,
+                E = UNKNOWN,
+                F,
+            }
+        };
+        let enum_ = lower_enum(&TEST_LOCATION_METADATA, &enum_, &mut lookup).unwrap();
+        let values: Vec<_> = enum_
+            .variants
+            .iter()
+            .map(|variant| variant.evaluated_discriminant.clone())
+            .collect();
+        // `D` overflows, and `F` counts up from something we don't know
+        assert_eq!(
+            values,
+            vec![
+                int(4, "u8"),
+                int(16, "u8"),
+                int(255, "u8"),
+                None,
+                None,
+                None
+            ]
+        );
     }
 
     #[test]
@@ -399,9 +966,10 @@ mod tests {
             Some(".banana".into())
         );
         assert!(!function_.signature.generics.is_empty());
-        assert!(!function_.signature.args.is_empty());
+        assert_eq!(function_.signature.args.len(), 1);
+        assert_eq!(function_.signature.args[0].metadata.name, Ident::from("t"));
         assert!(!function_.signature.ret.is_void());
-        assert_eq!(function_.name, Ident::from("f"));
+        assert_eq!(function_.metadata.name, Ident::from("f"));
 
         let function_ = syn::parse_quote! {
             fn g() {}
@@ -418,6 +986,276 @@ mod tests {
         assert!(function_.signature.generics.is_empty());
         assert!(function_.signature.args.is_empty());
         assert!(function_.signature.ret.is_void());
-        assert_eq!(function_.name, Ident::from("g"));
+        assert_eq!(function_.metadata.name, Ident::from("g"));
+    }
+
+    #[test]
+    fn trait_lowering() {
+        spoor::init();
+        let trait_: syn::ItemTrait = syn::parse_quote! {
+            /// A trait.
+            pub unsafe trait Container<'a, T: Clone>: Clone + 'a where Self: Send {
+                /// The item type.
+                type Item: Debug + 'a = T;
+                type Iter<'b>: Iterator<Item = &'b T> where Self: 'b;
+                const CAPACITY: usize = 16;
+                const ID: u32;
+
+                fn new() -> Self;
+                fn get(&self, i: usize) -> Option<&T>;
+                fn pinned(self: Pin<&mut Self>);
+                fn len(&self) -> usize {
+                    0
+                }
+            }
+        };
+        let trait_ = lower_trait(&TEST_LOCATION_METADATA, &trait_).unwrap();
+
+        assert_eq!(trait_.metadata.name, Ident::from("Container"));
+        assert_eq!(trait_.metadata.docs, Some(" A trait.".into()));
+        assert!(trait_.is_unsafe);
+        assert!(!trait_.is_auto);
+        assert_eq!(trait_.generics.lifetimes.len(), 1);
+        assert_eq!(trait_.generics.types.len(), 1);
+        assert!(trait_.generics.where_predicates.is_empty());
+
+        assert_eq!(trait_.supertraits.len(), 2);
+        assert_eq!(trait_.supertraits[0].id, fake("Clone"));
+        assert_eq!(trait_.supertraits[1].id, fake("Send"));
+        assert_eq!(trait_.lifetime_bounds.len(), 1);
+
+        assert_eq!(trait_.types.len(), 2);
+        assert_eq!(trait_.types[0].metadata.name, Ident::from("Item"));
+        assert_eq!(
+            trait_.types[0].metadata.docs,
+            Some(" The item type.".into())
+        );
+        assert_eq!(trait_.types[0].trait_bounds[0].id, fake("Debug"));
+        assert_eq!(trait_.types[0].lifetime_bounds.len(), 1);
+        assert_match!(trait_.types[0].default, Some(TypeId::Path(PathType { path, .. })) => {
+            assert_eq!(path, &fake("T"));
+        });
+        assert_eq!(trait_.types[1].metadata.name, Ident::from("Iter"));
+        assert_eq!(trait_.types[1].generics.lifetimes.len(), 1);
+        assert!(trait_.types[1].default.is_none());
+
+        assert_eq!(trait_.consts.len(), 2);
+        assert_eq!(trait_.consts[0].metadata.name, Ident::from("CAPACITY"));
+        assert_eq!(
+            trait_.consts[0].default,
+            Some(ConstExpr(Tokens::new("16").unwrap()))
+        );
+        assert!(trait_.consts[1].default.is_none());
+
+        assert_eq!(trait_.methods.len(), 4);
+        assert_eq!(trait_.methods[0].metadata.name, Ident::from("new"));
+        assert_eq!(trait_.methods[0].metadata.visibility, Visibility::Pub);
+        assert_match!(trait_.methods[0].signature.receiver, Receiver::None);
+        assert_match!(
+            trait_.methods[1].signature.receiver,
+            Receiver::RefSelf { mut_: false, .. }
+        );
+        assert_eq!(trait_.methods[1].signature.args.len(), 1);
+        assert_match!(trait_.methods[2].signature.receiver, Receiver::Other(..));
+        assert!(trait_.methods[2].signature.args.is_empty());
+        assert!(!trait_.methods[0].is_provided);
+        assert!(trait_.methods[3].is_provided);
+
+        let auto: syn::ItemTrait = syn::parse_quote! {
+            auto trait Marker {}
+        };
+        let auto = lower_trait(&TEST_LOCATION_METADATA, &auto).unwrap();
+        assert!(auto.is_auto);
+        assert!(!auto.is_unsafe);
+        assert!(auto.methods.is_empty());
+    }
+
+    #[test]
+    fn alias_lowering() {
+        spoor::init();
+        let alias: syn::ItemType = syn::parse_quote! {
+            /// A result.
+            pub type Result<T, E = Error> = std::result::Result<T, E>;
+        };
+        let alias = lower_type_alias(&TEST_LOCATION_METADATA, &alias).unwrap();
+        assert_eq!(alias.metadata.name, Ident::from("Result"));
+        assert_eq!(alias.metadata.docs, Some(" A result.".into()));
+        assert_eq!(alias.generics.types.len(), 2);
+        assert!(alias.generics.types[0].default.is_none());
+        assert!(alias.generics.types[1].default.is_some());
+        assert_match!(alias.target, TypeId::Path(PathType { path, params }) => {
+            assert_eq!(path, &fake("std::result::Result"));
+            assert_eq!(params.type_bindings.len(), 2);
+        });
+    }
+
+    #[test]
+    fn union_lowering() {
+        spoor::init();
+        let union_: syn::ItemUnion = syn::parse_quote! {
+            /// Bits.
+            #[repr(C)]
+            pub union Bits<T: Copy> {
+                pub int: u32,
+                float: f32,
+                other: T,
+            }
+        };
+        let union_ = lower_union(&TEST_LOCATION_METADATA, &union_).unwrap();
+        assert_eq!(union_.metadata.name, Ident::from("Bits"));
+        assert_eq!(union_.metadata.docs, Some(" Bits.".into()));
+        assert_eq!(union_.type_metadata.repr.base, ReprBase::C);
+        assert_eq!(union_.generics.types.len(), 1);
+        assert_eq!(union_.fields.len(), 3);
+        assert_eq!(union_.fields[0].metadata.name, Ident::from("int"));
+        assert_eq!(union_.fields[0].metadata.visibility, Visibility::Pub);
+        assert_eq!(
+            union_.fields[1].metadata.visibility,
+            Visibility::InScope(TEST_LOCATION_METADATA.module_path.clone())
+        );
+        assert_match!(union_.fields[2].type_, TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &fake("T"));
+        });
+    }
+
+    #[test]
+    fn const_static_lowering() {
+        spoor::init();
+        let const_: syn::ItemConst = syn::parse_quote! {
+            /// The answer.
+            pub const ANSWER: u8 = 6 * 7;
+        };
+        let const_ = lower_const(&TEST_LOCATION_METADATA, &const_, &mut |_| None).unwrap();
+        assert_eq!(const_.metadata.name, Ident::from("ANSWER"));
+        assert_eq!(const_.metadata.docs, Some(" The answer.".into()));
+        assert_match!(*const_.type_, TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &fake("u8"));
+        });
+        assert_eq!(const_.value, ConstExpr(Tokens::new("6 * 7").unwrap()));
+        assert_eq!(
+            const_.evaluated,
+            Some(ConstValue::Int(42, Ident::from("u8")))
+        );
+
+        let opaque: syn::ItemConst = syn::parse_quote! {
+            const OPAQUE: Thing = Thing::new();
+        };
+        let opaque = lower_const(&TEST_LOCATION_METADATA, &opaque, &mut |_| None).unwrap();
+        assert_eq!(opaque.evaluated, None);
+
+        let static_: syn::ItemStatic = syn::parse_quote! {
+            #[no_mangle]
+            pub static mut COUNTER: AtomicUsize = AtomicUsize::new(0);
+        };
+        let static_ = lower_static(&TEST_LOCATION_METADATA, &static_).unwrap();
+        assert_eq!(static_.metadata.name, Ident::from("COUNTER"));
+        assert!(static_.mut_);
+        assert!(static_.symbol_metadata.no_mangle);
+    }
+
+    #[test]
+    fn foreign_mod_lowering() {
+        spoor::init();
+        let foreign_mod: syn::ItemForeignMod = syn::parse_quote! {
+            #[link(name = "z", kind = "static")]
+            #[link(name = "m")]
+            extern "C" {
+                /// Compress some bytes.
+                #[link_name = "compress2"]
+                pub fn compress(dest: *mut u8, dest_len: *mut c_ulong, ...) -> c_int;
+                pub static mut errno: c_int;
+                pub type z_stream;
+                #[cfg(any())]
+                pub fn gone();
+            }
+        };
+        let (symbols, types) = lower_foreign_mod(&TEST_LOCATION_METADATA, &foreign_mod).unwrap();
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(types.len(), 1);
+
+        if let SymbolItem::ForeignFunction(fn_) = &symbols[0] {
+            assert_eq!(fn_.metadata.name, Ident::from("compress"));
+            assert_eq!(fn_.metadata.docs, Some(" Compress some bytes.".into()));
+            assert_eq!(fn_.foreign_metadata.abi, Abi::C);
+            assert_eq!(fn_.foreign_metadata.link_name, Some("compress2".into()));
+            assert_eq!(fn_.foreign_metadata.links.len(), 2);
+            assert_eq!(fn_.foreign_metadata.links[0].name, "z");
+            assert_eq!(fn_.foreign_metadata.links[0].kind, Some("static".into()));
+            assert_eq!(fn_.foreign_metadata.links[1].kind, None);
+            assert_eq!(fn_.signature.abi, Abi::C);
+            assert!(fn_.signature.variadic);
+            assert_eq!(fn_.signature.args.len(), 2);
+        } else {
+            panic!("expected foreign function");
+        }
+        if let SymbolItem::ForeignStatic(static_) = &symbols[1] {
+            assert_eq!(static_.metadata.name, Ident::from("errno"));
+            assert!(static_.mut_);
+            assert_eq!(static_.foreign_metadata.link_name, None);
+        } else {
+            panic!("expected foreign static");
+        }
+        if let TypeItem::ForeignType(type_) = &types[0] {
+            assert_eq!(type_.metadata.name, Ident::from("z_stream"));
+            assert_eq!(type_.metadata.visibility, Visibility::Pub);
+        } else {
+            panic!("expected foreign type");
+        }
+    }
+
+    #[test]
+    fn impl_lowering() {
+        spoor::init();
+        let impl_: syn::ItemImpl = syn::parse_quote! {
+            impl<'a, T: Clone> Iterator for Iter<'a, T> where Vec<T>: Debug {
+                type Item = &'a T;
+                const STEP: usize = 2 * 2;
+                fn next(&mut self) -> Option<&'a T> {
+                    None
+                }
+                #[cfg(any())]
+                fn gone(&self) {}
+            }
+        };
+        let impl_ = lower_impl(&TEST_LOCATION_METADATA, &impl_, 0, &mut |_| None).unwrap();
+        assert_eq!(impl_.scope, TEST_LOCATION_METADATA.module_path);
+        assert_eq!(impl_.generics.lifetimes.len(), 1);
+        assert_eq!(impl_.generics.types.len(), 1);
+        assert_eq!(impl_.generics.where_predicates.len(), 1);
+        assert_eq!(impl_.trait_.as_ref().unwrap().id, fake("Iterator"));
+        assert!(!impl_.is_negative);
+        assert_match!(impl_.self_type, TypeId::Path(PathType { path, params }) => {
+            assert_eq!(path, &fake("Iter"));
+            assert_eq!(params.lifetimes.len(), 1);
+        });
+        assert_eq!(impl_.methods.len(), 1);
+        assert_eq!(impl_.methods[0].metadata.name, Ident::from("next"));
+        assert_eq!(impl_.methods[0].metadata.visibility, Visibility::Pub);
+        assert_eq!(impl_.types.len(), 1);
+        assert_eq!(impl_.types[0].metadata.name, Ident::from("Item"));
+        assert_eq!(
+            impl_.consts[0].evaluated,
+            Some(ConstValue::Int(4, Ident::from("usize")))
+        );
+
+        let impl_: syn::ItemImpl = syn::parse_quote! {
+            impl !Send for Thing {}
+        };
+        let impl_ = lower_impl(&TEST_LOCATION_METADATA, &impl_, 0, &mut |_| None).unwrap();
+        assert!(impl_.is_negative);
+
+        let impl_: syn::ItemImpl = syn::parse_quote! {
+            impl Thing {
+                pub fn new() -> Thing {}
+                fn private(&self) {}
+            }
+        };
+        let impl_ = lower_impl(&TEST_LOCATION_METADATA, &impl_, 0, &mut |_| None).unwrap();
+        assert!(impl_.trait_.is_none());
+        assert_eq!(impl_.methods[0].metadata.visibility, Visibility::Pub);
+        assert_eq!(
+            impl_.methods[1].metadata.visibility,
+            Visibility::InScope(TEST_LOCATION_METADATA.module_path.clone())
+        );
     }
 }
@@ -1,77 +1,80 @@
 //! Lowering for referenced types.
-use super::{generics::lower_lifetime, LowerError};
+use super::{
+    generics::{lower_bound_lifetimes, lower_lifetime},
+    LowerError,
+};
 use tendon_api::{
-    expressions::ConstExpr,
-    generics::TypeBounds,
-    paths::Ident,
-    paths::Path,
-    tokens::Tokens,
-    types::{
-        ArrayType, BareFnType, GenericParams, ImplTraitType, NeverType, PathType, PointerType,
-        QSelfType, ReferenceType, SliceType, Trait, TraitObjectType, TupleType, Type,
+    expressions::{ConstArg, ConstExpr},
+    identities::Identity,
+    identities::{
+        ArrayType, BareFnType, GenericParams, ImplTraitType, LifetimeId, NeverType, PathType,
+        PointerType, QSelfType, ReferenceType, SliceType, TraitId, TraitObjectType, TupleType,
+        TypeId,
     },
+    paths::{Ident, UnresolvedPath},
+    tokens::Tokens,
 };
 
-/// Lower a syn type to a tendon-api type (with `Unresolved` paths, no resolution happens here.)
-pub fn lower_type(type_: &syn::Type) -> Result<Type, LowerError> {
+/// Lower a syn type to a tendon-api type (with `Identity::unresolved` paths, no resolution
+/// happens here.)
+pub fn lower_type(type_: &syn::Type) -> Result<TypeId, LowerError> {
     match type_ {
         syn::Type::Path(path) => lower_type_path(path),
-        syn::Type::Slice(slice) => Ok(Type::Slice(SliceType {
+        syn::Type::Slice(slice) => Ok(TypeId::Slice(SliceType {
             type_: Box::new(lower_type(&slice.elem)?),
         })),
-        syn::Type::Array(array) => Ok(Type::Array(ArrayType {
+        syn::Type::Array(array) => Ok(TypeId::Array(ArrayType {
             type_: Box::new(lower_type(&array.elem)?),
-            len: ConstExpr(Tokens::from(&array.len)),
+            len: lower_const_arg(&array.len),
         })),
-        syn::Type::Ptr(pointer) => Ok(Type::Pointer(PointerType {
+        syn::Type::Ptr(pointer) => Ok(TypeId::Pointer(PointerType {
             type_: Box::new(lower_type(&pointer.elem)?),
             mut_: pointer.mutability.is_some(),
         })),
-        syn::Type::Reference(reference) => Ok(Type::Reference(ReferenceType {
+        syn::Type::Reference(reference) => Ok(TypeId::Reference(ReferenceType {
             type_: Box::new(lower_type(&reference.elem)?),
             mut_: reference.mutability.is_some(),
             lifetime: reference.lifetime.as_ref().map(lower_lifetime),
         })),
-        syn::Type::Never(_) => Ok(Type::Never(NeverType)),
-        syn::Type::Tuple(tuple) => Ok(Type::Tuple(TupleType {
+        syn::Type::Never(_) => Ok(TypeId::Never(NeverType)),
+        syn::Type::Tuple(tuple) => Ok(TypeId::Tuple(TupleType {
             types: tuple
                 .elems
                 .iter()
                 .map(|type_| lower_type(type_))
-                .collect::<Result<Vec<Type>, LowerError>>()?,
+                .collect::<Result<Vec<TypeId>, LowerError>>()?,
         })),
         syn::Type::TraitObject(trait_object) => {
-            let bounds = lower_type_bounds(&trait_object.bounds)?;
-            Ok(Type::TraitObject(TraitObjectType { bounds }))
+            let (trait_bounds, _) = lower_type_bounds(&trait_object.bounds)?;
+            Ok(TypeId::TraitObject(TraitObjectType { trait_bounds }))
         }
         syn::Type::ImplTrait(impl_trait) => {
-            let bounds = lower_type_bounds(&impl_trait.bounds)?;
-            Ok(Type::ImplTrait(ImplTraitType { bounds }))
-        }
-        syn::Type::BareFn(bare_fn) => {
-            if bare_fn.lifetimes.is_none() {
-                Ok(Type::BareFn(BareFnType {
-                    args: bare_fn
-                        .inputs
-                        .iter()
-                        .map(|arg| lower_type(&arg.ty))
-                        .collect::<Result<Vec<Type>, LowerError>>()?,
-                    ret: Box::new(lower_return_type(&bare_fn.output)?),
-                    varargs: bare_fn.variadic.is_some(),
-                    unsafe_: bare_fn.unsafety.is_some(),
-                }))
-            } else {
-                Err(LowerError::UnhandledType(Tokens::from(&bare_fn.inputs)))
-            }
+            let (trait_bounds, lifetime_bounds) = lower_type_bounds(&impl_trait.bounds)?;
+            Ok(TypeId::ImplTrait(ImplTraitType {
+                trait_bounds,
+                lifetime_bounds,
+            }))
         }
+        syn::Type::BareFn(bare_fn) => Ok(TypeId::BareFn(BareFnType {
+            args: bare_fn
+                .inputs
+                .iter()
+                .map(|arg| lower_type(&arg.ty))
+                .collect::<Result<Vec<TypeId>, LowerError>>()?,
+            ret: Box::new(lower_return_type(&bare_fn.output)?),
+            varargs: bare_fn.variadic.is_some(),
+            unsafe_: bare_fn.unsafety.is_some(),
+            for_lifetimes: lower_bound_lifetimes(bare_fn.lifetimes.as_ref()),
+        })),
         syn::Type::Paren(paren) => lower_type(&paren.elem),
+        syn::Type::Group(group) => lower_type(&group.elem),
         syn::Type::Macro(_) => Err(LowerError::TypePositionMacro),
         other => Err(LowerError::UnhandledType(Tokens::from(&other))),
     }
 }
 
 /// Lower a TypePath. Big, so broken out into its own function.
-fn lower_type_path(path: &syn::TypePath) -> Result<Type, LowerError> {
+fn lower_type_path(path: &syn::TypePath) -> Result<TypeId, LowerError> {
     if let Some(qself) = &path.qself {
         // <T as Q>::V
 
@@ -94,68 +97,103 @@ fn lower_type_path(path: &syn::TypePath) -> Result<Type, LowerError> {
                 "qself without trait",
             ));
         }
+        // remove trailing `::`
+        let last = inner_path.segments.pop().unwrap().into_value();
+        inner_path.segments.push(last);
 
-        let (path, generics) = path_to_parts(&inner_path)?;
+        let (id, params) = path_to_parts(&inner_path)?;
 
-        Ok(Type::QSelf(QSelfType {
+        Ok(TypeId::QSelf(QSelfType {
             self_,
             output_,
-            trait_: Trait {
-                path,
-                params: generics,
+            trait_: TraitId {
+                id,
+                params,
                 is_maybe: false,
+                for_lifetimes: vec![],
             },
         }))
     } else {
-        let (path, generics) = path_to_parts(&path.path)?;
-        Ok(Type::Path(PathType {
-            path,
-            params: generics,
-        }))
+        let (path, params) = path_to_parts(&path.path)?;
+        Ok(TypeId::Path(PathType { path, params }))
+    }
+}
+
+/// Lower a const generic argument or array length.
+/// Bare paths, optionally wrapped in braces (`N`, `{ N }`), are kept as paths so they can be
+/// resolved later.
+pub fn lower_const_arg(expr: &syn::Expr) -> ConstArg {
+    match expr {
+        syn::Expr::Path(path)
+            if path.qself.is_none()
+                && path
+                    .path
+                    .segments
+                    .iter()
+                    .all(|seg| seg.arguments.is_empty()) =>
+        {
+            ConstArg::Path(Identity::unresolved(&UnresolvedPath::from(&path.path)))
+        }
+        syn::Expr::Block(block) if block.block.stmts.len() == 1 => {
+            if let syn::Stmt::Expr(inner) = &block.block.stmts[0] {
+                lower_const_arg(inner)
+            } else {
+                ConstArg::Expr(ConstExpr(Tokens::from(expr)))
+            }
+        }
+        syn::Expr::Paren(paren) => lower_const_arg(&paren.expr),
+        syn::Expr::Group(group) => lower_const_arg(&group.expr),
+        other => ConstArg::Expr(ConstExpr(Tokens::from(other))),
     }
 }
 
 /// Lower a return type.
-pub fn lower_return_type(ret: &syn::ReturnType) -> Result<Type, LowerError> {
+pub fn lower_return_type(ret: &syn::ReturnType) -> Result<TypeId, LowerError> {
     match ret {
         syn::ReturnType::Type(_, ret) => Ok(lower_type(&ret)?),
-        syn::ReturnType::Default => Ok(Type::Tuple(TupleType { types: vec![] })),
+        syn::ReturnType::Default => Ok(TypeId::Tuple(TupleType { types: vec![] })),
     }
 }
 
+/// Lower a single trait bound, `?Sized`, `Iterator<Item=u8>`.
+pub fn lower_trait_bound(trait_bound: &syn::TraitBound) -> Result<TraitId, LowerError> {
+    let (id, params) = path_to_parts(&trait_bound.path)?;
+    let is_maybe = if let syn::TraitBoundModifier::Maybe(_) = trait_bound.modifier {
+        true
+    } else {
+        false
+    };
+    Ok(TraitId {
+        id,
+        params,
+        is_maybe,
+        for_lifetimes: lower_bound_lifetimes(trait_bound.lifetimes.as_ref()),
+    })
+}
+
 /// Convert a set of type bounds to a list of trait bounds + a list of lifetime bounds
 pub fn lower_type_bounds(
     bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, syn::token::Add>,
-) -> Result<TypeBounds, LowerError> {
+) -> Result<(Vec<TraitId>, Vec<LifetimeId>), LowerError> {
     let mut traits = Vec::new();
     let mut lifetimes = Vec::new();
     for bound in bounds.iter() {
         match bound {
-            syn::TypeParamBound::Trait(trait_bound) => {
-                if trait_bound.lifetimes.is_some() {
-                    return Err(LowerError::NoHRTBsYet(Tokens::from(bound)));
-                }
-                let (path, generics) = path_to_parts(&trait_bound.path)?;
-                let is_maybe = if let syn::TraitBoundModifier::Maybe(_) = trait_bound.modifier {
-                    true
-                } else {
-                    false
-                };
-                traits.push(Trait {
-                    path,
-                    params: generics,
-                    is_maybe,
-                })
-            }
+            syn::TypeParamBound::Trait(trait_bound) => traits.push(lower_trait_bound(trait_bound)?),
             syn::TypeParamBound::Lifetime(lt) => lifetimes.push(lower_lifetime(lt)),
         }
     }
 
-    Ok(TypeBounds { traits, lifetimes })
+    Ok((traits, lifetimes))
 }
 
 /// Split a syn::Path to its constituent actual path and generic arguments.
-pub fn path_to_parts(path: &syn::Path) -> Result<(Path, GenericParams), LowerError> {
+///
+/// Positional arguments can't be matched to their parameters until the path is resolved, so
+/// they're stored under `Ident::positional` keys. Lifetimes are counted on their own; types and
+/// consts are counted together, since a bare `N` could be either until it's resolved.
+/// (Type parameters always come before const parameters, so the `i`th type argument has key `i`.)
+pub fn path_to_parts(path: &syn::Path) -> Result<(Identity, GenericParams), LowerError> {
     // No QSelf
     // check for generics
     let mut syn_args = None;
@@ -170,24 +208,27 @@ pub fn path_to_parts(path: &syn::Path) -> Result<(Path, GenericParams), LowerErr
         }
     }
 
-    let mut args = GenericParams {
-        lifetimes: vec![],
-        types: vec![],
-        type_bindings: vec![],
-        consts: vec![],
-    };
+    let mut args = GenericParams::default();
 
     match syn_args {
         Some(syn::PathArguments::AngleBracketed(brangled)) => {
             for arg in brangled.args.iter() {
                 match arg {
-                    syn::GenericArgument::Lifetime(lt) => args.lifetimes.push(lower_lifetime(lt)),
-                    syn::GenericArgument::Type(ty) => args.types.push(lower_type(ty)?),
-                    syn::GenericArgument::Binding(binding) => args
-                        .type_bindings
-                        .push((Ident::from(&binding.ident), lower_type(&binding.ty)?)),
+                    syn::GenericArgument::Lifetime(lt) => {
+                        let key = Ident::positional(args.lifetimes.len());
+                        args.lifetimes.insert(key, lower_lifetime(lt));
+                    }
+                    syn::GenericArgument::Type(ty) => {
+                        let key = Ident::positional(positional_args(&args));
+                        args.type_bindings.insert(key, lower_type(ty)?);
+                    }
+                    syn::GenericArgument::Binding(binding) => {
+                        args.type_bindings
+                            .insert(Ident::from(&binding.ident), lower_type(&binding.ty)?);
+                    }
                     syn::GenericArgument::Const(expr) => {
-                        args.consts.push(ConstExpr(Tokens::from(&expr)))
+                        let key = Ident::positional(positional_args(&args));
+                        args.consts.insert(key, lower_const_arg(expr));
                     }
                     _ => {
                         return Err(LowerError::MalformedType(
@@ -199,23 +240,29 @@ pub fn path_to_parts(path: &syn::Path) -> Result<(Path, GenericParams), LowerErr
             }
         }
         Some(syn::PathArguments::Parenthesized(parened)) => {
-            // Fn(X,Y) -> Z
+            // Fn(X, Y) -> Z
             // is lowered to
             // Fn<(X, Y), Output=Z>
-            args.types.push(Type::Tuple(TupleType {
-                types: parened
-                    .inputs
-                    .iter()
-                    .map(|ty| lower_type(ty))
-                    .collect::<Result<Vec<Type>, LowerError>>()?,
-            }));
-            args.type_bindings
-                .push((Ident::from("Output"), lower_return_type(&parened.output)?));
+            let inputs = parened
+                .inputs
+                .iter()
+                .map(|ty| lower_type(ty))
+                .collect::<Result<Vec<TypeId>, LowerError>>()?;
+            args = GenericParams::fn_sugar(inputs, lower_return_type(&parened.output)?);
         }
         _ => (),
     }
 
-    Ok((Path::from(path), args))
+    Ok((Identity::unresolved(&UnresolvedPath::from(path)), args))
+}
+
+/// The number of positional type and const arguments in a set of params.
+fn positional_args(args: &GenericParams) -> usize {
+    args.type_bindings
+        .keys()
+        .chain(args.consts.keys())
+        .filter(|k| k.as_positional().is_some())
+        .count()
 }
 
 #[cfg(test)]
@@ -224,15 +271,23 @@ mod tests {
     use std::error::Error;
     use syn::parse_quote;
 
-    fn lower(s: &str) -> Result<Type, Box<dyn Error>> {
+    fn lower(s: &str) -> Result<TypeId, Box<dyn Error>> {
         Ok(lower_type(&syn::parse_str(s)?)?)
     }
 
+    fn fake(s: &str) -> Identity {
+        Identity::unresolved(&UnresolvedPath::fake(s))
+    }
+
+    fn lt(s: &str) -> Identity {
+        Identity::unresolved(&UnresolvedPath::new(false, &[Ident::lifetime(s)]))
+    }
+
     #[test]
     fn simple() {
         spoor::init();
-        assert_match!(lower("!"), Ok(Type::Never(_)));
-        assert_match!(lower("()"), Ok(Type::Tuple(TupleType {types})) => {
+        assert_match!(lower("!"), Ok(TypeId::Never(_)));
+        assert_match!(lower("()"), Ok(TypeId::Tuple(TupleType {types})) => {
             assert_eq!(types.len(), 0);
         });
     }
@@ -240,34 +295,36 @@ mod tests {
     #[test]
     fn impl_dyn_trait() {
         spoor::init();
-        assert_match!(lower("dyn Banana<'a, X> + Copy + ?Sized + 'b"), Ok(Type::TraitObject(TraitObjectType { bounds: TypeBounds { lifetimes, traits } })) => {
-            assert_eq!(traits.len(), 3);
-            assert_eq!(lifetimes.len(), 1);
-            assert_eq!(traits[0].path, Path::fake("Banana"));
-            assert_eq!(traits[0].params.lifetimes[0].0, Ident::from("a"));
-            assert_match!(traits[0].params.types[0], Type::Path(PathType { path, ..}) => {
-                assert_eq!(path, &Path::fake("X"));
+        assert_match!(lower("dyn Banana<'a, X> + Copy + ?Sized + 'b"), Ok(TypeId::TraitObject(TraitObjectType { trait_bounds })) => {
+            assert_eq!(trait_bounds.len(), 3);
+            assert_eq!(trait_bounds[0].id, fake("Banana"));
+            assert_eq!(trait_bounds[0].params.lifetimes[&Ident::positional(0)].id(), &lt("a"));
+            assert_match!(trait_bounds[0].params.type_bindings[&Ident::positional(0)], TypeId::Path(PathType { path, ..}) => {
+                assert_eq!(path, &fake("X"));
             });
-            assert_eq!(traits[1].path, Path::fake("Copy"));
-            assert_eq!(traits[2].path, Path::fake("Sized"));
-            assert_eq!(traits[2].is_maybe, true);
-            assert_eq!(lifetimes[0].0, Ident::from("b"));
+            assert_eq!(trait_bounds[1].id, fake("Copy"));
+            assert_eq!(trait_bounds[2].id, fake("Sized"));
+            assert_eq!(trait_bounds[2].is_maybe, true);
         });
-        assert_match!(lower("impl Banana<'a, X> + Copy + ?Sized + 'b"), Ok(Type::ImplTrait(ImplTraitType { bounds: TypeBounds { lifetimes, traits }})) => {
-            assert_eq!(traits.len(), 3);
-            assert_eq!(lifetimes.len(), 1);
-            assert_eq!(traits[0].path, Path::fake("Banana"));
-            assert_eq!(traits[0].params.lifetimes[0].0, Ident::from("a"));
-            assert_match!(traits[0].params.types[0], Type::Path(PathType { path, ..}) => {
-                assert_eq!(path, &Path::fake("X"));
-            });
-            assert_eq!(traits[1].path, Path::fake("Copy"));
-            assert_eq!(traits[2].path, Path::fake("Sized"));
-            assert_eq!(traits[2].is_maybe, true);
-            assert_eq!(lifetimes[0].0, Ident::from("b"));
+        assert_match!(lower("impl Banana<'a, X> + Copy + ?Sized + 'b"), Ok(TypeId::ImplTrait(ImplTraitType { trait_bounds, lifetime_bounds })) => {
+            assert_eq!(trait_bounds.len(), 3);
+            assert_eq!(lifetime_bounds.len(), 1);
+            assert_eq!(trait_bounds[0].id, fake("Banana"));
+            assert_eq!(trait_bounds[1].id, fake("Copy"));
+            assert_eq!(trait_bounds[2].id, fake("Sized"));
+            assert_eq!(trait_bounds[2].is_maybe, true);
+            assert_eq!(lifetime_bounds[0].id(), &lt("b"));
+        });
+        assert_match!(lower("impl for<'a> Banana<'a>"), Ok(TypeId::ImplTrait(ImplTraitType { trait_bounds, .. })) => {
+            assert_eq!(trait_bounds[0].for_lifetimes.len(), 1);
+            assert_eq!(trait_bounds[0].for_lifetimes[0].id(), &lt("a"));
+            assert_eq!(trait_bounds[0].params.lifetimes[&Ident::positional(0)].id(), &lt("a"));
+        });
+        assert_match!(lower("for<'a, 'b> fn(&'a u8, &'b u8) -> &'a u8"), Ok(TypeId::BareFn(BareFnType { for_lifetimes, args, .. })) => {
+            assert_eq!(for_lifetimes.len(), 2);
+            assert_eq!(for_lifetimes[1].id(), &lt("b"));
+            assert_eq!(args.len(), 2);
         });
-        // no HRTBs yet
-        assert_match!(lower("impl for<'a> Banana<'a>"), Err(..));
     }
 
     #[test]
@@ -276,18 +333,17 @@ mod tests {
         // TODO is this actually legal?
         assert_match!(lower("<P>::Q"), Err(..));
 
-        assert_match!(lower("<P<F=(::M,)> as F<'a, Z, 2>>::W"), Ok(Type::QSelf(QSelfType {
-            self_, trait_: Trait { path, params, is_maybe }, output_
+        assert_match!(lower("<P<F=(::M,)> as F<'a, Z, 2>>::W"), Ok(TypeId::QSelf(QSelfType {
+            self_, trait_: TraitId { id, params, is_maybe, .. }, output_
         })) => {
-            assert_eq!(path, &Path::fake("F"));
+            assert_eq!(id, &fake("F"));
             assert_eq!(is_maybe, &false);
-            assert_eq!(params.lifetimes[0].0, Ident::from("a"));
-            assert_match!(**self_, Type::Path(PathType { path, params }) => {
-                assert_eq!(path, &Path::fake("P"));
-                assert_eq!(params.type_bindings[0].0, Ident::from("F"));
-                assert_match!(params.type_bindings[0].1, Type::Tuple(TupleType { types }) => {
-                    assert_match!(types[0], Type::Path(PathType { path, .. }) => {
-                        assert_eq!(path, &Path::fake("::M"));
+            assert_eq!(params.lifetimes[&Ident::positional(0)].id(), &lt("a"));
+            assert_match!(**self_, TypeId::Path(PathType { path, params }) => {
+                assert_eq!(path, &fake("P"));
+                assert_match!(params.type_bindings[&Ident::from("F")], TypeId::Tuple(TupleType { types }) => {
+                    assert_match!(types[0], TypeId::Path(PathType { path, .. }) => {
+                        assert_eq!(path, &fake("::M"));
                     });
                 });
             });
@@ -298,31 +354,86 @@ mod tests {
     #[test]
     fn lower_path() {
         spoor::init();
-        assert_match!(lower("::some::Thing<'a, 'b, A, B, C=D, 1>"), Ok(Type::Path(PathType { path, params })) => {
-            assert_eq!(path, &Path::fake("::some::Thing"));
+        assert_match!(lower("::some::Thing<'a, 'b, A, B, C=D, 1>"), Ok(TypeId::Path(PathType { path, params })) => {
+            assert_eq!(path, &fake("::some::Thing"));
 
             assert_eq!(params.lifetimes.len(), 2);
-            assert_eq!(params.types.len(), 2);
-
-            assert_eq!(params.type_bindings.len(), 1);
+            assert_eq!(params.type_bindings.len(), 3);
             assert_eq!(params.consts.len(), 1);
-            assert_eq!(params.lifetimes[0].0, Ident::from("a"));
-            assert_eq!(params.lifetimes[1].0, Ident::from("b"));
-            assert_match!(params.types[0], Type::Path(PathType { path, .. }) => {
-                assert_eq!(path, &Path::fake("A"));
+            assert_eq!(params.lifetimes[&Ident::positional(0)].id(), &lt("a"));
+            assert_eq!(params.lifetimes[&Ident::positional(1)].id(), &lt("b"));
+            assert_match!(params.type_bindings[&Ident::positional(0)], TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &fake("A"));
             });
-            assert_match!(params.types[1], Type::Path(PathType { path, .. }) => {
-                assert_eq!(path, &Path::fake("B"));
+            assert_match!(params.type_bindings[&Ident::positional(1)], TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &fake("B"));
             });
-            assert_eq!(params.type_bindings[0].0, Ident::from("C"));
-            assert_match!(params.type_bindings[0].1, Type::Path(PathType { path, .. }) => {
-                assert_eq!(path, &Path::fake("D"));
+            assert_match!(params.type_bindings[&Ident::from("C")], TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &fake("D"));
             });
-            assert_eq!(params.consts[0].0, Tokens::new("1").unwrap());
+            assert_eq!(params.consts[&Ident::positional(2)], ConstArg::Expr(ConstExpr(Tokens::new("1").unwrap())));
+        });
+        assert_match!(lower("Matrix<T, N, { M }, { M * 2 }>"), Ok(TypeId::Path(PathType { params, .. })) => {
+            // `N` is ambiguous until it's resolved
+            assert_eq!(params.type_bindings.len(), 2);
+            assert_eq!(params.consts[&Ident::positional(2)], ConstArg::Path(fake("M")));
+            assert_eq!(params.consts[&Ident::positional(3)], ConstArg::Expr(ConstExpr(Tokens::new("M * 2").unwrap())));
         });
         assert_match!(lower("::some<A>::thing<B>::Weird<D>"), Err(..));
     }
 
+    #[test]
+    fn fn_sugar() {
+        spoor::init();
+        let bound = |s: &str| match lower(s) {
+            Ok(TypeId::ImplTrait(ImplTraitType {
+                mut trait_bounds, ..
+            })) => trait_bounds.remove(0),
+            other => panic!("not impl trait: {:?}", other),
+        };
+
+        let trait_ = bound("impl Fn(i32, &str) -> String");
+        assert_eq!(trait_.id, fake("Fn"));
+        assert_match!(trait_.as_fn_sugar(), Some((inputs, output)) => {
+            assert_eq!(inputs.len(), 2);
+            assert_match!(inputs[1], TypeId::Reference(..));
+            assert_match!(output, TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &fake("String"));
+            });
+        });
+        assert_eq!(
+            format!("{:?}", trait_),
+            "{unresolved}[0.0.0]::Fn({unresolved}[0.0.0]::i32, &{unresolved}[0.0.0]::str) -> {unresolved}[0.0.0]::String"
+        );
+
+        // no return type means `()`
+        let trait_ = bound("impl FnOnce()");
+        assert_match!(trait_.as_fn_sugar(), Some((inputs, TypeId::Tuple(TupleType { types }))) => {
+            assert!(inputs.is_empty());
+            assert!(types.is_empty());
+        });
+
+        assert_match!(lower("Box<dyn FnMut(&[u8]) + Send>"), Ok(TypeId::Path(PathType { params, .. })) => {
+            assert_match!(&params.type_bindings[&Ident::positional(0)], TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+                assert_eq!(trait_bounds[0].id, fake("FnMut"));
+                assert_match!(trait_bounds[0].as_fn_sugar(), Some((inputs, _)) => {
+                    assert_match!(inputs[0], TypeId::Reference(..));
+                });
+                assert_match!(trait_bounds[1].as_fn_sugar(), None);
+            });
+        });
+
+        // the angle-bracketed form is the same thing
+        let trait_ = bound("impl Fn<(i32,), Output = u8>");
+        assert_match!(trait_.as_fn_sugar(), Some(..));
+        let trait_ = bound("impl Iterator<Item = u8>");
+        assert_match!(trait_.as_fn_sugar(), None);
+        // same shape, but only the `Fn` traits get sugar
+        let trait_ = bound("impl Callback<(i32,), Output = u8>");
+        assert_match!(trait_.as_fn_sugar(), None);
+        assert!(!format!("{:?}", trait_).contains("->"));
+    }
+
     #[test]
     fn malformed_path() {
         spoor::init();
@@ -335,12 +446,12 @@ mod tests {
     #[test]
     fn lower_others() {
         spoor::init();
-        assert_match!(lower("[i32]"), Ok(Type::Slice(..)));
-        assert_match!(lower("[i32; 2]"), Ok(Type::Array(..)));
-        assert_match!(lower("*const i32"), Ok(Type::Pointer(..)));
-        assert_match!(lower("&'a mut i32"), Ok(Type::Reference(..)));
-        assert_match!(lower("fn(i32) -> i32"), Ok(Type::BareFn(..)));
-        assert_match!(lower("Fn(i32) -> i32"), Ok(Type::Path(..)));
+        assert_match!(lower("[i32]"), Ok(TypeId::Slice(..)));
+        assert_match!(lower("[i32; 2]"), Ok(TypeId::Array(..)));
+        assert_match!(lower("*const i32"), Ok(TypeId::Pointer(..)));
+        assert_match!(lower("&'a mut i32"), Ok(TypeId::Reference(..)));
+        assert_match!(lower("fn(i32) -> i32"), Ok(TypeId::BareFn(..)));
+        assert_match!(lower("Fn(i32) -> i32"), Ok(TypeId::Path(..)));
 
         assert_match!(lower("Macro![Thing]"), Err(..));
     }
@@ -1,3 +1,57 @@
+//! Resolution passes, run over a crate's items once walking is complete.
+
+use tendon_api::database::{Crate, Db};
+
+pub mod auto_traits;
+mod const_values;
+mod consts;
+mod doc_links;
+mod elision;
+pub(crate) mod fold;
+mod impl_trait;
+pub mod layout;
+pub mod normalize;
+pub mod object_safety;
+mod paths;
+mod public_paths;
+pub mod reachability;
+mod self_type;
+
+/// Run the resolution passes over a freshly walked crate.
+/// Anything that can't be resolved is recorded in `crate_.resolution_report`.
+pub(crate) fn resolve_crate(db: &Db, crate_: &mut Crate) {
+    const_values::evaluate_consts(db, crate_);
+
+    let mut items = fold::Items::take(crate_);
+
+    // const arguments have to be picked out before their paths are resolved as types.
+    items.fold(&mut consts::ConstArgResolver::new(db, crate_));
+
+    let mut paths = paths::PathResolver::new(db, crate_);
+    items.fold(&mut paths);
+    let mut unresolved = paths.unresolved;
+
+    let mut self_type = self_type::SelfResolver::new(db, &items);
+    items.fold(&mut self_type);
+    unresolved.extend(self_type.unresolved);
+
+    let declared = elision::DeclaredLifetimes::new(db, &items);
+    items.for_each_signature(|scope, _owner, metadata, signature| {
+        elision::elide_lifetimes(scope, metadata, &declared, signature)
+    });
+    items.for_each_signature(impl_trait::desugar_impl_trait_args);
+
+    items.for_each_metadata(|scope, self_type, metadata| {
+        doc_links::resolve_doc_links(db, crate_, scope, self_type, metadata)
+    });
+
+    items.restore(crate_);
+    crate_.resolution_report.unresolved.extend(unresolved);
+    doc_links::resolve_scope_doc_links(db, crate_);
+
+    crate_.public_paths = public_paths::public_paths(crate_);
+}
+
 // https://github.com/rust-lang/rust/tree/master/src/librustc_resolve
 
 /*
@@ -18,7 +72,6 @@
 // TODO: is_safe_for_auto_derive -- trait has no type members
 // TODO: handle rust edition
 // TODO: distinguish between parse / walk failures and deliberately ignored items
-// TODO: for traits, `Self` is a generic; for other things, `Self` should resolve to self
 
 // https://github.com/rust-lang/rustc-guide/blob/master/src/name-resolution.md
 // https://doc.rust-lang.org/reference/items/extern-crates.html
@@ -99,9 +152,6 @@ lazy_static! {
 //      glob import
 //
 
-// TODO: choose "canonical import" for every defined import
-//     prefer short paths
-
 // TODO: fall back to non-pub paths in globs in case of lookup failure
 //     paper over shadowing issues...
 
@@ -200,7 +250,7 @@ pub fn absolutize_imports(
 mod tests {
     use super::*;
     use tendom_api::Map;
-    use tendon_api::attributes::{Metadata, Span, Visibility};
+    use tendon_api::attributes::Metadata;
     use tendon_api::paths::AbsoluteCrate;
 
     #[test]
@@ -208,21 +258,7 @@ mod tests {
         spoor::init();
 
         let fake_module = ModuleItem {
-            metadata: Metadata {
-                visibility: Visibility::Pub,
-                docs: None,
-                must_use: None,
-                deprecated: None,
-                extra_attributes: vec![],
-                span: Span {
-                    source_file: "fake_file.rs".into(),
-                    start_line: 0,
-                    start_column: 0,
-                    end_line: 0,
-                    end_column: 0,
-                    macro_invocation: None,
-                },
-            },
+            metadata: Metadata::fake("fake_module"),
             name: "fake_module".into(),
         };
 
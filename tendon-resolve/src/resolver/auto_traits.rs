@@ -0,0 +1,616 @@
+//! Auto traits: `Send`, `Sync` and `Unpin`.
+//!
+//! A type implements an auto trait if all of its fields do, unless there's an explicit impl
+//! saying otherwise (`unsafe impl<T: Send> Send for Thing<T>`, `impl !Send for Thing`). Raw
+//! pointers are neither `Send` nor `Sync`, `&T` is `Send` if `T: Sync`, and `PhantomData<T>`
+//! acts like a `T`. A few std types whose impls come from compiler internals, or which are
+//! commonly used without `core` and `alloc` having been lowered, are special-cased.
+//!
+//! Answers are conservative: a type we can't see into (an unresolved path, a foreign type, a
+//! crate that hasn't been lowered) doesn't implement anything.
+
+use super::normalize::{match_impl, ImplIndex};
+use crate::lower::generics::own_params;
+use lazy_static::lazy_static;
+use std::fmt;
+use tendon_api::{
+    builtins::{
+        is_impl_scope_name, ALLOC_CRATE, ARC, BOX, BUILTINS_CRATE, CORE_CRATE, PHANTOM_DATA,
+        PHANTOM_PINNED, RC, SEND, SIZED, SYNC, UNPIN,
+    },
+    database::Db,
+    generics::{Generics, WherePredicate},
+    identities::{
+        ArrayType, GenericParams, Identity, PathType, ReferenceType, SliceType, Substitutions,
+        TraitId, TupleType, TypeId,
+    },
+    items::{SymbolItem, TypeItem},
+    paths::Ident,
+};
+use tracing::error;
+
+/// Types nested deeper than this are assumed to be cyclic.
+const MAX_SOLVE_DEPTH: usize = 64;
+
+lazy_static! {
+    static ref SELF_TYPE: Ident = "Self".into();
+    static ref RC_WEAK: Identity = Identity::new(&*ALLOC_CRATE, &["rc", "Weak"]);
+    static ref ARC_WEAK: Identity = Identity::new(&*ALLOC_CRATE, &["sync", "Weak"]);
+    static ref VEC: Identity = Identity::new(&*ALLOC_CRATE, &["vec", "Vec"]);
+    static ref STRING: Identity = Identity::new(&*ALLOC_CRATE, &["string", "String"]);
+    static ref NON_NULL: Identity = Identity::new(&*CORE_CRATE, &["ptr", "non_null", "NonNull"]);
+    static ref CELL: Identity = Identity::new(&*CORE_CRATE, &["cell", "Cell"]);
+    static ref REF_CELL: Identity = Identity::new(&*CORE_CRATE, &["cell", "RefCell"]);
+    static ref UNSAFE_CELL: Identity = Identity::new(&*CORE_CRATE, &["cell", "UnsafeCell"]);
+}
+
+/// An auto trait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AutoTrait {
+    Send,
+    Sync,
+    Unpin,
+}
+impl AutoTrait {
+    /// The identity of the trait, in `core::marker`.
+    pub fn id(self) -> &'static Identity {
+        match self {
+            AutoTrait::Send => &*SEND,
+            AutoTrait::Sync => &*SYNC,
+            AutoTrait::Unpin => &*UNPIN,
+        }
+    }
+
+    /// The auto trait with identity `id`, if there is one.
+    pub fn from_id(id: &Identity) -> Option<AutoTrait> {
+        [AutoTrait::Send, AutoTrait::Sync, AutoTrait::Unpin]
+            .iter()
+            .copied()
+            .find(|trait_| trait_.id() == id)
+    }
+}
+
+/// A requirement that a type implement an auto trait, `T: Send`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AutoTraitBound {
+    pub type_: TypeId,
+    pub trait_: AutoTrait,
+}
+impl fmt::Debug for AutoTraitBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {:?}", self.type_, self.trait_)
+    }
+}
+
+/// Whether a type implements an auto trait.
+#[derive(Clone, Debug)]
+pub enum AutoTraitAnswer {
+    Yes,
+    /// No, or we can't tell.
+    No,
+    /// If all of these bounds hold. The bounded types are generic parameters, or projections
+    /// through them.
+    Conditional(Vec<AutoTraitBound>),
+}
+impl AutoTraitAnswer {
+    /// If the answer is an unconditional yes.
+    pub fn is_yes(&self) -> bool {
+        match self {
+            AutoTraitAnswer::Yes => true,
+            _ => false,
+        }
+    }
+
+    /// Both answers, `A && B`.
+    fn and(self, other: AutoTraitAnswer) -> AutoTraitAnswer {
+        match (self, other) {
+            (AutoTraitAnswer::No, _) | (_, AutoTraitAnswer::No) => AutoTraitAnswer::No,
+            (AutoTraitAnswer::Yes, other) | (other, AutoTraitAnswer::Yes) => other,
+            (AutoTraitAnswer::Conditional(mut a), AutoTraitAnswer::Conditional(b)) => {
+                for bound in b {
+                    if !a.contains(&bound) {
+                        a.push(bound);
+                    }
+                }
+                AutoTraitAnswer::Conditional(a)
+            }
+        }
+    }
+
+    fn bound(type_: &TypeId, trait_: AutoTrait) -> AutoTraitAnswer {
+        AutoTraitAnswer::Conditional(vec![AutoTraitBound {
+            type_: type_.clone(),
+            trait_,
+        }])
+    }
+}
+
+/// Decides whether types implement auto traits.
+pub struct AutoTraitSolver<'a> {
+    db: &'a Db,
+    index: &'a ImplIndex<'a>,
+}
+impl<'a> AutoTraitSolver<'a> {
+    pub fn new(db: &'a Db, index: &'a ImplIndex<'a>) -> AutoTraitSolver<'a> {
+        AutoTraitSolver { db, index }
+    }
+
+    /// Whether the type item `item`, with its own generic parameters, implements `trait_`.
+    /// A conditional answer is in terms of those parameters, `Thing::T: Send`.
+    pub fn solve_item(&self, trait_: AutoTrait, item: &Identity) -> AutoTraitAnswer {
+        let params = match self.db.get_item::<TypeItem>(item).and_then(generics_of) {
+            Some(generics) => own_params(generics, |name| item.clone_join(name.clone())),
+            None => GenericParams::empty(),
+        };
+        self.solve(
+            trait_,
+            &TypeId::Path(PathType {
+                path: item.clone(),
+                params,
+            }),
+        )
+    }
+
+    /// Whether a type implements `trait_`.
+    pub fn solve(&self, trait_: AutoTrait, type_: &TypeId) -> AutoTraitAnswer {
+        self.solve_(trait_, type_, &mut vec![])
+    }
+
+    /// `stack` holds the types currently being solved for, to break cycles.
+    fn solve_(
+        &self,
+        trait_: AutoTrait,
+        type_: &TypeId,
+        stack: &mut Vec<String>,
+    ) -> AutoTraitAnswer {
+        if stack.len() > MAX_SOLVE_DEPTH {
+            error!("auto trait solving too deep, cycle? {:?}", type_);
+            return AutoTraitAnswer::No;
+        }
+        let type_ = self.db.expand_aliases(type_);
+        let key = format!("{:?}: {:?}", type_, trait_);
+        if stack.contains(&key) {
+            // a recursive type implements an auto trait if nothing else prevents it
+            return AutoTraitAnswer::Yes;
+        }
+        stack.push(key);
+        let result = self.solve_type(trait_, &type_, stack);
+        stack.pop();
+        result
+    }
+
+    fn solve_all<'t>(
+        &self,
+        trait_: AutoTrait,
+        types: impl IntoIterator<Item = &'t TypeId>,
+        stack: &mut Vec<String>,
+    ) -> AutoTraitAnswer {
+        let mut result = AutoTraitAnswer::Yes;
+        for type_ in types {
+            result = result.and(self.solve_(trait_, type_, stack));
+            if let AutoTraitAnswer::No = result {
+                break;
+            }
+        }
+        result
+    }
+
+    fn solve_type(
+        &self,
+        trait_: AutoTrait,
+        type_: &TypeId,
+        stack: &mut Vec<String>,
+    ) -> AutoTraitAnswer {
+        match type_ {
+            TypeId::Path(path) => self.solve_path(trait_, type_, path, stack),
+            TypeId::Reference(ReferenceType { type_, mut_, .. }) => match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::Yes,
+                AutoTrait::Send if *mut_ => self.solve_(AutoTrait::Send, type_, stack),
+                AutoTrait::Send | AutoTrait::Sync => self.solve_(AutoTrait::Sync, type_, stack),
+            },
+            TypeId::Pointer(_) => match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::Yes,
+                AutoTrait::Send | AutoTrait::Sync => AutoTraitAnswer::No,
+            },
+            TypeId::Array(ArrayType { type_, .. }) | TypeId::Slice(SliceType { type_ }) => {
+                self.solve_(trait_, type_, stack)
+            }
+            TypeId::Tuple(TupleType { types }) => self.solve_all(trait_, types, stack),
+            TypeId::Never(_) | TypeId::BareFn(_) => AutoTraitAnswer::Yes,
+            TypeId::QSelf(_) => AutoTraitAnswer::bound(type_, trait_),
+            // auto traits leak through `impl Trait`, but we can't see the hidden type
+            TypeId::ImplTrait(impl_trait) => has_bound(&impl_trait.trait_bounds, trait_),
+            TypeId::TraitObject(object) => has_bound(&object.trait_bounds, trait_),
+        }
+    }
+
+    fn solve_path(
+        &self,
+        trait_: AutoTrait,
+        type_: &TypeId,
+        path: &PathType,
+        stack: &mut Vec<String>,
+    ) -> AutoTraitAnswer {
+        if path.path.crate_ == *BUILTINS_CRATE {
+            return AutoTraitAnswer::Yes;
+        }
+        if let Some(answer) = self.solve_explicit(trait_, type_, stack) {
+            return answer;
+        }
+        if let Some(answer) = self.solve_known(trait_, path, stack) {
+            return answer;
+        }
+
+        let item = match self.db.get_item::<TypeItem>(&path.path) {
+            Some(item) => item,
+            None if self.is_param(&path.path) => return AutoTraitAnswer::bound(type_, trait_),
+            None => return AutoTraitAnswer::No,
+        };
+        let generics = match generics_of(item) {
+            Some(generics) => generics,
+            None => return AutoTraitAnswer::No,
+        };
+        let substitutions = Substitutions::for_generics(&path.path, generics, &path.params);
+        let fields: Vec<TypeId> = match item {
+            TypeItem::Struct(struct_) => struct_
+                .fields
+                .iter()
+                .map(|field| field.type_.substitute(&substitutions))
+                .collect(),
+            TypeItem::Enum(enum_) => enum_
+                .variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter())
+                .map(|field| field.type_.substitute(&substitutions))
+                .collect(),
+            TypeItem::Union(union_) => union_
+                .fields
+                .iter()
+                .map(|field| field.type_.substitute(&substitutions))
+                .collect(),
+            _ => return AutoTraitAnswer::No,
+        };
+        self.solve_all(trait_, &fields, stack)
+    }
+
+    /// Check for an explicit impl of the trait, positive or negative. Blanket impls are ignored.
+    fn solve_explicit(
+        &self,
+        trait_: AutoTrait,
+        type_: &TypeId,
+        stack: &mut Vec<String>,
+    ) -> Option<AutoTraitAnswer> {
+        let trait_id = TraitId {
+            id: trait_.id().clone(),
+            params: GenericParams::empty(),
+            is_maybe: false,
+            for_lifetimes: vec![],
+        };
+        for impl_ in self.index.impls_of(trait_.id()) {
+            let substitutions = match match_impl(impl_, type_, &trait_id) {
+                Some(substitutions) => substitutions,
+                None => continue,
+            };
+            if impl_.is_negative {
+                return Some(AutoTraitAnswer::No);
+            }
+
+            // the impl holds if all of its bounds do. Only auto trait bounds can be expressed in
+            // an answer, so any other bound, including an outlives bound, makes it a no.
+            let generics = &impl_.generics;
+            if generics
+                .lifetimes
+                .iter()
+                .any(|param| !param.bounds.is_empty())
+            {
+                return Some(AutoTraitAnswer::No);
+            }
+            let owner = impl_.scope.clone_join(impl_.metadata.name.clone());
+            let params: Vec<TypeId> = generics
+                .types
+                .iter()
+                .map(|param| {
+                    TypeId::Path(PathType {
+                        path: owner.clone_join(param.metadata.name.clone()),
+                        params: GenericParams::empty(),
+                    })
+                })
+                .collect();
+            let mut bounds = vec![];
+            for (param, type_) in generics.types.iter().zip(&params) {
+                if !param.lifetime_bounds.is_empty() {
+                    return Some(AutoTraitAnswer::No);
+                }
+                bounds.extend(param.trait_bounds.iter().map(|bound| (type_, bound)));
+            }
+            for predicate in &generics.where_predicates {
+                match predicate {
+                    WherePredicate::Type(predicate) if predicate.lifetime_bounds.is_empty() => {
+                        bounds.extend(
+                            predicate
+                                .trait_bounds
+                                .iter()
+                                .map(|bound| (&predicate.type_, bound)),
+                        );
+                    }
+                    _ => return Some(AutoTraitAnswer::No),
+                }
+            }
+
+            let mut result = AutoTraitAnswer::Yes;
+            for (type_, bound) in bounds {
+                // parameters are `Sized` unless they say otherwise
+                if bound.is_maybe || (bound.id == *SIZED && params.contains(type_)) {
+                    continue;
+                }
+                let bound_trait = match AutoTrait::from_id(&bound.id) {
+                    Some(bound_trait) => bound_trait,
+                    None => return Some(AutoTraitAnswer::No),
+                };
+                result =
+                    result.and(self.solve_(bound_trait, &type_.substitute(&substitutions), stack));
+                if let AutoTraitAnswer::No = result {
+                    break;
+                }
+            }
+            return Some(result);
+        }
+        None
+    }
+
+    /// Special cases for std types.
+    fn solve_known(
+        &self,
+        trait_: AutoTrait,
+        path: &PathType,
+        stack: &mut Vec<String>,
+    ) -> Option<AutoTraitAnswer> {
+        let id = &path.path;
+        let arg = path.params.type_bindings.get(&Ident::positional(0));
+        let mut on_arg = |traits: &[AutoTrait]| match arg {
+            Some(arg) => traits.iter().fold(AutoTraitAnswer::Yes, |result, trait_| {
+                result.and(self.solve_(*trait_, arg, stack))
+            }),
+            None => AutoTraitAnswer::No,
+        };
+
+        Some(if id == &*PHANTOM_DATA || id == &*VEC {
+            on_arg(&[trait_])
+        } else if id == &*PHANTOM_PINNED {
+            match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::No,
+                _ => AutoTraitAnswer::Yes,
+            }
+        } else if id == &*RC || id == &*RC_WEAK || id == &*NON_NULL {
+            match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::Yes,
+                _ => AutoTraitAnswer::No,
+            }
+        } else if id == &*ARC || id == &*ARC_WEAK {
+            match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::Yes,
+                _ => on_arg(&[AutoTrait::Send, AutoTrait::Sync]),
+            }
+        } else if id == &*BOX {
+            match trait_ {
+                AutoTrait::Unpin => AutoTraitAnswer::Yes,
+                _ => on_arg(&[trait_]),
+            }
+        } else if id == &*CELL || id == &*REF_CELL || id == &*UNSAFE_CELL {
+            match trait_ {
+                AutoTrait::Sync => AutoTraitAnswer::No,
+                _ => on_arg(&[trait_]),
+            }
+        } else if id == &*STRING {
+            AutoTraitAnswer::Yes
+        } else {
+            return None;
+        })
+    }
+
+    /// If a path names a generic parameter: `Item::T`, `{impl_0}::T`, `Trait::Self`.
+    fn is_param(&self, path: &Identity) -> bool {
+        let (name, parent) = match (path.path.last(), path.parent()) {
+            (Some(name), Some(parent)) => (name, parent),
+            _ => return false,
+        };
+        if name == &*SELF_TYPE || parent.path.last().map(is_impl_scope_name) == Some(true) {
+            return true;
+        }
+        if let Some(generics) = self.db.get_item::<TypeItem>(&parent).and_then(generics_of) {
+            return generics.type_param(name).is_some();
+        }
+        match self.db.get_item::<SymbolItem>(&parent) {
+            Some(SymbolItem::Function(function)) => {
+                function.signature.generics.type_param(name).is_some()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `Yes` if a list of bounds includes `trait_`.
+fn has_bound(bounds: &[TraitId], trait_: AutoTrait) -> AutoTraitAnswer {
+    if bounds
+        .iter()
+        .any(|bound| !bound.is_maybe && &bound.id == trait_.id())
+    {
+        AutoTraitAnswer::Yes
+    } else {
+        AutoTraitAnswer::No
+    }
+}
+
+/// The generics of a type item, if it has any.
+pub(super) fn generics_of(item: &TypeItem) -> Option<&Generics> {
+    match item {
+        TypeItem::Struct(struct_) => Some(&struct_.generics),
+        TypeItem::Enum(enum_) => Some(&enum_.generics),
+        TypeItem::Union(union_) => Some(&union_.generics),
+        TypeItem::Trait(trait_) => Some(&trait_.generics),
+        TypeItem::Alias(alias) => Some(&alias.generics),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items_in;
+    use tendon_api::crates::CrateData;
+    use tendon_api::Map;
+
+    #[test]
+    fn auto_traits() {
+        spoor::init();
+        // lower a fake `core`, so `marker::Send` is the real thing
+        let core = (*CORE_CRATE).clone();
+        let mut crates = Map::default();
+        crates.insert(core.clone(), CrateData::fake(core.clone()));
+        let db = Db::new(crates);
+
+        let items: syn::File = syn::parse_quote! {
+            pub mod marker {
+                pub trait Send {}
+                pub trait Sync {}
+                pub trait Unpin {}
+                pub struct PhantomData<T>;
+                pub struct PhantomPinned;
+            }
+            pub struct Plain {
+                a: Holder<Plain>,
+                b: (fn(*const u8), [Holder<Plain>; 2]),
+            }
+            pub struct Ptr(*const Plain);
+            pub struct Holder<T> {
+                x: T,
+            }
+            pub struct Shared<'a, T>(&'a T);
+            pub struct Covariant<T>(marker::PhantomData<*const T>);
+            pub struct Raw<T>(*mut T);
+            unsafe impl<T: marker::Send> marker::Send for Raw<T> {}
+            unsafe impl<T: marker::Sync> marker::Sync for Raw<T> where T: marker::Send {}
+            pub trait Other {}
+            pub struct Picky<T>(T);
+            unsafe impl<T: marker::Send + Other> marker::Send for Picky<T> {}
+            unsafe impl<T: ?Sized> marker::Sync for Picky<T> where T: marker::Sync + Other {}
+            unsafe impl<T: 'static + marker::Send> marker::Unpin for Picky<T> {}
+            pub struct NotSend;
+            impl !marker::Send for NotSend {}
+            pub struct Pinned(marker::PhantomPinned);
+            pub enum Chain {
+                End,
+                Link(&'static Chain),
+            }
+            pub struct Unknown(Missing);
+        };
+        let root = walk_test_items_in(&db, &core, &items);
+
+        let index = ImplIndex::new(&db);
+        let solver = AutoTraitSolver::new(&db, &index);
+        let item = |name: &str| root.clone_join(name);
+        let bounds = |answer: AutoTraitAnswer| match answer {
+            AutoTraitAnswer::Conditional(bounds) => bounds
+                .iter()
+                .map(|bound| (format!("{:?}", bound.type_), bound.trait_))
+                .collect::<Vec<_>>(),
+            other => panic!("not conditional: {:?}", other),
+        };
+        let param = |owner: &str, name: &str| format!("{:?}", item(owner).clone_join(name));
+
+        assert!(solver.solve_item(AutoTrait::Send, &item("Plain")).is_yes());
+        assert!(solver.solve_item(AutoTrait::Sync, &item("Plain")).is_yes());
+
+        // raw pointers
+        assert_match!(
+            solver.solve_item(AutoTrait::Send, &item("Ptr")),
+            AutoTraitAnswer::No
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Sync, &item("Ptr")),
+            AutoTraitAnswer::No
+        );
+        assert!(solver.solve_item(AutoTrait::Unpin, &item("Ptr")).is_yes());
+
+        // generics
+        assert_eq!(
+            bounds(solver.solve_item(AutoTrait::Send, &item("Holder"))),
+            vec![(param("Holder", "T"), AutoTrait::Send)]
+        );
+        let holder = |arg: &str| {
+            let mut params = GenericParams::empty();
+            params.type_bindings.insert(
+                Ident::positional(0),
+                TypeId::Path(PathType {
+                    path: item(arg),
+                    params: GenericParams::empty(),
+                }),
+            );
+            TypeId::Path(PathType {
+                path: item("Holder"),
+                params,
+            })
+        };
+        assert!(solver.solve(AutoTrait::Send, &holder("Plain")).is_yes());
+        assert_match!(
+            solver.solve(AutoTrait::Send, &holder("Ptr")),
+            AutoTraitAnswer::No
+        );
+        assert_eq!(
+            bounds(solver.solve_item(AutoTrait::Send, &item("Shared"))),
+            vec![(param("Shared", "T"), AutoTrait::Sync)]
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Send, &item("Covariant")),
+            AutoTraitAnswer::No
+        );
+
+        // explicit impls
+        assert_eq!(
+            bounds(solver.solve_item(AutoTrait::Send, &item("Raw"))),
+            vec![(param("Raw", "T"), AutoTrait::Send)]
+        );
+        assert_eq!(
+            bounds(solver.solve_item(AutoTrait::Sync, &item("Raw"))),
+            vec![
+                (param("Raw", "T"), AutoTrait::Sync),
+                (param("Raw", "T"), AutoTrait::Send)
+            ]
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Send, &item("Picky")),
+            AutoTraitAnswer::No
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Sync, &item("Picky")),
+            AutoTraitAnswer::No
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Unpin, &item("Picky")),
+            AutoTraitAnswer::No
+        );
+        assert_match!(
+            solver.solve_item(AutoTrait::Send, &item("NotSend")),
+            AutoTraitAnswer::No
+        );
+        assert!(solver
+            .solve_item(AutoTrait::Sync, &item("NotSend"))
+            .is_yes());
+
+        // marker types
+        assert_match!(
+            solver.solve_item(AutoTrait::Unpin, &item("Pinned")),
+            AutoTraitAnswer::No
+        );
+        assert!(solver.solve_item(AutoTrait::Send, &item("Pinned")).is_yes());
+
+        // recursion
+        assert!(solver.solve_item(AutoTrait::Send, &item("Chain")).is_yes());
+
+        // conservative
+        assert_match!(
+            solver.solve_item(AutoTrait::Send, &item("Unknown")),
+            AutoTraitAnswer::No
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Const evaluation.
+//!
+//! Consts are lowered as they're walked, but a const can refer to one declared further down, or
+//! in a module walked later, so only self-contained expressions are evaluated then. Once the
+//! whole crate is walked, this pass evaluates everything that's left: consts, impl consts and
+//! enum discriminants. Referenced consts are evaluated on demand; a const that refers back to
+//! itself, directly or not, evaluates to `None`.
+
+use crate::lower::items::{evaluate_const_item, evaluate_discriminants};
+use crate::walker::helpers::try_to_resolve;
+use tendon_api::{
+    database::{Crate, Db},
+    expressions::ConstValue,
+    identities::Identity,
+    items::{SymbolItem, TypeItem},
+    paths::UnresolvedPath,
+    scopes::NamespaceId,
+    Map, Set,
+};
+use tracing::warn;
+
+/// Evaluate the consts and enum discriminants in a crate that couldn't be evaluated on their own.
+pub(crate) fn evaluate_consts(db: &Db, crate_: &mut Crate) {
+    let mut evaluator = ConstEvaluator {
+        db,
+        crate_: &*crate_,
+        values: Map::default(),
+        in_progress: Set::default(),
+    };
+
+    let consts: Vec<Identity> = crate_
+        .symbols
+        .0
+        .iter()
+        .filter_map(|(path, item)| match item {
+            SymbolItem::Const(const_) if const_.evaluated.is_none() => {
+                Some(Identity::new(&crate_.id, path))
+            }
+            _ => None,
+        })
+        .collect();
+    for const_ in &consts {
+        evaluator.value(const_);
+    }
+
+    let mut enums = vec![];
+    for (path, item) in &crate_.types.0 {
+        if let TypeItem::Enum(enum_) = item {
+            let unevaluated = enum_
+                .variants
+                .iter()
+                .any(|variant| variant.evaluated_discriminant.is_none());
+            if unevaluated {
+                let id = Identity::new(&crate_.id, path);
+                let module = id.parent().expect("enums are always in a module");
+                let discriminants =
+                    evaluate_discriminants(enum_, &mut |path| evaluator.lookup(&module, path));
+                enums.push((id, discriminants));
+            }
+        }
+    }
+
+    let mut impls = vec![];
+    for (i, impl_) in crate_.impls.iter().enumerate() {
+        for (j, const_) in impl_.consts.iter().enumerate() {
+            if const_.evaluated.is_none() {
+                let value =
+                    evaluate_const_item(const_, &mut |path| evaluator.lookup(&impl_.scope, path));
+                impls.push((i, j, value));
+            }
+        }
+    }
+
+    let values = evaluator.values;
+    for (id, value) in values {
+        if let Some(SymbolItem::Const(const_)) = crate_.get_mut::<SymbolItem>(&id) {
+            const_.evaluated = value;
+        }
+    }
+    for (id, discriminants) in enums {
+        if let Some(TypeItem::Enum(enum_)) = crate_.get_mut::<TypeItem>(&id) {
+            for (variant, discriminant) in enum_.variants.iter_mut().zip(discriminants) {
+                variant.evaluated_discriminant = discriminant;
+            }
+        }
+    }
+    for (i, j, value) in impls {
+        crate_.impls[i].consts[j].evaluated = value;
+    }
+}
+
+/// Evaluates consts on demand, remembering the results.
+struct ConstEvaluator<'a> {
+    db: &'a Db,
+    crate_: &'a Crate,
+    /// The consts evaluated so far.
+    values: Map<Identity, Option<ConstValue>>,
+    /// The consts currently being evaluated, for finding cycles.
+    in_progress: Set<Identity>,
+}
+
+impl<'a> ConstEvaluator<'a> {
+    /// Look up the value of the const `path` refers to from `in_module`.
+    fn lookup(&mut self, in_module: &Identity, path: &UnresolvedPath) -> Option<ConstValue> {
+        let id = try_to_resolve(self.db, self.crate_, in_module, NamespaceId::Symbol, path).ok()?;
+        self.value(&id)
+    }
+
+    /// The value of a const, evaluating it if it hasn't been yet.
+    fn value(&mut self, id: &Identity) -> Option<ConstValue> {
+        if id.crate_ != self.crate_.id {
+            return match self.db.get_item::<SymbolItem>(id) {
+                Some(SymbolItem::Const(const_)) => const_.evaluated.clone(),
+                _ => None,
+            };
+        }
+        if let Some(value) = self.values.get(id) {
+            return value.clone();
+        }
+        let const_ = match self.crate_.get::<SymbolItem>(id) {
+            Some(SymbolItem::Const(const_)) => const_,
+            _ => return None,
+        };
+        if const_.evaluated.is_some() {
+            return const_.evaluated.clone();
+        }
+        if !self.in_progress.insert(id.clone()) {
+            warn!("cycle evaluating const {:?}", id);
+            return None;
+        }
+
+        let module = id.parent().expect("consts are always in a module");
+        let value = evaluate_const_item(const_, &mut |path| self.lookup(&module, path));
+
+        self.in_progress.remove(id);
+        self.values.insert(id.clone(), value.clone());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items;
+    use tendon_api::paths::Ident;
+
+    #[test]
+    fn later_consts() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub const A: u8 = B + inner::C;
+            pub const B: u8 = 1;
+
+            pub enum E {
+                X = D,
+                Y,
+            }
+            pub const D: isize = 5;
+
+            pub struct S;
+            impl S {
+                pub const F: u32 = A as u32 * 2;
+            }
+
+            pub const G: u8 = H;
+            pub const H: u8 = G;
+
+            pub mod inner {
+                pub const C: u8 = super::B + 1;
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let int = |value, type_| Some(ConstValue::Int(value, Ident::from(type_)));
+        let value = |name: &str| match db.get_item::<SymbolItem>(&root.clone_join(name)) {
+            Some(SymbolItem::Const(const_)) => const_.evaluated.clone(),
+            _ => panic!("no const {}", name),
+        };
+        assert_eq!(value("A"), int(3, "u8"));
+        assert_eq!(value("G"), None);
+        assert_eq!(value("H"), None);
+
+        match db.get_item::<TypeItem>(&root.clone_join("E")) {
+            Some(TypeItem::Enum(enum_)) => {
+                let values: Vec<_> = enum_
+                    .variants
+                    .iter()
+                    .map(|variant| variant.evaluated_discriminant.clone())
+                    .collect();
+                assert_eq!(values, vec![int(5, "isize"), int(6, "isize")]);
+            }
+            _ => panic!("no enum E"),
+        }
+
+        let crate_ = db.get_crate(&root.crate_);
+        assert_eq!(crate_.impls[0].consts[0].evaluated, int(6, "u32"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Const generic arguments.
+//!
+//! `Thing<N>` is ambiguous at parse time: `N` could be a type or a const. Lowering stores bare
+//! paths in generic arguments as types; this pass moves the ones that name a const (or a const
+//! parameter) over to `GenericParams::consts`. It also resolves the paths in array lengths and
+//! other `ConstArg`s.
+
+use super::fold::{fold_params_children, Folder, GenericsScope};
+use crate::walker::helpers::try_to_resolve;
+use tendon_api::{
+    database::{Crate, Db},
+    expressions::ConstArg,
+    generics::Generics,
+    identities::{GenericParams, Identity, PathType, TypeId},
+    paths::UnresolvedPath,
+    scopes::NamespaceId,
+};
+
+/// Disambiguates and resolves const arguments.
+pub(crate) struct ConstArgResolver<'a> {
+    db: &'a Db,
+    crate_: &'a Crate,
+    scope: Identity,
+    generics: Vec<GenericsScope>,
+}
+
+impl<'a> ConstArgResolver<'a> {
+    pub(crate) fn new(db: &'a Db, crate_: &'a Crate) -> Self {
+        ConstArgResolver {
+            db,
+            crate_,
+            scope: crate_.id.root(),
+            generics: vec![],
+        }
+    }
+
+    /// If `path` names a const or const parameter, return its identity.
+    fn resolve_const(&self, path: &UnresolvedPath) -> Option<Identity> {
+        if !path.rooted && path.path.len() == 1 {
+            let name = &path.path[0];
+            // innermost generics shadow outer ones, and everything shadows module-level items.
+            for scope in self.generics.iter().rev() {
+                if scope.types.contains(name) {
+                    return None;
+                }
+                if scope.consts.contains(name) {
+                    return Some(scope.owner.clone_join(name.clone()));
+                }
+            }
+        }
+        let resolve =
+            |namespace| try_to_resolve(self.db, self.crate_, &self.scope, namespace, path);
+        if resolve(NamespaceId::Type).is_ok() {
+            return None;
+        }
+        resolve(NamespaceId::Symbol).ok()
+    }
+}
+
+impl<'a> Folder for ConstArgResolver<'a> {
+    fn enter_item(&mut self, scope: &Identity, _item: &Identity) {
+        self.scope = scope.clone();
+        self.generics.clear();
+    }
+
+    fn enter_generics(&mut self, owner: &Identity, generics: &Generics) {
+        self.generics.push(GenericsScope::new(owner, generics));
+    }
+
+    fn exit_generics(&mut self) {
+        self.generics.pop();
+    }
+
+    fn fold_params(&mut self, params: &GenericParams) -> GenericParams {
+        let mut params = fold_params_children(self, params);
+
+        let consts: Vec<_> = params
+            .type_bindings
+            .iter()
+            .filter_map(|(name, type_)| match type_ {
+                TypeId::Path(PathType { path, params }) if params.is_empty() => {
+                    let path = path.as_unresolved()?;
+                    self.resolve_const(&path).map(|id| (name.clone(), id))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for (name, id) in consts {
+            params.type_bindings.remove(&name);
+            params.consts.insert(name, ConstArg::Path(id));
+        }
+        params
+    }
+
+    fn fold_const(&mut self, const_: &ConstArg) -> ConstArg {
+        if let ConstArg::Path(path) = const_ {
+            if let Some(path) = path.as_unresolved() {
+                if let Some(id) = self.resolve_const(&path) {
+                    return ConstArg::Path(id);
+                }
+            }
+        }
+        const_.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walker::walk_test_items;
+    use tendon_api::database::Db;
+    use tendon_api::expressions::ConstArg;
+    use tendon_api::identities::{ArrayType, PathType, TypeId};
+    use tendon_api::items::TypeItem;
+    use tendon_api::paths::Ident;
+
+    #[test]
+    fn const_args() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub const N: usize = 3;
+            pub struct Buf<const M: usize> {
+                data: [u8; M],
+                other: Buf<M>,
+            }
+            pub struct Holder<T> {
+                a: Buf<N>,
+                b: Buf<{ N }>,
+                c: Vec<Holder<T>>,
+                d: [u8; N],
+                e: Vec<T>,
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let n = root.clone_join("N");
+        let buf_m = root.clone_join("Buf").clone_join("M");
+        let field_type = |item: &str, field: &str| -> TypeId {
+            match db.get_item::<TypeItem>(&root.clone_join(item)) {
+                Some(TypeItem::Struct(struct_)) => struct_
+                    .fields
+                    .iter()
+                    .find(|f| &f.metadata.name[..] == field)
+                    .unwrap()
+                    .type_
+                    .clone(),
+                _ => panic!("no struct {}", item),
+            }
+        };
+        let params = |type_: TypeId| match type_ {
+            TypeId::Path(PathType { params, .. }) => params,
+            _ => panic!("not a path: {:?}", type_),
+        };
+
+        match field_type("Buf", "data") {
+            TypeId::Array(ArrayType { len, .. }) => assert_eq!(len, ConstArg::Path(buf_m.clone())),
+            other => panic!("not an array: {:?}", other),
+        }
+        let other = params(field_type("Buf", "other"));
+        assert!(other.type_bindings.is_empty());
+        assert_eq!(other.consts[&Ident::positional(0)], ConstArg::Path(buf_m));
+
+        for field in &["a", "b"] {
+            let params = params(field_type("Holder", field));
+            assert!(params.type_bindings.is_empty());
+            assert_eq!(
+                params.consts[&Ident::positional(0)],
+                ConstArg::Path(n.clone())
+            );
+        }
+        match field_type("Holder", "d") {
+            TypeId::Array(ArrayType { len, .. }) => assert_eq!(len, ConstArg::Path(n.clone())),
+            other => panic!("not an array: {:?}", other),
+        }
+        for field in &["c", "e"] {
+            let params = params(field_type("Holder", field));
+            assert!(params.consts.is_empty());
+            assert_eq!(params.type_bindings.len(), 1);
+        }
+        match &params(field_type("Holder", "e")).type_bindings[&Ident::positional(0)] {
+            TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &root.clone_join("Holder").clone_join("T"))
+            }
+            other => panic!("not a path: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Intra-doc links: `[Vec]`, `[`crate::x::Y`]`, `[Foo::bar()]`.
+//!
+//! Links are looked up like any other path, from the module their item is declared in; inside a
+//! type, trait or impl, `Self` means that type. A path that doesn't name an item may name a member
+//! of one, `[Vec::push]`, so we fall back to looking up all but the last segment as a type, and
+//! link to the type's identity joined with the member's name.
+//!
+//! Links that can't be resolved are left with no target.
+
+use crate::walker::helpers::try_to_resolve;
+use lazy_static::lazy_static;
+use tendon_api::{
+    attributes::{DocLink, Metadata},
+    database::{Crate, Db},
+    identities::Identity,
+    paths::{Ident, UnresolvedPath},
+    scopes::NamespaceId,
+};
+
+lazy_static! {
+    static ref SELF_TYPE: Ident = "Self".into();
+}
+
+/// The namespaces to try, in order, for a link with no disambiguator.
+const ANY_NAMESPACE: [NamespaceId; 4] = [
+    NamespaceId::Type,
+    NamespaceId::Symbol,
+    NamespaceId::Scope,
+    NamespaceId::Macro,
+];
+
+/// Resolve the links in some docs, declared in the module `scope`.
+/// `self_type` is what `Self` means there, if anything.
+pub(crate) fn resolve_doc_links(
+    db: &Db,
+    crate_: &Crate,
+    scope: &Identity,
+    self_type: Option<&Identity>,
+    metadata: &mut Metadata,
+) {
+    for link in &mut metadata.doc_links {
+        link.target = resolve_doc_link(db, crate_, scope, self_type, link);
+    }
+}
+
+/// Resolve the links in the docs of every scope in a crate.
+/// Module docs are resolved from inside the module, other scopes from their parents.
+pub(crate) fn resolve_scope_doc_links(db: &Db, crate_: &mut Crate) {
+    let resolved = crate_
+        .scopes
+        .0
+        .iter()
+        .filter(|(_, scope)| !scope.metadata.doc_links.is_empty())
+        .map(|(path, scope)| {
+            let id = Identity::new(&crate_.id, path);
+            let from = match id.parent() {
+                Some(parent) if !scope.is_module => parent,
+                _ => id,
+            };
+            let targets = scope
+                .metadata
+                .doc_links
+                .iter()
+                .map(|link| resolve_doc_link(db, crate_, &from, None, link))
+                .collect::<Vec<_>>();
+            (path.clone(), targets)
+        })
+        .collect::<Vec<_>>();
+
+    for (path, targets) in resolved {
+        let scope = crate_.scopes.0.get_mut(&path).expect("scope disappeared");
+        for (link, target) in scope.metadata.doc_links.iter_mut().zip(targets) {
+            link.target = target;
+        }
+    }
+}
+
+fn resolve_doc_link(
+    db: &Db,
+    crate_: &Crate,
+    scope: &Identity,
+    self_type: Option<&Identity>,
+    link: &DocLink,
+) -> Option<Identity> {
+    let path = &link.path;
+    if !path.rooted && path.path[0] == *SELF_TYPE {
+        let self_type = self_type?;
+        return Some(
+            path.path[1..]
+                .iter()
+                .fold(self_type.clone(), |id, name| id.clone_join(name.clone())),
+        );
+    }
+
+    let namespaces = match &link.namespace {
+        Some(namespace) => std::slice::from_ref(namespace),
+        None => &ANY_NAMESPACE[..],
+    };
+    for &namespace in namespaces {
+        if let Ok(target) = try_to_resolve(db, crate_, scope, namespace, path) {
+            return Some(target);
+        }
+    }
+
+    // a member, `[Vec::push]`
+    let (member, parent) = path.path.split_last()?;
+    if parent.is_empty() {
+        return None;
+    }
+    let parent = UnresolvedPath::new(path.rooted, parent.iter().cloned());
+    try_to_resolve(db, crate_, scope, NamespaceId::Type, &parent)
+        .ok()
+        .map(|parent| parent.clone_join(member.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walker::walk_test_items;
+    use tendon_api::attributes::DocLink;
+    use tendon_api::builtins::{CORE_CRATE, U8};
+    use tendon_api::database::Db;
+    use tendon_api::identities::Identity;
+    use tendon_api::items::{SymbolItem, TypeItem};
+    use tendon_api::scopes::Scope;
+
+    #[test]
+    fn doc_link_resolution() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            /// Makes a [`Thing`], see [`crate::inner::Y`] and [Thing::new()].
+            /// Not a [Clone], [`fn@Thing`] or [`Missing`].
+            pub fn make() {}
+            /// A thing.
+            pub struct Thing {
+                /// The [size](Self::size), in [`u8`]s.
+                pub size: u8,
+            }
+            impl Thing {
+                /// Makes a [Self], like [struct@Thing].
+                pub fn new() -> Thing {}
+            }
+            pub mod inner {
+                //! Helpers for [`super::Thing`], see [`Y`] and [`make`](super::make).
+                pub struct Y;
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let crate_ = db.get_crate(&root.crate_);
+        let targets = |links: &[DocLink]| {
+            links
+                .iter()
+                .map(|link| link.target.clone())
+                .collect::<Vec<_>>()
+        };
+        let thing = root.clone_join("Thing");
+        let inner = root.clone_join("inner");
+        let y = inner.clone_join("Y");
+
+        let make = match crate_.get::<SymbolItem>(&root.clone_join("make")) {
+            Some(SymbolItem::Function(make)) => make,
+            _ => panic!("wrong item"),
+        };
+        assert_eq!(
+            targets(&make.metadata.doc_links),
+            vec![
+                Some(thing.clone()),
+                Some(y.clone()),
+                Some(thing.clone_join("new")),
+                Some(Identity::new(&*CORE_CRATE, &["clone", "Clone"])),
+                None,
+                None,
+            ]
+        );
+
+        let thing_ = match crate_.get::<TypeItem>(&thing) {
+            Some(TypeItem::Struct(thing_)) => thing_,
+            _ => panic!("wrong item"),
+        };
+        assert_eq!(
+            targets(&thing_.fields[0].metadata.doc_links),
+            vec![Some(thing.clone_join("size")), Some(U8.clone()),]
+        );
+
+        let impl_ = &crate_.impls[0];
+        assert_eq!(
+            targets(&impl_.methods[0].metadata.doc_links),
+            vec![Some(thing.clone()), Some(thing.clone())]
+        );
+
+        let inner_ = crate_.get::<Scope>(&inner).unwrap();
+        assert_eq!(
+            targets(&inner_.metadata.doc_links),
+            vec![Some(thing.clone()), Some(y), Some(root.clone_join("make"))]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Lifetime elision.
+//!
+//! Fills in the lifetimes left out of function signatures, following
+//! https://doc.rust-lang.org/reference/lifetime-elision.html:
+//!
+//! - Each elided lifetime in the inputs (`&T`, `Thing<'_>`) becomes a fresh lifetime parameter,
+//!   named `Ident::elided_lifetime(i)` and added to the signature's generics.
+//! - If the receiver is `&self` or `&mut self` (or `self: &Self`), its lifetime is given to
+//!   every elided lifetime in the return type.
+//! - Otherwise, if there's exactly one lifetime in the inputs, elided or not, it's given to every
+//!   elided lifetime in the return type. `&'a T, &'a U` count as one lifetime.
+//!
+//! Anything else is an error in rust, so the return type is left alone.
+//!
+//! Lifetimes left out of paths entirely (`Ref<T>` for `struct Ref<'a, T>`) are elided like
+//! `Ref<'_, T>`. So are lifetimes in the arguments and lifetime bounds of `impl Trait` and
+//! `dyn Trait`, `impl Iterator<Item = &u8> + '_`; the lifetime bounds of `dyn Trait` aren't kept
+//! by lowering, though. Function pointers and `Fn(&T) -> &U` bounds have their own elision scopes,
+//! so lifetimes inside them aren't touched.
+
+use super::auto_traits::generics_of;
+use super::fold::Items;
+use tendon_api::{
+    attributes::{Metadata, Visibility},
+    database::Db,
+    identities::{
+        ArrayType, GenericParams, Identity, ImplTraitType, LifetimeId, PathType, PointerType,
+        QSelfType, ReferenceType, SliceType, TraitId, TraitObjectType, TupleType, TypeId,
+    },
+    items::{LifetimeParamItem, Receiver, Signature, TypeItem},
+    paths::{Ident, UnresolvedPath},
+    Map,
+};
+
+/// The number of lifetime parameters types declare, for filling in lifetimes left out of paths.
+pub(crate) struct DeclaredLifetimes<'a> {
+    db: &'a Db,
+    /// The types of the crate being resolved, which are out of the crate while it's resolved.
+    local: Map<Identity, usize>,
+}
+impl<'a> DeclaredLifetimes<'a> {
+    pub(crate) fn new(db: &'a Db, items: &Items) -> Self {
+        DeclaredLifetimes {
+            db,
+            local: items
+                .types()
+                .filter_map(|(id, item)| Some((id, generics_of(item)?.lifetimes.len())))
+                .collect(),
+        }
+    }
+
+    /// Only the types of crates already in `db` are known.
+    #[cfg(test)]
+    pub(crate) fn from_db(db: &'a Db) -> Self {
+        DeclaredLifetimes {
+            db,
+            local: Map::default(),
+        }
+    }
+
+    /// How many lifetime parameters the type `id` declares; 0 if it isn't known.
+    fn count(&self, id: &Identity) -> usize {
+        if let Some(count) = self.local.get(id) {
+            return *count;
+        }
+        match self.db.get_item::<TypeItem>(id).and_then(generics_of) {
+            Some(generics) => generics.lifetimes.len(),
+            None => 0,
+        }
+    }
+}
+
+/// Make every elided lifetime in a signature explicit, where rust's rules allow.
+/// `scope` and `metadata` are the function's module and metadata, used for the synthetic
+/// parameters.
+pub(crate) fn elide_lifetimes(
+    scope: &Identity,
+    metadata: &Metadata,
+    declared: &DeclaredLifetimes,
+    signature: &mut Signature,
+) {
+    let mut elided = vec![];
+    let mut inputs = vec![];
+    let mut fresh = |lifetime: Option<&LifetimeId>| {
+        let lifetime = match lifetime {
+            Some(lifetime) if !is_elided(lifetime) => lifetime.clone(),
+            _ => {
+                let name = Ident::elided_lifetime(elided.len());
+                elided.push(name.clone());
+                lifetime_id(name)
+            }
+        };
+        if !inputs.contains(&lifetime) {
+            inputs.push(lifetime.clone());
+        }
+        Some(lifetime)
+    };
+
+    let self_lifetime = match &mut signature.receiver {
+        Receiver::RefSelf { lifetime, .. } => {
+            *lifetime = fresh(lifetime.as_ref());
+            lifetime.clone()
+        }
+        Receiver::Other(type_) => {
+            visit_lifetimes(type_, declared, &mut fresh);
+            match type_ {
+                TypeId::Reference(ReferenceType { lifetime, .. }) => lifetime.clone(),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    for arg in &mut signature.args {
+        visit_lifetimes(&mut arg.type_, declared, &mut fresh);
+    }
+
+    let output = if self_lifetime.is_some() {
+        self_lifetime
+    } else if inputs.len() == 1 {
+        inputs.pop()
+    } else {
+        None
+    };
+    if let Some(output) = output {
+        visit_lifetimes(
+            &mut signature.ret,
+            declared,
+            &mut |lifetime| match lifetime {
+                Some(lifetime) if !is_elided(lifetime) => Some(lifetime.clone()),
+                _ => Some(output.clone()),
+            },
+        );
+    }
+
+    for name in elided {
+        signature.generics.lifetimes.push(LifetimeParamItem {
+            metadata: Metadata::synthetic(
+                name,
+                Visibility::InScope(scope.clone()),
+                metadata.span.clone(),
+            ),
+            bounds: vec![],
+        });
+    }
+}
+
+fn lifetime_id(name: Ident) -> LifetimeId {
+    LifetimeId::new(Identity::unresolved(&UnresolvedPath::new(false, &[name])))
+}
+
+/// If a lifetime is `'_`.
+fn is_elided(lifetime: &LifetimeId) -> bool {
+    match lifetime.id().as_unresolved() {
+        Some(path) => path.path.len() == 1 && &path.path[0][..] == "'_",
+        None => false,
+    }
+}
+
+/// Call `f` on every lifetime position in a type, left to right, replacing the lifetime with
+/// its result. `f` gets `None` for references with no lifetime written, and for each lifetime
+/// left out of a path.
+fn visit_lifetimes(
+    type_: &mut TypeId,
+    declared: &DeclaredLifetimes,
+    f: &mut dyn FnMut(Option<&LifetimeId>) -> Option<LifetimeId>,
+) {
+    match type_ {
+        TypeId::Reference(ReferenceType {
+            lifetime, type_, ..
+        }) => {
+            *lifetime = f(lifetime.as_ref());
+            visit_lifetimes(type_, declared, f);
+        }
+        TypeId::Path(PathType { path, params }) => {
+            let hidden = if params.lifetimes.is_empty() {
+                declared.count(path)
+            } else {
+                0
+            };
+            visit_params(params, hidden, declared, f);
+        }
+        TypeId::Array(ArrayType { type_, .. })
+        | TypeId::Slice(SliceType { type_ })
+        | TypeId::Pointer(PointerType { type_, .. }) => visit_lifetimes(type_, declared, f),
+        TypeId::Tuple(TupleType { types }) => {
+            for type_ in types {
+                visit_lifetimes(type_, declared, f);
+            }
+        }
+        TypeId::QSelf(QSelfType { self_, .. }) => visit_lifetimes(self_, declared, f),
+        TypeId::ImplTrait(ImplTraitType {
+            trait_bounds,
+            lifetime_bounds,
+        }) => {
+            visit_bounds(trait_bounds, declared, f);
+            for lifetime in lifetime_bounds {
+                if let Some(replacement) = f(Some(lifetime)) {
+                    *lifetime = replacement;
+                }
+            }
+        }
+        TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+            visit_bounds(trait_bounds, declared, f)
+        }
+        TypeId::Never(_) | TypeId::BareFn(_) => (),
+    }
+}
+
+/// Visit the arguments of trait bounds, skipping `Fn` sugar, which is its own elision scope.
+fn visit_bounds(
+    bounds: &mut [TraitId],
+    declared: &DeclaredLifetimes,
+    f: &mut dyn FnMut(Option<&LifetimeId>) -> Option<LifetimeId>,
+) {
+    for bound in bounds {
+        if bound.as_fn_sugar().is_none() {
+            visit_params(&mut bound.params, 0, declared, f);
+        }
+    }
+}
+
+/// Visit generic arguments in declaration order, so synthetic lifetimes are numbered predictably.
+/// `hidden` is the number of lifetime arguments left out entirely, which are filled in first.
+fn visit_params(
+    params: &mut GenericParams,
+    hidden: usize,
+    declared: &DeclaredLifetimes,
+    f: &mut dyn FnMut(Option<&LifetimeId>) -> Option<LifetimeId>,
+) {
+    let mut names: Vec<Ident> = params.lifetimes.keys().cloned().collect();
+    names.sort_by_key(|name| (name.as_positional(), name.clone()));
+    for i in 0..hidden {
+        if let Some(lifetime) = f(None) {
+            params.lifetimes.insert(Ident::positional(i), lifetime);
+        }
+    }
+    for name in names {
+        let lifetime = params.lifetimes.get(&name);
+        if let Some(lifetime) = f(lifetime) {
+            params.lifetimes.insert(name, lifetime);
+        }
+    }
+    let mut names: Vec<Ident> = params.type_bindings.keys().cloned().collect();
+    names.sort_by_key(|name| (name.as_positional(), name.clone()));
+    for name in names {
+        visit_lifetimes(params.type_bindings.get_mut(&name).unwrap(), declared, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::items::lower_signature;
+    use crate::walker::TEST_LOCATION_METADATA;
+
+    fn elided(method: syn::TraitItemMethod) -> Signature {
+        let loc = &*TEST_LOCATION_METADATA;
+        let db = Db::fake_db();
+        // `struct Ref<'a, T>`
+        let mut local = Map::default();
+        local.insert(Identity::unresolved(&UnresolvedPath::fake("Ref")), 1);
+        let declared = DeclaredLifetimes { db: &db, local };
+
+        let mut signature = lower_signature(loc, &method.sig).unwrap();
+        elide_lifetimes(
+            &loc.module_path,
+            &Metadata::fake("f"),
+            &declared,
+            &mut signature,
+        );
+        signature
+    }
+
+    fn lt(name: &str) -> Option<LifetimeId> {
+        Some(lifetime_id(Ident::lifetime(name)))
+    }
+
+    fn ref_lifetime(type_: &TypeId) -> Option<LifetimeId> {
+        match type_ {
+            TypeId::Reference(ReferenceType { lifetime, .. }) => lifetime.clone(),
+            _ => panic!("not a reference: {:?}", type_),
+        }
+    }
+
+    #[test]
+    fn lifetime_elision() {
+        let e0 = lt(&Ident::elided_lifetime(0));
+        let e1 = lt(&Ident::elided_lifetime(1));
+
+        // single input
+        let sig = elided(syn::parse_quote!(
+            fn first(x: &u8) -> &u8;
+        ));
+        assert_eq!(ref_lifetime(&sig.args[0].type_), e0);
+        assert_eq!(ref_lifetime(&sig.ret), e0);
+        assert_eq!(sig.generics.lifetimes.len(), 1);
+        assert!(sig.generics.lifetimes[0].metadata.name.is_elided_lifetime());
+
+        // &self wins
+        let sig = elided(syn::parse_quote!(
+            fn get(&self, key: &str) -> &Thing;
+        ));
+        assert_match!(&sig.receiver, Receiver::RefSelf { lifetime, .. } => {
+            assert_eq!(lifetime, &e0);
+        });
+        assert_eq!(ref_lifetime(&sig.args[0].type_), e1);
+        assert_eq!(ref_lifetime(&sig.ret), e0);
+        assert_eq!(sig.generics.lifetimes.len(), 2);
+
+        // ambiguous: inputs are filled in, output isn't
+        let sig = elided(syn::parse_quote!(
+            fn pick(a: &u8, b: &u8) -> &u8;
+        ));
+        assert_eq!(ref_lifetime(&sig.args[1].type_), e1);
+        assert_eq!(ref_lifetime(&sig.ret), None);
+
+        // `'_` counts as elided
+        let sig = elided(syn::parse_quote!(
+            fn wrap(x: Thing<'_>) -> &u8;
+        ));
+        assert_match!(&sig.args[0].type_, TypeId::Path(PathType { params, .. }) => {
+            assert_eq!(Some(params.lifetimes[&Ident::positional(0)].clone()), e0);
+        });
+        assert_eq!(ref_lifetime(&sig.ret), e0);
+
+        // explicit lifetimes are used, and not duplicated
+        let sig = elided(syn::parse_quote!(
+            fn explicit<'a>(x: &'a u8) -> &u8;
+        ));
+        assert_eq!(ref_lifetime(&sig.ret), lt("a"));
+        assert_eq!(sig.generics.lifetimes.len(), 1);
+
+        // the same lifetime in several places is still one lifetime
+        let sig = elided(syn::parse_quote!(
+            fn same<'a>(x: &'a u8, y: &'a u8) -> &u8;
+        ));
+        assert_eq!(ref_lifetime(&sig.ret), lt("a"));
+        assert_eq!(sig.generics.lifetimes.len(), 1);
+
+        // function pointers are their own scope
+        let sig = elided(syn::parse_quote!(
+            fn callback(f: fn(&u8) -> &u8) -> &u8;
+        ));
+        assert_match!(&sig.args[0].type_, TypeId::BareFn(bare_fn) => {
+            assert_eq!(ref_lifetime(&bare_fn.args[0]), None);
+        });
+        assert_eq!(ref_lifetime(&sig.ret), None);
+        assert!(sig.generics.lifetimes.is_empty());
+    }
+
+    #[test]
+    fn hidden_and_bound_lifetimes() {
+        let e0 = lt(&Ident::elided_lifetime(0));
+        let path_lifetime = |type_: &TypeId| match type_ {
+            TypeId::Path(PathType { params, .. }) => {
+                params.lifetimes.get(&Ident::positional(0)).cloned()
+            }
+            _ => panic!("not a path: {:?}", type_),
+        };
+
+        // lifetimes left out of paths are elided
+        let sig = elided(syn::parse_quote!(
+            fn hidden(x: Ref<u8>) -> &u8;
+        ));
+        assert_eq!(path_lifetime(&sig.args[0].type_), e0);
+        assert_eq!(ref_lifetime(&sig.ret), e0);
+        assert_eq!(sig.generics.lifetimes.len(), 1);
+
+        let sig = elided(syn::parse_quote!(
+            fn hidden_out(&self) -> Ref<u8>;
+        ));
+        assert_eq!(path_lifetime(&sig.ret), e0);
+        let sig = elided(syn::parse_quote!(
+            fn no_lifetimes(&self) -> Thing<u8>;
+        ));
+        assert_eq!(path_lifetime(&sig.ret), None);
+
+        // `impl Trait` and `dyn Trait` in the return type
+        let sig = elided(syn::parse_quote!(
+            fn iter(&self) -> impl Iterator<Item = &u8> + '_;
+        ));
+        assert_match!(&sig.ret, TypeId::ImplTrait(ImplTraitType { trait_bounds, lifetime_bounds }) => {
+            assert_eq!(ref_lifetime(&trait_bounds[0].params.type_bindings[&Ident::from("Item")]), e0);
+            assert_eq!(Some(lifetime_bounds[0].clone()), e0);
+        });
+        let sig = elided(syn::parse_quote!(
+            fn boxed(&self) -> Box<dyn Into<&u8>>;
+        ));
+        assert_match!(&sig.ret, TypeId::Path(PathType { params, .. }) => {
+            assert_match!(&params.type_bindings[&Ident::positional(0)], TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+                assert_eq!(ref_lifetime(&trait_bounds[0].params.type_bindings[&Ident::positional(0)]), e0);
+            });
+        });
+
+        // but `Fn` sugar is its own scope
+        let sig = elided(syn::parse_quote!(
+            fn callback(&self) -> impl Fn(&u8) -> &u8;
+        ));
+        assert_match!(&sig.ret, TypeId::ImplTrait(ImplTraitType { trait_bounds, .. }) => {
+            let (inputs, output) = trait_bounds[0].as_fn_sugar().unwrap();
+            assert_eq!(ref_lifetime(&inputs[0]), None);
+            assert_eq!(ref_lifetime(output), None);
+        });
+    }
+}
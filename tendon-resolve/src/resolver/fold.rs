@@ -0,0 +1,654 @@
+//! Folding over every type and trait reference in a crate's items.
+//!
+//! Resolution passes implement `Folder` and override the bits they care about; the `fold_*`
+//! functions here take care of walking the item tree.
+
+use crate::lower::generics::own_params;
+use std::mem;
+use tendon_api::{
+    attributes::{Metadata, TypeMetadata},
+    database::{Crate, Namespace},
+    expressions::ConstArg,
+    generics::{Generics, WherePredicate},
+    identities::{
+        ArrayType, BareFnType, CrateId, GenericParams, Identity, ImplTraitType, LifetimeId,
+        PathType, PointerType, QSelfType, ReferenceType, SliceType, TraitId, TraitObjectType,
+        TupleType, TypeId,
+    },
+    items::{ImplItem, Receiver, Signature, SymbolItem, TypeItem},
+    paths::Ident,
+};
+
+/// Rewrites the types and trait references in items.
+///
+/// The `fold_*` methods default to rebuilding their input from folded children.
+/// `enter_generics` and `exit_generics` bracket everything a set of generic parameters is in
+/// scope for, including the parameters' own bounds.
+pub(crate) trait Folder {
+    /// Called before folding the item `item`, declared in the module `scope`.
+    fn enter_item(&mut self, _scope: &Identity, _item: &Identity) {}
+    /// Called when the generics declared by `owner` come into scope.
+    fn enter_generics(&mut self, _owner: &Identity, _generics: &Generics) {}
+    /// Called when the generics most recently entered go out of scope.
+    fn exit_generics(&mut self) {}
+    /// Called when entering a type, trait, or impl, where `Self` means something.
+    /// Items are only nested one level deep, so this lasts until the next `enter_item`.
+    fn enter_self(&mut self, _context: &SelfContext) {}
+
+    fn fold_type(&mut self, type_: &TypeId) -> TypeId {
+        fold_type_children(self, type_)
+    }
+    fn fold_trait(&mut self, trait_: &TraitId) -> TraitId {
+        fold_trait_children(self, trait_)
+    }
+    fn fold_params(&mut self, params: &GenericParams) -> GenericParams {
+        fold_params_children(self, params)
+    }
+    fn fold_lifetime(&mut self, lifetime: &LifetimeId) -> LifetimeId {
+        lifetime.clone()
+    }
+    fn fold_const(&mut self, const_: &ConstArg) -> ConstArg {
+        const_.clone()
+    }
+}
+
+/// Rebuild a type, folding everything it contains.
+pub(crate) fn fold_type_children<F: Folder + ?Sized>(folder: &mut F, type_: &TypeId) -> TypeId {
+    match type_ {
+        TypeId::Path(PathType { path, params }) => TypeId::Path(PathType {
+            path: path.clone(),
+            params: folder.fold_params(params),
+        }),
+        TypeId::Array(ArrayType { type_, len }) => TypeId::Array(ArrayType {
+            type_: Box::new(folder.fold_type(type_)),
+            len: folder.fold_const(len),
+        }),
+        TypeId::Slice(SliceType { type_ }) => TypeId::Slice(SliceType {
+            type_: Box::new(folder.fold_type(type_)),
+        }),
+        TypeId::Reference(ReferenceType {
+            type_,
+            mut_,
+            lifetime,
+        }) => TypeId::Reference(ReferenceType {
+            type_: Box::new(folder.fold_type(type_)),
+            mut_: *mut_,
+            lifetime: lifetime.as_ref().map(|lt| folder.fold_lifetime(lt)),
+        }),
+        TypeId::Pointer(PointerType { type_, mut_ }) => TypeId::Pointer(PointerType {
+            type_: Box::new(folder.fold_type(type_)),
+            mut_: *mut_,
+        }),
+        TypeId::Tuple(TupleType { types }) => TypeId::Tuple(TupleType {
+            types: types.iter().map(|t| folder.fold_type(t)).collect(),
+        }),
+        TypeId::Never(_) => type_.clone(),
+        TypeId::QSelf(QSelfType {
+            self_,
+            trait_,
+            output_,
+        }) => TypeId::QSelf(QSelfType {
+            self_: Box::new(folder.fold_type(self_)),
+            trait_: folder.fold_trait(trait_),
+            output_: output_.clone(),
+        }),
+        TypeId::BareFn(BareFnType {
+            args,
+            ret,
+            varargs,
+            unsafe_,
+            for_lifetimes,
+        }) => TypeId::BareFn(BareFnType {
+            args: args.iter().map(|t| folder.fold_type(t)).collect(),
+            ret: Box::new(folder.fold_type(ret)),
+            varargs: *varargs,
+            unsafe_: *unsafe_,
+            for_lifetimes: for_lifetimes.clone(),
+        }),
+        TypeId::ImplTrait(ImplTraitType {
+            lifetime_bounds,
+            trait_bounds,
+        }) => TypeId::ImplTrait(ImplTraitType {
+            lifetime_bounds: lifetime_bounds
+                .iter()
+                .map(|lt| folder.fold_lifetime(lt))
+                .collect(),
+            trait_bounds: trait_bounds.iter().map(|t| folder.fold_trait(t)).collect(),
+        }),
+        TypeId::TraitObject(TraitObjectType { trait_bounds }) => {
+            TypeId::TraitObject(TraitObjectType {
+                trait_bounds: trait_bounds.iter().map(|t| folder.fold_trait(t)).collect(),
+            })
+        }
+    }
+}
+
+/// Rebuild a trait reference, folding its generic arguments.
+pub(crate) fn fold_trait_children<F: Folder + ?Sized>(folder: &mut F, trait_: &TraitId) -> TraitId {
+    TraitId {
+        id: trait_.id.clone(),
+        params: folder.fold_params(&trait_.params),
+        is_maybe: trait_.is_maybe,
+        for_lifetimes: trait_.for_lifetimes.clone(),
+    }
+}
+
+/// Rebuild a set of generic arguments, folding every argument.
+pub(crate) fn fold_params_children<F: Folder + ?Sized>(
+    folder: &mut F,
+    params: &GenericParams,
+) -> GenericParams {
+    GenericParams {
+        type_bindings: params
+            .type_bindings
+            .iter()
+            .map(|(name, type_)| (name.clone(), folder.fold_type(type_)))
+            .collect(),
+        lifetimes: params
+            .lifetimes
+            .iter()
+            .map(|(name, lifetime)| (name.clone(), folder.fold_lifetime(lifetime)))
+            .collect(),
+        consts: params
+            .consts
+            .iter()
+            .map(|(name, const_)| (name.clone(), folder.fold_const(const_)))
+            .collect(),
+    }
+}
+
+/// The items of a crate, taken out of it so that they can be folded while the rest of the crate
+/// (its scopes, mainly) is used for resolution.
+pub(crate) struct Items {
+    crate_: CrateId,
+    types: Namespace<TypeItem>,
+    symbols: Namespace<SymbolItem>,
+    impls: Vec<ImplItem>,
+}
+impl Items {
+    /// Take the items out of a crate.
+    pub(crate) fn take(crate_: &mut Crate) -> Items {
+        Items {
+            crate_: crate_.id.clone(),
+            types: mem::replace(&mut crate_.types, Namespace::new()),
+            symbols: mem::replace(&mut crate_.symbols, Namespace::new()),
+            impls: mem::replace(&mut crate_.impls, vec![]),
+        }
+    }
+
+    /// Put the items back.
+    pub(crate) fn restore(self, crate_: &mut Crate) {
+        assert_eq!(
+            crate_.id, self.crate_,
+            "restoring items to the wrong crate!"
+        );
+        crate_.types = self.types;
+        crate_.symbols = self.symbols;
+        crate_.impls = self.impls;
+    }
+
+    /// Every type item in the crate, by identity.
+    pub(crate) fn types(&self) -> impl Iterator<Item = (Identity, &TypeItem)> + '_ {
+        let crate_ = &self.crate_;
+        self.types
+            .0
+            .iter()
+            .map(move |(path, item)| (Identity::new(crate_, path), item))
+    }
+
+    /// Call `f` on every function signature, along with the module it's declared in, the
+    /// function's identity (which owns its generics), and its metadata.
+    pub(crate) fn for_each_signature(
+        &mut self,
+        mut f: impl FnMut(&Identity, &Identity, &Metadata, &mut Signature),
+    ) {
+        let crate_ = &self.crate_;
+        for (path, item) in self.types.0.iter_mut() {
+            if let TypeItem::Trait(trait_) = item {
+                let owner = Identity::new(crate_, path);
+                let scope = owner.parent().expect("items are in modules");
+                for method in &mut trait_.methods {
+                    let owner = owner.clone_join(method.metadata.name.clone());
+                    f(&scope, &owner, &method.metadata, &mut method.signature);
+                }
+            }
+        }
+        for (path, item) in self.symbols.0.iter_mut() {
+            let owner = Identity::new(crate_, path);
+            let scope = owner.parent().expect("items are in modules");
+            match item {
+                SymbolItem::Function(function) => {
+                    f(&scope, &owner, &function.metadata, &mut function.signature)
+                }
+                SymbolItem::ForeignFunction(function) => {
+                    f(&scope, &owner, &function.metadata, &mut function.signature)
+                }
+                _ => (),
+            }
+        }
+        for impl_ in self.impls.iter_mut() {
+            let owner = impl_.scope.clone_join(impl_.metadata.name.clone());
+            for method in &mut impl_.methods {
+                let owner = owner.clone_join(method.metadata.name.clone());
+                f(
+                    &impl_.scope,
+                    &owner,
+                    &method.metadata,
+                    &mut method.signature,
+                );
+            }
+        }
+    }
+
+    /// Call `f` on the metadata of every item and member (fields, variants, methods, ...), along
+    /// with the module it's declared in and, for members of types, traits and impls, what `Self`
+    /// means there.
+    pub(crate) fn for_each_metadata(
+        &mut self,
+        mut f: impl FnMut(&Identity, Option<&Identity>, &mut Metadata),
+    ) {
+        let crate_ = &self.crate_;
+        for (path, item) in self.types.0.iter_mut() {
+            let owner = Identity::new(crate_, path);
+            let scope = owner.parent().expect("items are in modules");
+            match item {
+                TypeItem::Struct(struct_) => {
+                    f(&scope, Some(&owner), &mut struct_.metadata);
+                    for field in &mut struct_.fields {
+                        f(&scope, Some(&owner), &mut field.metadata);
+                    }
+                }
+                TypeItem::Enum(enum_) => {
+                    f(&scope, Some(&owner), &mut enum_.metadata);
+                    for variant in &mut enum_.variants {
+                        f(&scope, Some(&owner), &mut variant.metadata);
+                        for field in &mut variant.fields {
+                            f(&scope, Some(&owner), &mut field.metadata);
+                        }
+                    }
+                }
+                TypeItem::Union(union_) => {
+                    f(&scope, Some(&owner), &mut union_.metadata);
+                    for field in &mut union_.fields {
+                        f(&scope, Some(&owner), &mut field.metadata);
+                    }
+                }
+                TypeItem::Trait(trait_) => {
+                    f(&scope, Some(&owner), &mut trait_.metadata);
+                    for method in &mut trait_.methods {
+                        f(&scope, Some(&owner), &mut method.metadata);
+                    }
+                    for type_ in &mut trait_.types {
+                        f(&scope, Some(&owner), &mut type_.metadata);
+                    }
+                    for const_ in &mut trait_.consts {
+                        f(&scope, Some(&owner), &mut const_.metadata);
+                    }
+                }
+                TypeItem::Alias(alias) => f(&scope, None, &mut alias.metadata),
+                TypeItem::ForeignType(type_) => f(&scope, None, &mut type_.metadata),
+                // generic parameters live in their items, not in modules
+                TypeItem::TypeParam(_) | TypeItem::LifetimeParam(_) => (),
+            }
+        }
+        for (path, item) in self.symbols.0.iter_mut() {
+            let owner = Identity::new(crate_, path);
+            let scope = owner.parent().expect("items are in modules");
+            match item {
+                SymbolItem::Const(const_) => f(&scope, None, &mut const_.metadata),
+                SymbolItem::Static(static_) => f(&scope, None, &mut static_.metadata),
+                SymbolItem::Function(function) => f(&scope, None, &mut function.metadata),
+                SymbolItem::ForeignFunction(function) => f(&scope, None, &mut function.metadata),
+                SymbolItem::ForeignStatic(static_) => f(&scope, None, &mut static_.metadata),
+                SymbolItem::ConstParam(_) => (),
+            }
+        }
+        for impl_ in self.impls.iter_mut() {
+            let self_type = match &impl_.self_type {
+                TypeId::Path(PathType { path, .. }) => Some(path.clone()),
+                _ => None,
+            };
+            let self_type = self_type.as_ref();
+            f(&impl_.scope, self_type, &mut impl_.metadata);
+            for method in &mut impl_.methods {
+                f(&impl_.scope, self_type, &mut method.metadata);
+            }
+            for type_ in &mut impl_.types {
+                f(&impl_.scope, self_type, &mut type_.metadata);
+            }
+            for const_ in &mut impl_.consts {
+                f(&impl_.scope, self_type, &mut const_.metadata);
+            }
+        }
+    }
+
+    /// Fold every item.
+    pub(crate) fn fold<F: Folder>(&mut self, folder: &mut F) {
+        let crate_ = &self.crate_;
+        for (path, item) in self.types.0.iter_mut() {
+            let owner = Identity::new(crate_, path);
+            folder.enter_item(&owner.parent().expect("items are in modules"), &owner);
+            fold_type_item(folder, &owner, item);
+        }
+        for (path, item) in self.symbols.0.iter_mut() {
+            let owner = Identity::new(crate_, path);
+            folder.enter_item(&owner.parent().expect("items are in modules"), &owner);
+            fold_symbol_item(folder, &owner, item);
+        }
+        for impl_ in self.impls.iter_mut() {
+            let owner = impl_.scope.clone_join(impl_.metadata.name.clone());
+            folder.enter_item(&impl_.scope, &owner);
+            fold_impl(folder, impl_);
+        }
+    }
+}
+
+fn fold_in_place<T, F: FnOnce(&T) -> T>(value: &mut T, f: F) {
+    *value = f(value);
+}
+
+/// Fold a type item declared at `owner`.
+pub(crate) fn fold_type_item<F: Folder>(folder: &mut F, owner: &Identity, item: &mut TypeItem) {
+    match item {
+        TypeItem::Struct(struct_) => {
+            folder.enter_generics(owner, &struct_.generics);
+            fold_generics(folder, &mut struct_.generics);
+            folder.enter_self(&SelfContext::of_type(owner, &struct_.generics));
+            fold_type_metadata(folder, &mut struct_.type_metadata);
+            for field in &mut struct_.fields {
+                fold_in_place(&mut field.type_, |t| folder.fold_type(t));
+            }
+            folder.exit_generics();
+        }
+        TypeItem::Enum(enum_) => {
+            folder.enter_generics(owner, &enum_.generics);
+            fold_generics(folder, &mut enum_.generics);
+            folder.enter_self(&SelfContext::of_type(owner, &enum_.generics));
+            fold_type_metadata(folder, &mut enum_.type_metadata);
+            for variant in &mut enum_.variants {
+                for field in &mut variant.fields {
+                    fold_in_place(&mut field.type_, |t| folder.fold_type(t));
+                }
+            }
+            folder.exit_generics();
+        }
+        TypeItem::Union(union_) => {
+            folder.enter_generics(owner, &union_.generics);
+            fold_generics(folder, &mut union_.generics);
+            folder.enter_self(&SelfContext::of_type(owner, &union_.generics));
+            fold_type_metadata(folder, &mut union_.type_metadata);
+            for field in &mut union_.fields {
+                fold_in_place(&mut field.type_, |t| folder.fold_type(t));
+            }
+            folder.exit_generics();
+        }
+        TypeItem::Trait(trait_) => {
+            folder.enter_generics(owner, &trait_.generics);
+            fold_generics(folder, &mut trait_.generics);
+            for bound in &mut trait_.self_param.trait_bounds {
+                fold_in_place(bound, |t| folder.fold_trait(t));
+            }
+            folder.enter_self(&SelfContext {
+                type_: TypeId::Path(PathType {
+                    path: owner.clone_join(trait_.self_param.metadata.name.clone()),
+                    params: GenericParams::empty(),
+                }),
+                trait_: trait_.self_param.trait_bounds.first().cloned(),
+                types: trait_
+                    .types
+                    .iter()
+                    .map(|t| t.metadata.name.clone())
+                    .collect(),
+                supertraits: trait_.supertraits.clone(),
+                is_impl: false,
+            });
+            for supertrait in &mut trait_.supertraits {
+                fold_in_place(supertrait, |t| folder.fold_trait(t));
+            }
+            for lifetime in &mut trait_.lifetime_bounds {
+                fold_in_place(lifetime, |lt| folder.fold_lifetime(lt));
+            }
+            for method in &mut trait_.methods {
+                let owner = owner.clone_join(method.metadata.name.clone());
+                fold_signature(folder, &owner, &mut method.signature);
+            }
+            for type_ in &mut trait_.types {
+                let owner = owner.clone_join(type_.metadata.name.clone());
+                folder.enter_generics(&owner, &type_.generics);
+                fold_generics(folder, &mut type_.generics);
+                for bound in &mut type_.trait_bounds {
+                    fold_in_place(bound, |t| folder.fold_trait(t));
+                }
+                for lifetime in &mut type_.lifetime_bounds {
+                    fold_in_place(lifetime, |lt| folder.fold_lifetime(lt));
+                }
+                if let Some(default) = &mut type_.default {
+                    fold_in_place(default, |t| folder.fold_type(t));
+                }
+                folder.exit_generics();
+            }
+            for const_ in &mut trait_.consts {
+                fold_in_place(&mut const_.type_, |t| folder.fold_type(t));
+            }
+            folder.exit_generics();
+        }
+        TypeItem::Alias(alias) => {
+            folder.enter_generics(owner, &alias.generics);
+            fold_generics(folder, &mut alias.generics);
+            fold_in_place(&mut alias.target, |t| folder.fold_type(t));
+            folder.exit_generics();
+        }
+        TypeItem::ForeignType(_) => (),
+        TypeItem::TypeParam(param) => {
+            for bound in &mut param.trait_bounds {
+                fold_in_place(bound, |t| folder.fold_trait(t));
+            }
+            if let Some(default) = &mut param.default {
+                fold_in_place(default, |t| folder.fold_type(t));
+            }
+        }
+        TypeItem::LifetimeParam(param) => {
+            for bound in &mut param.bounds {
+                fold_in_place(bound, |lt| folder.fold_lifetime(lt));
+            }
+        }
+    }
+}
+
+/// Fold a symbol item declared at `owner`.
+pub(crate) fn fold_symbol_item<F: Folder>(folder: &mut F, owner: &Identity, item: &mut SymbolItem) {
+    match item {
+        SymbolItem::Const(const_) => fold_in_place(&mut *const_.type_, |t| folder.fold_type(t)),
+        SymbolItem::Static(static_) => fold_in_place(&mut *static_.type_, |t| folder.fold_type(t)),
+        SymbolItem::Function(function) => fold_signature(folder, owner, &mut function.signature),
+        SymbolItem::ForeignFunction(function) => {
+            fold_signature(folder, owner, &mut function.signature)
+        }
+        SymbolItem::ForeignStatic(static_) => {
+            fold_in_place(&mut static_.type_, |t| folder.fold_type(t))
+        }
+        SymbolItem::ConstParam(param) => {
+            fold_in_place(&mut param.type_, |t| folder.fold_type(t));
+            if let Some(default) = &mut param.default {
+                fold_in_place(default, |c| folder.fold_const(c));
+            }
+        }
+    }
+}
+
+/// Fold an impl block.
+pub(crate) fn fold_impl<F: Folder>(folder: &mut F, impl_: &mut ImplItem) {
+    let owner = impl_.scope.clone_join(impl_.metadata.name.clone());
+    folder.enter_generics(&owner, &impl_.generics);
+    fold_generics(folder, &mut impl_.generics);
+    if let Some(trait_) = &mut impl_.trait_ {
+        fold_in_place(trait_, |t| folder.fold_trait(t));
+    }
+    fold_in_place(&mut impl_.self_type, |t| folder.fold_type(t));
+    folder.enter_self(&SelfContext {
+        type_: impl_.self_type.clone(),
+        trait_: impl_.trait_.clone(),
+        types: impl_
+            .types
+            .iter()
+            .map(|t| t.metadata.name.clone())
+            .collect(),
+        supertraits: vec![],
+        is_impl: true,
+    });
+    for method in &mut impl_.methods {
+        let owner = owner.clone_join(method.metadata.name.clone());
+        fold_signature(folder, &owner, &mut method.signature);
+    }
+    for type_ in &mut impl_.types {
+        let owner = owner.clone_join(type_.metadata.name.clone());
+        folder.enter_generics(&owner, &type_.generics);
+        fold_generics(folder, &mut type_.generics);
+        fold_in_place(&mut type_.type_, |t| folder.fold_type(t));
+        folder.exit_generics();
+    }
+    for const_ in &mut impl_.consts {
+        fold_in_place(&mut *const_.type_, |t| folder.fold_type(t));
+    }
+    folder.exit_generics();
+}
+
+/// Fold a function signature, declared at `owner`.
+pub(crate) fn fold_signature<F: Folder>(
+    folder: &mut F,
+    owner: &Identity,
+    signature: &mut Signature,
+) {
+    folder.enter_generics(owner, &signature.generics);
+    fold_generics(folder, &mut signature.generics);
+    for arg in &mut signature.args {
+        fold_in_place(&mut arg.type_, |t| folder.fold_type(t));
+    }
+    fold_in_place(&mut signature.ret, |t| folder.fold_type(t));
+    match &mut signature.receiver {
+        Receiver::RefSelf {
+            lifetime: Some(lifetime),
+            ..
+        } => fold_in_place(lifetime, |lt| folder.fold_lifetime(lt)),
+        Receiver::Other(type_) => fold_in_place(type_, |t| folder.fold_type(t)),
+        _ => (),
+    }
+    folder.exit_generics();
+}
+
+/// Fold the bounds, defaults and predicates of a set of generics.
+/// Doesn't call `enter_generics`; the caller should have done that already.
+pub(crate) fn fold_generics<F: Folder>(folder: &mut F, generics: &mut Generics) {
+    for param in &mut generics.lifetimes {
+        for bound in &mut param.bounds {
+            fold_in_place(bound, |lt| folder.fold_lifetime(lt));
+        }
+    }
+    for param in &mut generics.types {
+        for bound in &mut param.trait_bounds {
+            fold_in_place(bound, |t| folder.fold_trait(t));
+        }
+        for bound in &mut param.lifetime_bounds {
+            fold_in_place(bound, |lt| folder.fold_lifetime(lt));
+        }
+        if let Some(default) = &mut param.default {
+            fold_in_place(default, |t| folder.fold_type(t));
+        }
+    }
+    for param in &mut generics.consts {
+        fold_in_place(&mut param.type_, |t| folder.fold_type(t));
+        if let Some(default) = &mut param.default {
+            fold_in_place(default, |c| folder.fold_const(c));
+        }
+    }
+    for predicate in &mut generics.where_predicates {
+        match predicate {
+            WherePredicate::Type(predicate) => {
+                fold_in_place(&mut predicate.type_, |t| folder.fold_type(t));
+                for bound in &mut predicate.trait_bounds {
+                    fold_in_place(bound, |t| folder.fold_trait(t));
+                }
+                for bound in &mut predicate.lifetime_bounds {
+                    fold_in_place(bound, |lt| folder.fold_lifetime(lt));
+                }
+            }
+            WherePredicate::Lifetime(predicate) => {
+                fold_in_place(&mut predicate.lifetime, |lt| folder.fold_lifetime(lt));
+                for bound in &mut predicate.bounds {
+                    fold_in_place(bound, |lt| folder.fold_lifetime(lt));
+                }
+            }
+        }
+    }
+}
+
+fn fold_type_metadata<F: Folder>(folder: &mut F, type_metadata: &mut TypeMetadata) {
+    for derive in &mut type_metadata.derives {
+        fold_in_place(derive, |t| folder.fold_trait(t));
+    }
+}
+
+/// What `Self` means inside a type, trait, or impl.
+///
+/// Note that the trait and self type are only resolved if the folder runs after path resolution.
+#[derive(Clone)]
+pub(crate) struct SelfContext {
+    /// The type `Self` stands for: the type itself, the trait's implicit `Self` parameter, or the
+    /// impl's self type.
+    pub type_: TypeId,
+    /// The trait that `Self::Assoc` projections go through, if any.
+    pub trait_: Option<TraitId>,
+    /// Associated types declared in this trait or impl.
+    pub types: Vec<Ident>,
+    /// The supertraits of a trait, whose associated types can be used through `Self` too.
+    pub supertraits: Vec<TraitId>,
+    /// If this is an impl block (rather than a type or trait declaration).
+    pub is_impl: bool,
+}
+impl SelfContext {
+    /// `Self` in a struct, enum or union: the type itself, with its parameters passed through.
+    fn of_type(owner: &Identity, generics: &Generics) -> SelfContext {
+        SelfContext {
+            type_: TypeId::Path(PathType {
+                path: owner.clone(),
+                params: own_params(generics, |name| owner.clone_join(name.clone())),
+            }),
+            trait_: None,
+            types: vec![],
+            supertraits: vec![],
+            is_impl: false,
+        }
+    }
+}
+
+/// The names of the parameters declared by a set of generics, for `Folder`s that need to track
+/// what's in scope.
+pub(crate) struct GenericsScope {
+    /// The item that declared these generics.
+    pub owner: Identity,
+    pub types: Vec<Ident>,
+    pub lifetimes: Vec<Ident>,
+    pub consts: Vec<Ident>,
+}
+impl GenericsScope {
+    pub(crate) fn new(owner: &Identity, generics: &Generics) -> GenericsScope {
+        GenericsScope {
+            owner: owner.clone(),
+            types: generics
+                .types
+                .iter()
+                .map(|p| p.metadata.name.clone())
+                .collect(),
+            lifetimes: generics
+                .lifetimes
+                .iter()
+                .map(|p| p.metadata.name.clone())
+                .collect(),
+            consts: generics
+                .consts
+                .iter()
+                .map(|p| p.metadata.name.clone())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Argument-position `impl Trait`.
+//!
+//! `fn f(x: impl Into<String>)` is sugar for `fn f<{impl_trait0}: Into<String>>(x: {impl_trait0})`,
+//! except that callers can't name the parameter. This pass does that desugaring, so generators
+//! can treat these arguments like any other generic. Return-position `impl Trait` is a different
+//! thing entirely (an opaque type chosen by the callee) and is left alone.
+
+use tendon_api::{
+    attributes::{Metadata, Visibility},
+    identities::{GenericParams, Identity, ImplTraitType, PathType, TypeId},
+    items::{Signature, TypeParamItem},
+    paths::Ident,
+};
+
+/// Replace every `impl Trait` in a signature's arguments with an anonymous type parameter of the
+/// function `owner`, declared in the module `scope`.
+pub(crate) fn desugar_impl_trait_args(
+    scope: &Identity,
+    owner: &Identity,
+    metadata: &Metadata,
+    signature: &mut Signature,
+) {
+    let mut params = vec![];
+    for arg in &mut signature.args {
+        arg.type_ = desugar(&arg.type_, owner, &mut params);
+    }
+
+    for (
+        name,
+        ImplTraitType {
+            trait_bounds,
+            lifetime_bounds,
+        },
+    ) in params
+    {
+        signature.generics.types.push(TypeParamItem {
+            metadata: Metadata::synthetic(
+                name,
+                Visibility::InScope(scope.clone()),
+                metadata.span.clone(),
+            ),
+            trait_bounds,
+            lifetime_bounds,
+            default: None,
+        });
+    }
+}
+
+/// Desugar a single type, adding new parameters to `params`.
+/// `impl Trait`s nested in the bounds of other `impl Trait`s are desugared too.
+fn desugar(type_: &TypeId, owner: &Identity, params: &mut Vec<(Ident, ImplTraitType)>) -> TypeId {
+    if let TypeId::ImplTrait(impl_trait) = type_ {
+        let bounds = ImplTraitType {
+            trait_bounds: impl_trait
+                .trait_bounds
+                .iter()
+                .map(|bound| {
+                    bound.map_children(
+                        &mut |type_| desugar(type_, owner, params),
+                        &mut |lifetime| lifetime.clone(),
+                        &mut |const_| const_.clone(),
+                    )
+                })
+                .collect(),
+            lifetime_bounds: impl_trait.lifetime_bounds.clone(),
+        };
+        let name = Ident::impl_trait_param(params.len());
+        params.push((name.clone(), bounds));
+        return TypeId::Path(PathType {
+            path: owner.clone_join(name),
+            params: GenericParams::empty(),
+        });
+    }
+    type_.map_children(
+        &mut |type_| desugar(type_, owner, params),
+        &mut |lifetime| lifetime.clone(),
+        &mut |const_| const_.clone(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::items::lower_signature;
+    use crate::resolver::elision::{elide_lifetimes, DeclaredLifetimes};
+    use crate::walker::TEST_LOCATION_METADATA;
+    use tendon_api::builtins::{FUTURE, OUTPUT};
+    use tendon_api::database::Db;
+    use tendon_api::generics::WherePredicate;
+
+    fn lower(method: syn::TraitItemMethod) -> (Identity, Signature) {
+        let loc = &*TEST_LOCATION_METADATA;
+        let owner = loc.module_path.clone_join(Ident::from(&method.sig.ident));
+        (owner, lower_signature(loc, &method.sig).unwrap())
+    }
+
+    #[test]
+    fn impl_trait_args() {
+        let scope = &TEST_LOCATION_METADATA.module_path;
+        let (owner, mut sig) = lower(syn::parse_quote! {
+            fn f<T>(x: impl Clone, y: Vec<impl Iterator<Item = impl Copy>>, z: T) -> impl Clone;
+        });
+        desugar_impl_trait_args(scope, &owner, &Metadata::fake("f"), &mut sig);
+
+        let param = |i| owner.clone_join(Ident::impl_trait_param(i));
+        assert_match!(&sig.args[0].type_, TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &param(0));
+        });
+        assert_match!(&sig.args[1].type_, TypeId::Path(PathType { params, .. }) => {
+            // the nested one is desugared first
+            assert_match!(&params.type_bindings[&Ident::positional(0)], TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &param(2));
+            });
+        });
+        assert_match!(&sig.ret, TypeId::ImplTrait(_));
+
+        let types = &sig.generics.types;
+        assert_eq!(types.len(), 4);
+        assert_eq!(types[0].metadata.name, Ident::from("T"));
+        assert!(types[1..]
+            .iter()
+            .all(|param| param.metadata.name.is_impl_trait_param()));
+        assert_eq!(types[1].trait_bounds.len(), 1);
+        let iterator = &types[3].trait_bounds[0];
+        assert_match!(&iterator.params.type_bindings[&Ident::from("Item")], TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &param(1));
+        });
+    }
+
+    #[test]
+    fn async_desugaring() {
+        let scope = &TEST_LOCATION_METADATA.module_path;
+        let metadata = Metadata::fake("get");
+        let (_, mut sig) = lower(syn::parse_quote! {
+            async fn get(&self, x: &u8) -> u8;
+        });
+        let db = Db::fake_db();
+        elide_lifetimes(scope, &metadata, &DeclaredLifetimes::from_db(&db), &mut sig);
+
+        let desugared = sig.desugar_async(&metadata);
+        assert!(!desugared.is_async);
+        assert_match!(&desugared.ret, TypeId::ImplTrait(ImplTraitType { trait_bounds, lifetime_bounds }) => {
+            assert_eq!(trait_bounds[0].id, *FUTURE);
+            assert_match!(&trait_bounds[0].params.type_bindings[&*OUTPUT], TypeId::Path(_));
+            assert_eq!(lifetime_bounds.len(), 1);
+        });
+        let lifetimes = &desugared.generics.lifetimes;
+        assert_eq!(lifetimes.len(), 3);
+        assert_eq!(lifetimes[2].metadata.name, Ident::captured_lifetime());
+        let predicates = &desugared.generics.where_predicates;
+        assert_eq!(predicates.len(), 2);
+        assert_match!(&predicates[0], WherePredicate::Lifetime(_));
+
+        // the original is untouched, and sync functions don't change
+        assert!(sig.is_async);
+        let (_, sync) = lower(syn::parse_quote!(
+            fn f() -> u8;
+        ));
+        assert_match!(sync.desugar_async(&metadata).ret, TypeId::Path(_));
+    }
+}
@@ -0,0 +1,663 @@
+//! Type layouts: sizes, alignments and field offsets.
+//!
+//! Only layouts rust guarantees are computed:
+//!
+//! - `#[repr(C)]` structs and unions, laid out in declaration order like C would, including
+//!   `packed(n)` and `align(n)` modifiers.
+//! - `#[repr(transparent)]` types, which are laid out like their one non-zero-sized field.
+//! - `#[repr(packed)]` structs. These are `repr(Rust)`, but with every field at alignment 1
+//!   there's nothing to gain by reordering, so rustc keeps them in declaration order.
+//! - Enums with a primitive (`#[repr(u8)]`) or C-plus-primitive (`#[repr(C, u8)]`) tag, as
+//!   specified in https://github.com/rust-lang/rfcs/blob/master/text/2195-really-tagged-unions.md,
+//!   and `#[repr(C)]` enums, which use a C `int` tag.
+//!
+//! Anything else -- `repr(Rust)` types, tuples, trait objects, generic parameters -- has an
+//! unknown layout, and so does anything containing one. So do pointers to types that may be
+//! dynamically sized, other than slices, `str` and trait objects: `?Sized` parameters and
+//! structs with an unsized last field. Extern types (`extern { type Opaque; }`) are unsized, but
+//! pointers to them are thin.
+
+use super::auto_traits::generics_of;
+use crate::lower::expressions::evaluate_const;
+use lazy_static::lazy_static;
+use tendon_api::{
+    attributes::{Repr, ReprBase},
+    builtins::{BUILTINS_CRATE, PHANTOM_DATA, SIZED, STR},
+    database::Db,
+    expressions::{ConstArg, ConstValue},
+    generics::{Generics, WherePredicate},
+    identities::{ArrayType, Identity, PathType, Substitutions, TraitId, TupleType, TypeId},
+    items::{EnumItem, StructField, SymbolItem, TypeItem},
+    paths::Ident,
+    Map,
+};
+use tracing::error;
+
+/// Types nested deeper than this are assumed to be cyclic.
+const MAX_LAYOUT_DEPTH: usize = 64;
+
+lazy_static! {
+    static ref USIZE: Ident = "usize".into();
+    static ref SELF_TYPE: Ident = "Self".into();
+    /// The tag type of `#[repr(C)]` enums, C's `int`.
+    static ref C_INT: Ident = "i32".into();
+}
+
+/// The properties of a compilation target that affect layout.
+#[derive(Clone, Debug)]
+pub struct Target {
+    /// The size of pointers, `usize` and `isize`, in bytes.
+    pub pointer_size: u64,
+    /// The alignment of each primitive type, by name (`u64`, `f64`, `usize`, ...).
+    /// Primitives missing from this map are aligned to their size; primitives aligned to 0 have
+    /// an unknown layout.
+    pub primitive_alignments: Map<Ident, u64>,
+}
+impl Target {
+    /// A target with `pointer_width`-bit pointers, where every primitive is aligned to its size.
+    pub fn new(pointer_width: u64) -> Target {
+        Target {
+            pointer_size: pointer_width / 8,
+            primitive_alignments: Map::default(),
+        }
+    }
+
+    /// The layout of a primitive type, `u8`, `bool`, `usize`; `None` if `name` isn't one.
+    pub fn primitive(&self, name: &str) -> Option<Layout> {
+        let size = match name {
+            "bool" | "u8" | "i8" => 1,
+            "u16" | "i16" => 2,
+            "u32" | "i32" | "f32" | "char" => 4,
+            "u64" | "i64" | "f64" => 8,
+            "u128" | "i128" => 16,
+            "usize" | "isize" => self.pointer_size,
+            _ => return None,
+        };
+        let align = self
+            .primitive_alignments
+            .get(&Ident::from(name))
+            .cloned()
+            .unwrap_or(size);
+        if align == 0 {
+            return None;
+        }
+        Some(Layout::scalar(size, align))
+    }
+}
+
+/// The layout of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The size of the type in bytes, including trailing padding.
+    pub size: u64,
+    /// The alignment of the type in bytes.
+    pub align: u64,
+    /// The offset of each field of a struct or union, in declaration order.
+    /// Empty for other types.
+    pub field_offsets: Vec<u64>,
+    /// For enums, the offset of each field of each variant. The tag is always at offset 0.
+    pub variant_offsets: Vec<Vec<u64>>,
+}
+impl Layout {
+    fn scalar(size: u64, align: u64) -> Layout {
+        Layout {
+            size,
+            align,
+            field_offsets: vec![],
+            variant_offsets: vec![],
+        }
+    }
+}
+
+/// Computes type layouts.
+pub struct LayoutEngine<'a> {
+    db: &'a Db,
+    target: Target,
+}
+impl<'a> LayoutEngine<'a> {
+    pub fn new(db: &'a Db, target: Target) -> LayoutEngine<'a> {
+        LayoutEngine { db, target }
+    }
+
+    /// The layout of a type, if it's known.
+    pub fn layout(&self, type_: &TypeId) -> Option<Layout> {
+        self.layout_(type_, 0)
+    }
+
+    fn layout_(&self, type_: &TypeId, depth: usize) -> Option<Layout> {
+        if depth > MAX_LAYOUT_DEPTH {
+            error!("layout too deep, cycle? {:?}", type_);
+            return None;
+        }
+        match self.db.expand_aliases(type_) {
+            TypeId::Path(path) => self.path_layout(&path, depth),
+            TypeId::Reference(reference) => self.pointer_layout(&reference.type_, depth),
+            TypeId::Pointer(pointer) => self.pointer_layout(&pointer.type_, depth),
+            TypeId::BareFn(_) => Some(Layout::scalar(
+                self.target.pointer_size,
+                self.pointer_align()?,
+            )),
+            TypeId::Array(ArrayType { type_, len }) => {
+                let element = self.layout_(&type_, depth + 1)?;
+                let len = self.array_len(&len)?;
+                Some(Layout::scalar(
+                    element.size.checked_mul(len)?,
+                    element.align,
+                ))
+            }
+            TypeId::Tuple(tuple) if tuple.types.is_empty() => Some(Layout::scalar(0, 1)),
+            TypeId::Never(_) => Some(Layout::scalar(0, 1)),
+            _ => None,
+        }
+    }
+
+    fn pointer_align(&self) -> Option<u64> {
+        Some(self.target.primitive(&USIZE)?.align)
+    }
+
+    /// Pointers to slices, `str` and trait objects carry a length or vtable. Pointers to extern
+    /// types don't. Pointers to other types that may be unsized are unknown.
+    fn pointer_layout(&self, pointee: &TypeId, depth: usize) -> Option<Layout> {
+        let pointee = self.db.expand_aliases(pointee);
+        let words = match &pointee {
+            TypeId::Slice(_) | TypeId::TraitObject(_) => 2,
+            TypeId::Path(PathType { path, .. }) if path == &*STR => 2,
+            _ if self.is_sized(&pointee, depth + 1) => 1,
+            TypeId::Path(PathType { path, .. }) => match self.db.get_item::<TypeItem>(path) {
+                Some(TypeItem::ForeignType(_)) => 1,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Layout::scalar(
+            self.target.pointer_size * words,
+            self.pointer_align()?,
+        ))
+    }
+
+    /// If a type is known to be `Sized`.
+    fn is_sized(&self, type_: &TypeId, depth: usize) -> bool {
+        if depth > MAX_LAYOUT_DEPTH {
+            error!("layout too deep, cycle? {:?}", type_);
+            return false;
+        }
+        match self.db.expand_aliases(type_) {
+            TypeId::Slice(_) | TypeId::TraitObject(_) | TypeId::QSelf(_) => false,
+            TypeId::Tuple(TupleType { types }) => match types.last() {
+                Some(last) => self.is_sized(last, depth + 1),
+                None => true,
+            },
+            TypeId::Path(path) => self.path_is_sized(&path, depth),
+            _ => true,
+        }
+    }
+
+    fn path_is_sized(&self, path: &PathType, depth: usize) -> bool {
+        if path.path.crate_ == *BUILTINS_CRATE {
+            return path.path != *STR;
+        }
+        if path.path == *PHANTOM_DATA {
+            return true;
+        }
+        match self.db.get_item::<TypeItem>(&path.path) {
+            // only a struct's last field can be unsized
+            Some(TypeItem::Struct(struct_)) => match struct_.fields.last() {
+                Some(last) => {
+                    let substitutions =
+                        Substitutions::for_generics(&path.path, &struct_.generics, &path.params);
+                    self.is_sized(&last.type_.substitute(&substitutions), depth + 1)
+                }
+                None => true,
+            },
+            Some(TypeItem::Enum(_)) | Some(TypeItem::Union(_)) => true,
+            Some(_) => false,
+            None => self.param_is_sized(&path.path),
+        }
+    }
+
+    /// If `path` names a generic parameter without a `?Sized` bound. `Self` in a trait is
+    /// `?Sized`; parameters we can't find the declaration of aren't known to be sized.
+    fn param_is_sized(&self, path: &Identity) -> bool {
+        let (name, parent) = match (path.path.last(), path.parent()) {
+            (Some(name), Some(parent)) => (name, parent),
+            _ => return false,
+        };
+        if name == &*SELF_TYPE {
+            return false;
+        }
+        let generics = match self.db.get_item::<TypeItem>(&parent).and_then(generics_of) {
+            Some(generics) => generics,
+            None => match self.db.get_item::<SymbolItem>(&parent) {
+                Some(SymbolItem::Function(function)) => &function.signature.generics,
+                _ => return false,
+            },
+        };
+        match generics.type_param(name) {
+            Some(param) => !is_maybe_sized(&param.trait_bounds, generics, path),
+            None => false,
+        }
+    }
+
+    fn array_len(&self, len: &ConstArg) -> Option<u64> {
+        let value = match len {
+            ConstArg::Path(path) => match self.db.get_item::<SymbolItem>(path)? {
+                SymbolItem::Const(const_) => const_.evaluated.clone()?,
+                _ => return None,
+            },
+            ConstArg::Expr(expr) => {
+                let expr = expr.0.parse::<syn::Expr>().ok()?;
+                evaluate_const(&expr, Some(&USIZE), &mut |_| None)?
+            }
+        };
+        match value {
+            ConstValue::Int(len, _) if len >= 0 => Some(len as u64),
+            _ => None,
+        }
+    }
+
+    fn path_layout(&self, path: &PathType, depth: usize) -> Option<Layout> {
+        if path.path.crate_ == *BUILTINS_CRATE {
+            return self.target.primitive(path.path.path.last()?);
+        }
+        if path.path == *PHANTOM_DATA {
+            return Some(Layout::scalar(0, 1));
+        }
+
+        let item = self.db.get_item::<TypeItem>(&path.path)?;
+        let field_layouts = |generics, fields: &[StructField]| {
+            let substitutions = Substitutions::for_generics(&path.path, generics, &path.params);
+            fields
+                .iter()
+                .map(|field| self.layout_(&field.type_.substitute(&substitutions), depth + 1))
+                .collect::<Option<Vec<Layout>>>()
+        };
+        match item {
+            TypeItem::Struct(struct_) => {
+                let fields = field_layouts(&struct_.generics, &struct_.fields)?;
+                let repr = &struct_.type_metadata.repr;
+                match &repr.base {
+                    ReprBase::Transparent => Some(transparent(&fields)),
+                    _ if is_ordered(repr) => Some(with_align(c_struct(&fields, repr.packed), repr)),
+                    _ => None,
+                }
+            }
+            TypeItem::Union(union_) => {
+                let fields = field_layouts(&union_.generics, &union_.fields)?;
+                let repr = &union_.type_metadata.repr;
+                match &repr.base {
+                    ReprBase::Transparent => Some(transparent(&fields)),
+                    _ if is_ordered(repr) => Some(with_align(c_union(&fields, repr.packed), repr)),
+                    _ => None,
+                }
+            }
+            TypeItem::Enum(enum_) => {
+                let variants = enum_
+                    .variants
+                    .iter()
+                    .map(|variant| field_layouts(&enum_.generics, &variant.fields))
+                    .collect::<Option<Vec<Vec<Layout>>>>()?;
+                let layout = self.enum_layout(enum_, &variants)?;
+                Some(with_align(layout, &enum_.type_metadata.repr))
+            }
+            _ => None,
+        }
+    }
+
+    fn enum_layout(&self, enum_: &EnumItem, variants: &[Vec<Layout>]) -> Option<Layout> {
+        let fieldless = variants.iter().all(|fields| fields.is_empty());
+        let (tag, outer) = match &enum_.type_metadata.repr.base {
+            ReprBase::Int(int) => (self.target.primitive(int)?, false),
+            ReprBase::IntOuterTag(int) => (self.target.primitive(int)?, true),
+            ReprBase::C => (self.target.primitive(&C_INT)?, true),
+            _ => return None,
+        };
+        if variants.is_empty() {
+            // uninhabited, and can't be represented in C
+            return None;
+        }
+        if fieldless {
+            let mut layout = tag;
+            layout.variant_offsets = vec![vec![]; variants.len()];
+            return Some(layout);
+        }
+
+        if outer {
+            // #[repr(C)] struct { tag: Tag, payload: #[repr(C)] union { #[repr(C)] struct ... } }
+            let payloads: Vec<Layout> = variants
+                .iter()
+                .map(|fields| c_struct(fields, None))
+                .collect();
+            let payload = c_union(&payloads, None);
+            let mut layout = c_struct(&[tag, payload], None);
+            let payload_offset = layout.field_offsets[1];
+            layout.field_offsets = vec![];
+            layout.variant_offsets = payloads
+                .into_iter()
+                .map(|payload| {
+                    payload
+                        .field_offsets
+                        .iter()
+                        .map(|offset| payload_offset + offset)
+                        .collect()
+                })
+                .collect();
+            Some(layout)
+        } else {
+            // #[repr(C)] union { #[repr(C)] struct { tag: Tag, fields... } ... }
+            let variant_structs: Vec<Layout> = variants
+                .iter()
+                .map(|fields| {
+                    let mut with_tag = vec![tag.clone()];
+                    with_tag.extend(fields.iter().cloned());
+                    c_struct(&with_tag, None)
+                })
+                .collect();
+            let mut layout = c_union(&variant_structs, None);
+            layout.field_offsets = vec![];
+            layout.variant_offsets = variant_structs
+                .into_iter()
+                .map(|variant| variant.field_offsets[1..].to_vec())
+                .collect();
+            Some(layout)
+        }
+    }
+}
+
+/// Round `offset` up to a multiple of `align`. An alignment of 0 is treated as 1.
+fn align_to(offset: u64, align: u64) -> u64 {
+    let align = align.max(1);
+    (offset + align - 1) / align * align
+}
+
+/// If the parameter `param` is bounded by `?Sized`, inline or in a where clause.
+fn is_maybe_sized(bounds: &[TraitId], generics: &Generics, param: &Identity) -> bool {
+    let maybe_sized = |bounds: &[TraitId]| {
+        bounds
+            .iter()
+            .any(|bound| bound.is_maybe && bound.id == *SIZED)
+    };
+    maybe_sized(bounds)
+        || generics
+            .where_predicates
+            .iter()
+            .any(|predicate| match predicate {
+                WherePredicate::Type(predicate) => {
+                    maybe_sized(&predicate.trait_bounds)
+                        && match &predicate.type_ {
+                            TypeId::Path(PathType { path, params }) => {
+                                path == param && params.is_empty()
+                            }
+                            _ => false,
+                        }
+                }
+                _ => false,
+            })
+}
+
+/// If a struct or union is laid out in declaration order: it's `repr(C)`, or `repr(packed)`
+/// with nothing else.
+fn is_ordered(repr: &Repr) -> bool {
+    match repr.base {
+        ReprBase::C => true,
+        ReprBase::Rust => repr.packed == Some(1) && repr.align.is_none(),
+        _ => false,
+    }
+}
+
+/// Apply `#[repr(align(n))]`.
+fn with_align(mut layout: Layout, repr: &Repr) -> Layout {
+    if let Some(align) = repr.align {
+        layout.align = layout.align.max(align);
+        layout.size = align_to(layout.size, layout.align);
+    }
+    layout
+}
+
+/// Lay out fields in order, padding each to its alignment, capped at `packed`.
+fn c_struct(fields: &[Layout], packed: Option<u64>) -> Layout {
+    let mut offset = 0;
+    let mut align = 1;
+    let mut field_offsets = vec![];
+    for field in fields {
+        let field_align = packed.map_or(field.align, |packed| field.align.min(packed));
+        offset = align_to(offset, field_align);
+        field_offsets.push(offset);
+        offset += field.size;
+        align = align.max(field_align);
+    }
+    Layout {
+        size: align_to(offset, align),
+        align,
+        field_offsets,
+        variant_offsets: vec![],
+    }
+}
+
+/// Overlap fields at offset 0.
+fn c_union(fields: &[Layout], packed: Option<u64>) -> Layout {
+    let size = fields.iter().map(|field| field.size).max().unwrap_or(0);
+    let align = fields
+        .iter()
+        .map(|field| packed.map_or(field.align, |packed| field.align.min(packed)))
+        .max()
+        .unwrap_or(1);
+    Layout {
+        size: align_to(size, align),
+        align,
+        field_offsets: vec![0; fields.len()],
+        variant_offsets: vec![],
+    }
+}
+
+/// Lay out like the one non-zero-sized field.
+fn transparent(fields: &[Layout]) -> Layout {
+    let inner = fields.iter().find(|field| field.size > 0);
+    Layout {
+        size: inner.map(|field| field.size).unwrap_or(0),
+        align: inner.map(|field| field.align).unwrap_or(1),
+        field_offsets: vec![0; fields.len()],
+        variant_offsets: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items;
+    use tendon_api::identities::{GenericParams, Identity};
+
+    #[test]
+    fn layouts() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            #[repr(C)]
+            pub struct Header {
+                tag: u8,
+                len: u32,
+                data: *const u8,
+            }
+            #[repr(packed)]
+            pub struct Packed {
+                a: u8,
+                b: u32,
+            }
+            #[repr(transparent)]
+            pub struct Wrapper(u64, ());
+            #[repr(C)]
+            pub union Either {
+                a: u16,
+                b: [u8; 3],
+            }
+            #[repr(u8)]
+            pub enum Primitive {
+                A(u32),
+                B(u8, u16),
+            }
+            #[repr(C, u8)]
+            pub enum Outer {
+                A(u32),
+                B(u8, u16),
+            }
+            #[repr(C)]
+            pub enum Fieldless {
+                A,
+                B,
+            }
+            pub struct Rusty {
+                a: u8,
+            }
+            #[repr(C)]
+            pub struct Nested {
+                header: Header,
+                rusty: Rusty,
+            }
+            #[repr(C)]
+            pub struct Wide {
+                slice: &'static [u8],
+                callback: fn(),
+            }
+            pub const LEN: usize = 3;
+            #[repr(C)]
+            pub struct Array {
+                a: [u16; LEN],
+            }
+            #[repr(C, packed(2))]
+            pub struct PackedTo2 {
+                a: u8,
+                b: u64,
+            }
+            #[repr(C, align(16))]
+            pub struct Aligned {
+                a: u32,
+            }
+            #[repr(u8, C)]
+            pub enum OuterReversed {
+                A(u32),
+                B(u8, u16),
+            }
+            #[repr(C)]
+            pub struct Pair<T> {
+                a: T,
+                b: u8,
+            }
+            #[repr(C)]
+            pub struct ToSized<T>(*const T, &'static Pair<T>, *const (u8, u16));
+            #[repr(C)]
+            pub struct ToUnsized<T: ?Sized>(*const T);
+            #[repr(C)]
+            pub struct ToUnsizedWhere<T>(*const T)
+            where
+                T: ?Sized;
+            pub struct Tail {
+                len: usize,
+                data: [u8],
+            }
+            #[repr(C)]
+            pub struct ToTail(*const Tail);
+            #[repr(C)]
+            pub struct ToTuple(*const (u8, [u8]));
+            extern "C" {
+                pub type Opaque;
+            }
+            #[repr(C)]
+            pub struct ToOpaque(*const Opaque, u8);
+        };
+        let root = walk_test_items(&db, &items);
+
+        let type_ = |name: &str| {
+            TypeId::Path(PathType {
+                path: root.clone_join(name),
+                params: GenericParams::empty(),
+            })
+        };
+        let layout_64 = LayoutEngine::new(&db, Target::new(64));
+        let layout_32 = LayoutEngine::new(&db, Target::new(32));
+        let size_align = |layout: Option<Layout>| {
+            let layout = layout.unwrap();
+            (layout.size, layout.align)
+        };
+
+        let header = layout_64.layout(&type_("Header")).unwrap();
+        assert_eq!((header.size, header.align), (16, 8));
+        assert_eq!(header.field_offsets, vec![0, 4, 8]);
+        let header = layout_32.layout(&type_("Header")).unwrap();
+        assert_eq!((header.size, header.align), (12, 4));
+
+        let packed = layout_64.layout(&type_("Packed")).unwrap();
+        assert_eq!((packed.size, packed.align), (5, 1));
+        assert_eq!(packed.field_offsets, vec![0, 1]);
+
+        assert_eq!(size_align(layout_64.layout(&type_("Wrapper"))), (8, 8));
+        assert_eq!(size_align(layout_64.layout(&type_("Either"))), (4, 2));
+
+        // tag in each variant
+        let primitive = layout_64.layout(&type_("Primitive")).unwrap();
+        assert_eq!((primitive.size, primitive.align), (8, 4));
+        assert_eq!(primitive.variant_offsets, vec![vec![4], vec![1, 2]]);
+
+        // tag, then a union of the variants
+        let outer = layout_64.layout(&type_("Outer")).unwrap();
+        assert_eq!((outer.size, outer.align), (8, 4));
+        assert_eq!(outer.variant_offsets, vec![vec![4], vec![4, 6]]);
+
+        assert_eq!(size_align(layout_64.layout(&type_("Fieldless"))), (4, 4));
+
+        // repr(Rust) is unknown, and so is anything containing it
+        assert_eq!(layout_64.layout(&type_("Rusty")), None);
+        assert_eq!(layout_64.layout(&type_("Nested")), None);
+
+        let wide = layout_64.layout(&type_("Wide")).unwrap();
+        assert_eq!(wide.field_offsets, vec![0, 16]);
+        assert_eq!(wide.size, 24);
+
+        assert_eq!(size_align(layout_64.layout(&type_("Array"))), (6, 2));
+
+        // combined reprs
+        let packed = layout_64.layout(&type_("PackedTo2")).unwrap();
+        assert_eq!((packed.size, packed.align), (10, 2));
+        assert_eq!(packed.field_offsets, vec![0, 2]);
+        assert_eq!(size_align(layout_64.layout(&type_("Aligned"))), (16, 16));
+        assert_eq!(
+            layout_64.layout(&type_("OuterReversed")),
+            layout_64.layout(&type_("Outer"))
+        );
+
+        // generic, with a substituted parameter
+        let mut params = GenericParams::empty();
+        params.type_bindings.insert(
+            Ident::positional(0),
+            TypeId::Path(PathType {
+                path: Identity::new(&*BUILTINS_CRATE, &["u32"]),
+                params: GenericParams::empty(),
+            }),
+        );
+        let pair = TypeId::Path(PathType {
+            path: root.clone_join("Pair"),
+            params,
+        });
+        assert_eq!(size_align(layout_64.layout(&pair)), (8, 4));
+        assert_eq!(layout_64.layout(&type_("Pair")), None);
+
+        // pointers to things that may be unsized
+        let to_sized = layout_64.layout(&type_("ToSized")).unwrap();
+        assert_eq!(to_sized.field_offsets, vec![0, 8, 16]);
+        assert_eq!(to_sized.size, 24);
+        assert_eq!(layout_64.layout(&type_("ToUnsized")), None);
+        assert_eq!(layout_64.layout(&type_("ToUnsizedWhere")), None);
+        assert_eq!(layout_64.layout(&type_("ToTail")), None);
+        assert_eq!(layout_64.layout(&type_("ToTuple")), None);
+        // extern types are unsized, but thin
+        let to_opaque = layout_64.layout(&type_("ToOpaque")).unwrap();
+        assert_eq!(to_opaque.field_offsets, vec![0, 8]);
+        assert_eq!((to_opaque.size, to_opaque.align), (16, 8));
+
+        // a bogus target doesn't panic
+        let mut target = Target::new(64);
+        target.primitive_alignments.insert("u32".into(), 0);
+        let layout_bogus = LayoutEngine::new(&db, target);
+        assert_eq!(layout_bogus.layout(&type_("Header")), None);
+        assert_eq!(align_to(3, 0), 3);
+    }
+}
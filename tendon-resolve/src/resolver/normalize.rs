@@ -0,0 +1,398 @@
+//! Normalizing associated type projections, `<Vec<u8> as IntoIterator>::Item` to `u8`.
+//!
+//! A projection is normalized when its self type is known -- headed by a real type, not a
+//! generic parameter -- and exactly one impl of the trait matches it. Impls for a bare type
+//! parameter (`impl<T: Bound> Trait for T`) are skipped, since we can't check their bounds;
+//! projections they'd apply to are left alone.
+
+use tendon_api::{
+    builtins::BUILTINS_CRATE,
+    database::Db,
+    expressions::ConstArg,
+    identities::{
+        ArrayType, GenericParams, Identity, LifetimeId, PathType, PointerType, QSelfType,
+        ReferenceType, SliceType, Substitutions, TraitId, TupleType, TypeId,
+    },
+    items::{ImplItem, TypeItem},
+    paths::UnresolvedPath,
+    Map,
+};
+use tracing::error;
+
+/// Projections nested deeper than this are assumed to be cyclic.
+const MAX_NORMALIZE_DEPTH: usize = 64;
+
+/// The trait impls in a database, indexed by trait.
+pub struct ImplIndex<'a> {
+    impls: Map<Identity, Vec<&'a ImplItem>>,
+}
+impl<'a> ImplIndex<'a> {
+    /// Index the trait impls in every crate lowered so far.
+    pub fn new(db: &'a Db) -> ImplIndex<'a> {
+        let mut impls: Map<Identity, Vec<&'a ImplItem>> = Map::default();
+        for crate_ in db.crates() {
+            for impl_ in &crate_.impls {
+                if let Some(trait_) = &impl_.trait_ {
+                    impls.entry(trait_.id.clone()).or_default().push(impl_);
+                }
+            }
+        }
+        ImplIndex { impls }
+    }
+
+    /// All impls of a trait, including negative impls.
+    pub fn impls_of(&self, trait_: &Identity) -> &[&'a ImplItem] {
+        self.impls.get(trait_).map(|v| &v[..]).unwrap_or(&[])
+    }
+}
+
+/// Replaces associated type projections with the types they stand for.
+pub struct Normalizer<'a> {
+    db: &'a Db,
+    index: &'a ImplIndex<'a>,
+}
+impl<'a> Normalizer<'a> {
+    pub fn new(db: &'a Db, index: &'a ImplIndex<'a>) -> Normalizer<'a> {
+        Normalizer { db, index }
+    }
+
+    /// Normalize every projection in a type that can be normalized.
+    pub fn normalize(&self, type_: &TypeId) -> TypeId {
+        self.normalize_(type_, 0)
+    }
+
+    fn normalize_(&self, type_: &TypeId, depth: usize) -> TypeId {
+        if depth > MAX_NORMALIZE_DEPTH {
+            error!("projection normalization too deep, cycle? {:?}", type_);
+            return type_.clone();
+        }
+        // only projections count towards the limit, deeply nested types are fine
+        let type_ = type_.map_children(
+            &mut |type_| self.normalize_(type_, depth),
+            &mut |lifetime| lifetime.clone(),
+            &mut |const_| const_.clone(),
+        );
+        if let TypeId::QSelf(projection) = &type_ {
+            if let Some(normalized) = self.project(projection) {
+                return self.normalize_(&normalized, depth + 1);
+            }
+        }
+        type_
+    }
+
+    /// Find the type a single projection stands for, if it's known.
+    fn project(&self, projection: &QSelfType) -> Option<TypeId> {
+        if !self.is_known(&projection.self_) {
+            return None;
+        }
+
+        let mut candidates = self
+            .index
+            .impls_of(&projection.trait_.id)
+            .iter()
+            .filter(|impl_| !impl_.is_negative)
+            .filter_map(|impl_| {
+                let substitutions = match_impl(impl_, &projection.self_, &projection.trait_)?;
+                let output = impl_
+                    .types
+                    .iter()
+                    .find(|type_| type_.metadata.name == projection.output_)?;
+                Some(output.type_.substitute(&substitutions))
+            });
+
+        let result = candidates.next()?;
+        if candidates.next().is_some() {
+            error!(
+                "ambiguous projection: {:?}",
+                TypeId::QSelf(projection.clone())
+            );
+            return None;
+        }
+        Some(result)
+    }
+
+    /// If a type is headed by something real, rather than a generic parameter, `Self`, or an
+    /// unresolved path.
+    fn is_known(&self, type_: &TypeId) -> bool {
+        match type_ {
+            TypeId::Path(PathType { path, .. }) => {
+                path.crate_ == *BUILTINS_CRATE
+                    || match self.db.get_item::<TypeItem>(path) {
+                        Some(TypeItem::TypeParam(_)) | Some(TypeItem::LifetimeParam(_)) => false,
+                        Some(_) => true,
+                        None => false,
+                    }
+            }
+            TypeId::QSelf(_) | TypeId::ImplTrait(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Check if an impl applies to a self type and trait reference; if it does, return the values of
+/// the impl's parameters.
+pub(crate) fn match_impl(
+    impl_: &ImplItem,
+    self_: &TypeId,
+    trait_: &TraitId,
+) -> Option<Substitutions> {
+    let impl_trait = impl_.trait_.as_ref()?;
+    if impl_trait.id != trait_.id {
+        return None;
+    }
+
+    let owner = impl_.scope.clone_join(impl_.metadata.name.clone());
+    let mut matcher = Matcher {
+        types: impl_
+            .generics
+            .types
+            .iter()
+            .map(|param| owner.clone_join(param.metadata.name.clone()))
+            .collect(),
+        consts: impl_
+            .generics
+            .consts
+            .iter()
+            .map(|param| owner.clone_join(param.metadata.name.clone()))
+            .collect(),
+        lifetimes: impl_
+            .generics
+            .lifetimes
+            .iter()
+            .map(|param| {
+                Identity::unresolved(&UnresolvedPath::new(false, &[param.metadata.name.clone()]))
+            })
+            .collect(),
+        substitutions: Substitutions::default(),
+    };
+
+    if let TypeId::Path(PathType { path, .. }) = &impl_.self_type {
+        if matcher.types.contains(path) {
+            // blanket impl
+            return None;
+        }
+    }
+
+    if matcher.match_type(&impl_.self_type, self_)
+        && matcher.match_params(&impl_trait.params, &trait_.params)
+    {
+        Some(matcher.substitutions)
+    } else {
+        None
+    }
+}
+
+/// Matches an impl's types, which may mention its parameters, against concrete types.
+struct Matcher {
+    types: Vec<Identity>,
+    consts: Vec<Identity>,
+    lifetimes: Vec<Identity>,
+    substitutions: Substitutions,
+}
+impl Matcher {
+    fn match_type(&mut self, pattern: &TypeId, type_: &TypeId) -> bool {
+        match (pattern, type_) {
+            (TypeId::Path(PathType { path, params }), _)
+                if params.is_empty() && self.types.contains(path) =>
+            {
+                if let Some(bound) = self.substitutions.types.get(path) {
+                    bound == type_
+                } else {
+                    self.substitutions.types.insert(path.clone(), type_.clone());
+                    true
+                }
+            }
+            (TypeId::Path(pattern), TypeId::Path(type_)) => {
+                pattern.path == type_.path && self.match_params(&pattern.params, &type_.params)
+            }
+            (
+                TypeId::Reference(ReferenceType {
+                    type_: pattern,
+                    mut_: pattern_mut,
+                    lifetime: pattern_lifetime,
+                }),
+                TypeId::Reference(ReferenceType {
+                    type_,
+                    mut_,
+                    lifetime,
+                }),
+            ) => {
+                if let (Some(pattern_lifetime), Some(lifetime)) = (pattern_lifetime, lifetime) {
+                    self.match_lifetime(pattern_lifetime, lifetime);
+                }
+                pattern_mut == mut_ && self.match_type(pattern, type_)
+            }
+            (
+                TypeId::Pointer(PointerType {
+                    type_: pattern,
+                    mut_: pattern_mut,
+                }),
+                TypeId::Pointer(PointerType { type_, mut_ }),
+            ) => pattern_mut == mut_ && self.match_type(pattern, type_),
+            (TypeId::Slice(SliceType { type_: pattern }), TypeId::Slice(SliceType { type_ })) => {
+                self.match_type(pattern, type_)
+            }
+            (
+                TypeId::Array(ArrayType {
+                    type_: pattern,
+                    len: pattern_len,
+                }),
+                TypeId::Array(ArrayType { type_, len }),
+            ) => self.match_type(pattern, type_) && self.match_const(pattern_len, len),
+            (TypeId::Tuple(TupleType { types: patterns }), TypeId::Tuple(TupleType { types })) => {
+                patterns.len() == types.len()
+                    && patterns
+                        .iter()
+                        .zip(types.iter())
+                        .all(|(pattern, type_)| self.match_type(pattern, type_))
+            }
+            // anything else has to match exactly
+            (pattern, type_) => pattern == type_,
+        }
+    }
+
+    /// Match generic arguments. Arguments missing from `params` (e.g. defaults that haven't been
+    /// filled in) don't match.
+    fn match_params(&mut self, pattern: &GenericParams, params: &GenericParams) -> bool {
+        for (name, pattern) in &pattern.lifetimes {
+            if let Some(lifetime) = params.lifetimes.get(name) {
+                self.match_lifetime(pattern, lifetime);
+            }
+        }
+        pattern
+            .type_bindings
+            .iter()
+            .all(|(name, pattern)| match params.type_bindings.get(name) {
+                Some(type_) => self.match_type(pattern, type_),
+                None => false,
+            })
+            && pattern
+                .consts
+                .iter()
+                .all(|(name, pattern)| match params.consts.get(name) {
+                    Some(const_) => self.match_const(pattern, const_),
+                    None => false,
+                })
+    }
+
+    fn match_const(&mut self, pattern: &ConstArg, const_: &ConstArg) -> bool {
+        match pattern {
+            ConstArg::Path(path) if self.consts.contains(path) => {
+                if let Some(bound) = self.substitutions.consts.get(path) {
+                    bound == const_
+                } else {
+                    self.substitutions
+                        .consts
+                        .insert(path.clone(), const_.clone());
+                    true
+                }
+            }
+            _ => pattern == const_,
+        }
+    }
+
+    /// Lifetimes never prevent a match; we just record them for substitution.
+    fn match_lifetime(&mut self, pattern: &LifetimeId, lifetime: &LifetimeId) {
+        if self.lifetimes.contains(pattern.id()) {
+            self.substitutions
+                .lifetimes
+                .insert(pattern.id().clone(), lifetime.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items;
+    use tendon_api::items::SymbolItem;
+    use tendon_api::paths::Ident;
+
+    #[test]
+    fn projection_normalization() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub struct Byte;
+            pub struct MyVec<T>(T);
+            pub trait IntoIter {
+                type Item;
+            }
+            impl<T> IntoIter for MyVec<T> {
+                type Item = T;
+            }
+            impl<'a, T> IntoIter for &'a MyVec<T> {
+                type Item = &'a T;
+            }
+            pub fn concrete(
+                a: <MyVec<Byte> as IntoIter>::Item,
+                b: <&'static MyVec<Byte> as IntoIter>::Item,
+                c: <<MyVec<MyVec<Byte>> as IntoIter>::Item as IntoIter>::Item,
+                d: <Byte as IntoIter>::Item,
+            ) {
+            }
+            pub fn generic<T>(a: <T as IntoIter>::Item, b: <MyVec<T> as IntoIter>::Item) {}
+        };
+        let root = walk_test_items(&db, &items);
+
+        let index = ImplIndex::new(&db);
+        assert_eq!(index.impls_of(&root.clone_join("IntoIter")).len(), 2);
+        let normalizer = Normalizer::new(&db, &index);
+
+        let args = |f: &str| -> Vec<TypeId> {
+            match db.get_item::<SymbolItem>(&root.clone_join(f)) {
+                Some(SymbolItem::Function(function)) => function
+                    .signature
+                    .args
+                    .iter()
+                    .map(|arg| normalizer.normalize(&arg.type_))
+                    .collect(),
+                _ => panic!("no function {}", f),
+            }
+        };
+        let byte = root.clone_join("Byte");
+
+        let concrete = args("concrete");
+        assert_match!(&concrete[0], TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &byte);
+        });
+        assert_match!(&concrete[1], TypeId::Reference(ReferenceType { type_, lifetime: Some(lifetime), .. }) => {
+            assert_match!(&**type_, TypeId::Path(PathType { path, .. }) => {
+                assert_eq!(path, &byte);
+            });
+            assert_eq!(lifetime.id().path, vec![Ident::lifetime("static")]);
+        });
+        assert_match!(&concrete[2], TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &byte);
+        });
+        // no impl
+        assert_match!(&concrete[3], TypeId::QSelf(_));
+
+        // plain nesting doesn't count as normalization
+        let mut nested = match db.get_item::<SymbolItem>(&root.clone_join("concrete")) {
+            Some(SymbolItem::Function(function)) => function.signature.args[0].type_.clone(),
+            _ => panic!("no function concrete"),
+        };
+        for _ in 0..MAX_NORMALIZE_DEPTH * 2 {
+            nested = TypeId::Reference(ReferenceType {
+                type_: Box::new(nested),
+                mut_: false,
+                lifetime: None,
+            });
+        }
+        let mut innermost = normalizer.normalize(&nested);
+        while let TypeId::Reference(reference) = innermost {
+            innermost = *reference.type_;
+        }
+        assert_match!(innermost, TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &byte);
+        });
+
+        let generic = args("generic");
+        assert_match!(&generic[0], TypeId::QSelf(_));
+        assert_match!(&generic[1], TypeId::Path(PathType { path, .. }) => {
+            assert_eq!(path, &root.clone_join("generic").clone_join("T"));
+        });
+    }
+}
@@ -0,0 +1,418 @@
+//! Object safety: whether a trait can be used as `dyn Trait`.
+//!
+//! A trait is object safe if it doesn't require `Self: Sized`, has no associated consts or
+//! generic associated types, doesn't mention `Self` in its supertraits' arguments, all of its
+//! supertraits are object safe, and all of its methods are dispatchable. A method that's
+//! bounded by `where Self: Sized` is exempt: it just isn't available on the trait object.
+//!
+//! Supertraits that haven't been lowered are assumed to be object safe, except for a few common
+//! `core` traits that are known not to be.
+
+use lazy_static::lazy_static;
+use tendon_api::{
+    builtins::{ARC, BOX, CORE_CRATE, PIN, RC, SIZED},
+    database::Db,
+    generics::{Generics, WherePredicate},
+    identities::{Identity, PathType, QSelfType, ReferenceType, TraitId, TypeId},
+    items::{Receiver, TraitItem, TraitMethod, TypeItem},
+    paths::Ident,
+};
+
+lazy_static! {
+    static ref SELF_TYPE: Ident = "Self".into();
+    /// `core` traits that require `Self: Sized` or use `Self` in a way that can't be dispatched.
+    static ref NOT_OBJECT_SAFE: Vec<Identity> = vec![
+        Identity::new(&*CORE_CRATE, &["clone", "Clone"]),
+        Identity::new(&*CORE_CRATE, &["marker", "Copy"]),
+        Identity::new(&*CORE_CRATE, &["default", "Default"]),
+        Identity::new(&*CORE_CRATE, &["cmp", "Eq"]),
+        Identity::new(&*CORE_CRATE, &["cmp", "Ord"]),
+        Identity::new(&*CORE_CRATE, &["hash", "Hash"]),
+    ];
+    /// `core` traits with a type parameter that defaults to `Self`, `trait PartialEq<Rhs = Self>`.
+    static ref DEFAULTS_TO_SELF: Vec<Identity> = vec![
+        Identity::new(&*CORE_CRATE, &["cmp", "PartialEq"]),
+        Identity::new(&*CORE_CRATE, &["cmp", "PartialOrd"]),
+    ];
+}
+
+/// Why a trait can't be made into a trait object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraitViolation {
+    /// `trait Thing: Sized`, or `where Self: Sized` on the trait.
+    SizedSelf,
+    /// A supertrait takes `Self` as an argument, `trait Thing: PartialEq<Self>`.
+    SelfInSupertrait(Identity),
+    /// A supertrait isn't object safe.
+    Supertrait(Identity),
+    /// An associated const, `const N: usize;`.
+    AssociatedConst(Ident),
+    /// A generic associated type, `type Item<'a>;`.
+    GenericAssociatedType(Ident),
+}
+
+/// Why a method can't be called through a trait object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MethodViolation {
+    /// No receiver, `fn new() -> u8`.
+    NoReceiver,
+    /// A receiver that can't be dispatched on, `self: Wrapper<Self>`.
+    /// `self`, `&self`, `&mut self`, `Box<Self>`, `Rc<Self>`, `Arc<Self>` and `Pin`s of those can.
+    UndispatchableReceiver,
+    /// Type or const parameters, `fn map<T>(&self, t: T)`. Includes `impl Trait` arguments.
+    Generic,
+    /// `Self` in an argument, `fn eq(&self, other: &Self)`.
+    SelfArgument,
+    /// `Self` in the return type, `fn duplicate(&self) -> Self`.
+    SelfReturn,
+    /// `async fn`, or a returned `impl Trait`.
+    OpaqueReturn,
+}
+
+/// The result of checking a trait for object safety.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectSafety {
+    /// Violations by the trait as a whole.
+    pub trait_violations: Vec<TraitViolation>,
+    /// Methods that can't be dispatched, in declaration order, with every rule they break.
+    /// Methods exempted by `where Self: Sized` aren't included.
+    pub method_violations: Vec<(Ident, Vec<MethodViolation>)>,
+}
+impl ObjectSafety {
+    /// If `dyn Trait` is legal.
+    pub fn is_object_safe(&self) -> bool {
+        self.trait_violations.is_empty() && self.method_violations.is_empty()
+    }
+}
+
+/// Check whether the trait `id` can be made into a trait object.
+pub fn object_safety(db: &Db, id: &Identity, trait_: &TraitItem) -> ObjectSafety {
+    check_trait(db, id, trait_, &mut vec![])
+}
+
+fn check_trait(
+    db: &Db,
+    id: &Identity,
+    trait_: &TraitItem,
+    visiting: &mut Vec<Identity>,
+) -> ObjectSafety {
+    visiting.push(id.clone());
+    let self_ = id.clone_join(&*SELF_TYPE);
+    let mut result = ObjectSafety::default();
+
+    for supertrait in &trait_.supertraits {
+        if supertrait.is_maybe {
+            continue;
+        }
+        if supertrait.id == *SIZED {
+            result.trait_violations.push(TraitViolation::SizedSelf);
+            continue;
+        }
+        if let Some(violation) = check_supertrait(db, supertrait, &self_, visiting) {
+            result.trait_violations.push(violation);
+        }
+    }
+    for const_ in &trait_.consts {
+        result
+            .trait_violations
+            .push(TraitViolation::AssociatedConst(
+                const_.metadata.name.clone(),
+            ));
+    }
+    for type_ in &trait_.types {
+        if !type_.generics.is_empty() {
+            result
+                .trait_violations
+                .push(TraitViolation::GenericAssociatedType(
+                    type_.metadata.name.clone(),
+                ));
+        }
+    }
+    for method in &trait_.methods {
+        if requires_sized(&method.signature.generics, &self_) {
+            continue;
+        }
+        let violations = check_method(method, &self_);
+        if !violations.is_empty() {
+            result
+                .method_violations
+                .push((method.metadata.name.clone(), violations));
+        }
+    }
+
+    visiting.pop();
+    result
+}
+
+/// Check a supertrait, `self_` being the subtrait's `Self`.
+fn check_supertrait(
+    db: &Db,
+    supertrait: &TraitId,
+    self_: &Identity,
+    visiting: &mut Vec<Identity>,
+) -> Option<TraitViolation> {
+    let id = &supertrait.id;
+    let params = &supertrait.params;
+    if params
+        .type_bindings
+        .values()
+        .any(|arg| mentions_self(arg, self_))
+    {
+        return Some(TraitViolation::SelfInSupertrait(id.clone()));
+    }
+    match db.get_item::<TypeItem>(id) {
+        Some(TypeItem::Trait(inner)) => {
+            // an omitted parameter defaulting to the supertrait's `Self` is our `Self`
+            let inner_self = id.clone_join(&*SELF_TYPE);
+            let defaults_to_self = inner.generics.types.iter().enumerate().any(|(i, param)| {
+                !params.type_bindings.contains_key(&param.metadata.name)
+                    && !params.type_bindings.contains_key(&Ident::positional(i))
+                    && param
+                        .default
+                        .as_ref()
+                        .map_or(false, |default| mentions_self(default, &inner_self))
+            });
+            if defaults_to_self {
+                Some(TraitViolation::SelfInSupertrait(id.clone()))
+            } else if visiting.contains(id) {
+                // a cycle, which rustc rejects anyway
+                None
+            } else if !check_trait(db, id, inner, visiting).is_object_safe() {
+                Some(TraitViolation::Supertrait(id.clone()))
+            } else {
+                None
+            }
+        }
+        _ => {
+            if DEFAULTS_TO_SELF.contains(id) && params.type_bindings.is_empty() {
+                Some(TraitViolation::SelfInSupertrait(id.clone()))
+            } else if NOT_OBJECT_SAFE.contains(id) {
+                Some(TraitViolation::Supertrait(id.clone()))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Every rule a method breaks.
+fn check_method(method: &TraitMethod, self_: &Identity) -> Vec<MethodViolation> {
+    let signature = &method.signature;
+    let mut violations = vec![];
+
+    match &signature.receiver {
+        Receiver::None => violations.push(MethodViolation::NoReceiver),
+        Receiver::ConsumeSelf | Receiver::RefSelf { .. } => (),
+        Receiver::Other(type_) => {
+            if !is_self(type_, self_) && !is_dispatchable(type_, self_) {
+                violations.push(MethodViolation::UndispatchableReceiver);
+            }
+        }
+    }
+    if !signature.generics.types.is_empty() || !signature.generics.consts.is_empty() {
+        violations.push(MethodViolation::Generic);
+    }
+    if signature
+        .args
+        .iter()
+        .any(|arg| mentions_self(&arg.type_, self_))
+    {
+        violations.push(MethodViolation::SelfArgument);
+    }
+    if mentions_self(&signature.ret, self_) {
+        violations.push(MethodViolation::SelfReturn);
+    }
+    if signature.is_async || contains_impl_trait(&signature.ret) {
+        violations.push(MethodViolation::OpaqueReturn);
+    }
+    violations
+}
+
+/// If `generics` contain `where Self: Sized`.
+fn requires_sized(generics: &Generics, self_: &Identity) -> bool {
+    generics
+        .where_predicates
+        .iter()
+        .any(|predicate| match predicate {
+            WherePredicate::Type(predicate) => {
+                is_self(&predicate.type_, self_)
+                    && predicate
+                        .trait_bounds
+                        .iter()
+                        .any(|bound| !bound.is_maybe && bound.id == *SIZED)
+            }
+            WherePredicate::Lifetime(_) => false,
+        })
+}
+
+/// If a type is exactly `Self`.
+fn is_self(type_: &TypeId, self_: &Identity) -> bool {
+    match type_ {
+        TypeId::Path(PathType { path, params }) => path == self_ && params.is_empty(),
+        _ => false,
+    }
+}
+
+/// If a receiver type is a pointer to `Self` that a trait object can be called through.
+fn is_dispatchable(type_: &TypeId, self_: &Identity) -> bool {
+    match type_ {
+        TypeId::Reference(ReferenceType { type_, .. }) => is_self(type_, self_),
+        TypeId::Path(PathType { path, params }) => {
+            let arg = match params.type_bindings.get(&Ident::positional(0)) {
+                Some(arg) if params.type_bindings.len() == 1 => arg,
+                _ => return false,
+            };
+            if path == &*BOX || path == &*RC || path == &*ARC {
+                is_self(arg, self_)
+            } else if path == &*PIN {
+                is_dispatchable(arg, self_)
+            } else {
+                false
+            }
+        }
+        _ => false,
+    }
+}
+
+/// If `Self` appears anywhere in a type, other than as the base of a projection, `Self::Item`.
+fn mentions_self(type_: &TypeId, self_: &Identity) -> bool {
+    match type_ {
+        TypeId::Path(PathType { path, .. }) if path == self_ => true,
+        TypeId::QSelf(QSelfType {
+            self_: projected,
+            trait_,
+            ..
+        }) if is_self(projected, self_) => trait_
+            .params
+            .type_bindings
+            .values()
+            .any(|arg| mentions_self(arg, self_)),
+        _ => any_child(type_, &mut |child| mentions_self(child, self_)),
+    }
+}
+
+/// If a type contains `impl Trait`.
+fn contains_impl_trait(type_: &TypeId) -> bool {
+    match type_ {
+        TypeId::ImplTrait(_) => true,
+        _ => any_child(type_, &mut contains_impl_trait),
+    }
+}
+
+/// If `f` is true of any type directly contained in `type_`.
+fn any_child(type_: &TypeId, f: &mut dyn FnMut(&TypeId) -> bool) -> bool {
+    let mut found = false;
+    type_.map_children(
+        &mut |child| {
+            found = found || f(child);
+            child.clone()
+        },
+        &mut |lifetime| lifetime.clone(),
+        &mut |const_| const_.clone(),
+    );
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items;
+
+    #[test]
+    fn object_safety_rules() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub struct Wrapper<T>(T);
+            pub trait Safe {
+                type Item;
+                fn get(&self) -> Option<Self::Item>;
+                fn set(&mut self, item: Self::Item);
+                fn consume(self);
+                fn boxed(self: Box<Self>);
+                fn make() -> Self where Self: Sized;
+                fn map<T>(&self, t: T) where Self: Sized;
+                fn compare(&self, other: &dyn Safe<Item = u8>) -> bool;
+            }
+            pub trait Methods {
+                fn new() -> u8;
+                fn wrapped(self: Wrapper<Self>);
+                fn generic<T>(&self, t: T);
+                fn args(&self, other: &Self);
+                fn duplicate(&self) -> Self;
+                fn both(&self, other: Vec<Self>) -> Option<Self>;
+                fn opaque(&self) -> impl Iterator<Item = u8>;
+                async fn later(&self);
+            }
+            pub trait Consts {
+                const N: usize;
+            }
+            pub trait Gat {
+                type Item<'a>;
+            }
+            pub trait Sizes: Sized {}
+            pub trait WhereSized where Self: Sized {}
+            pub trait SelfArg: AsRef<Self> {}
+            pub trait Compare: PartialEq {}
+            pub trait Derived: Consts {}
+            pub trait Fine: Safe + Send {}
+            pub trait Duplicate: Clone {}
+        };
+        let root = walk_test_items(&db, &items);
+
+        let check = |name: &str| {
+            let id = root.clone_join(name);
+            match db.get_item::<TypeItem>(&id) {
+                Some(TypeItem::Trait(trait_)) => object_safety(&db, &id, trait_),
+                _ => panic!("no trait {}", name),
+            }
+        };
+        let trait_violations = |name: &str| check(name).trait_violations;
+
+        let safe = check("Safe");
+        assert!(safe.is_object_safe(), "{:?}", safe);
+        assert!(check("Fine").is_object_safe());
+
+        let methods = check("Methods");
+        assert!(!methods.is_object_safe());
+        assert!(methods.trait_violations.is_empty());
+        let method = |name: &str| {
+            methods
+                .method_violations
+                .iter()
+                .find(|(method, _)| method == &Ident::from(name))
+                .map(|(_, violations)| violations.clone())
+                .unwrap_or_default()
+        };
+        use MethodViolation::*;
+        assert_eq!(method("new"), vec![NoReceiver]);
+        assert_eq!(method("wrapped"), vec![UndispatchableReceiver]);
+        assert_eq!(method("generic"), vec![Generic]);
+        assert_eq!(method("args"), vec![SelfArgument]);
+        assert_eq!(method("duplicate"), vec![SelfReturn]);
+        assert_eq!(method("both"), vec![SelfArgument, SelfReturn]);
+        assert_eq!(method("opaque"), vec![OpaqueReturn]);
+        assert_eq!(method("later"), vec![OpaqueReturn]);
+        assert_eq!(methods.method_violations.len(), 8);
+
+        use TraitViolation::*;
+        assert_eq!(
+            trait_violations("Consts"),
+            vec![AssociatedConst("N".into())]
+        );
+        assert_eq!(
+            trait_violations("Gat"),
+            vec![GenericAssociatedType("Item".into())]
+        );
+        assert_eq!(trait_violations("Sizes"), vec![SizedSelf]);
+        assert_eq!(trait_violations("WhereSized"), vec![SizedSelf]);
+        assert_match!(&trait_violations("SelfArg")[..], [SelfInSupertrait(_)]);
+        assert_match!(&trait_violations("Compare")[..], [SelfInSupertrait(_)]);
+        assert_eq!(
+            trait_violations("Derived"),
+            vec![Supertrait(root.clone_join("Consts"))]
+        );
+        assert_match!(&trait_violations("Duplicate")[..], [Supertrait(_)]);
+    }
+}
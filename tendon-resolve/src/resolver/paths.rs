@@ -0,0 +1,253 @@
+//! Type and trait paths.
+//!
+//! Lowering leaves every path as an `Identity::unresolved` placeholder; this pass looks each one
+//! up from the module its item was declared in. Type parameters resolve to `{owner}::{param}`.
+//! Paths that can't be found are left alone and recorded in the crate's `ResolutionReport`.
+
+use super::fold::{fold_trait_children, fold_type_children, Folder, GenericsScope};
+use crate::walker::helpers::try_to_resolve;
+use lazy_static::lazy_static;
+use tendon_api::{
+    database::{Crate, Db, UnresolvedReference},
+    generics::Generics,
+    identities::{Identity, PathType, TraitId, TypeId},
+    paths::{Ident, UnresolvedPath},
+    scopes::NamespaceId,
+};
+
+lazy_static! {
+    static ref SELF_TYPE: Ident = "Self".into();
+}
+
+/// Resolves the paths to types and traits in items.
+pub(crate) struct PathResolver<'a> {
+    db: &'a Db,
+    crate_: &'a Crate,
+    scope: Identity,
+    item: Identity,
+    generics: Vec<GenericsScope>,
+    /// Paths that failed to resolve.
+    pub(crate) unresolved: Vec<UnresolvedReference>,
+}
+
+impl<'a> PathResolver<'a> {
+    pub(crate) fn new(db: &'a Db, crate_: &'a Crate) -> Self {
+        PathResolver {
+            db,
+            crate_,
+            scope: crate_.id.root(),
+            item: crate_.id.root(),
+            generics: vec![],
+            unresolved: vec![],
+        }
+    }
+
+    /// The generic type parameter named `name`, if there is one in scope.
+    fn type_param(&self, name: &Ident) -> Option<Identity> {
+        // innermost generics shadow outer ones
+        self.generics
+            .iter()
+            .rev()
+            .find(|scope| scope.types.contains(name))
+            .map(|scope| scope.owner.clone_join(name.clone()))
+    }
+
+    /// Resolve a placeholder identity. Real identities are returned as-is.
+    fn resolve(&mut self, namespace: NamespaceId, id: &Identity) -> Identity {
+        let path = if let Some(path) = id.as_unresolved() {
+            path
+        } else {
+            return id.clone();
+        };
+
+        if !path.rooted {
+            let first = &path.path[0];
+            if path.path.len() == 1 && namespace == NamespaceId::Type {
+                if let Some(param) = self.type_param(first) {
+                    return param;
+                }
+            }
+            // `Self` and associated types (`T::Item`) aren't paths to items;
+            // they're left for later passes.
+            if first == &*SELF_TYPE || (path.path.len() > 1 && self.type_param(first).is_some()) {
+                return id.clone();
+            }
+        }
+
+        match try_to_resolve(self.db, self.crate_, &self.scope, namespace, &path) {
+            Ok(resolved) => resolved,
+            Err(_) => {
+                self.record(namespace, path);
+                id.clone()
+            }
+        }
+    }
+
+    fn record(&mut self, namespace: NamespaceId, path: UnresolvedPath) {
+        let reference = UnresolvedReference {
+            item: self.item.clone(),
+            namespace,
+            path,
+        };
+        if !self.unresolved.contains(&reference) {
+            self.unresolved.push(reference);
+        }
+    }
+}
+
+impl<'a> Folder for PathResolver<'a> {
+    fn enter_item(&mut self, scope: &Identity, item: &Identity) {
+        self.scope = scope.clone();
+        self.item = item.clone();
+        self.generics.clear();
+    }
+
+    fn enter_generics(&mut self, owner: &Identity, generics: &Generics) {
+        self.generics.push(GenericsScope::new(owner, generics));
+    }
+
+    fn exit_generics(&mut self) {
+        self.generics.pop();
+    }
+
+    fn fold_type(&mut self, type_: &TypeId) -> TypeId {
+        if let TypeId::Path(PathType { path, params }) = type_ {
+            TypeId::Path(PathType {
+                path: self.resolve(NamespaceId::Type, path),
+                params: self.fold_params(params),
+            })
+        } else {
+            fold_type_children(self, type_)
+        }
+    }
+
+    fn fold_trait(&mut self, trait_: &TraitId) -> TraitId {
+        let mut result = fold_trait_children(self, trait_);
+        result.id = self.resolve(NamespaceId::Type, &trait_.id);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walker::{walk_test_items, walk_test_items_in};
+    use tendon_api::builtins::{CORE_CRATE, FN};
+    use tendon_api::crates::CrateData;
+    use tendon_api::database::Db;
+    use tendon_api::identities::{PathType, TypeId};
+    use tendon_api::items::{SymbolItem, TypeItem};
+    use tendon_api::paths::UnresolvedPath;
+    use tendon_api::scopes::NamespaceId;
+    use tendon_api::Map;
+
+    #[test]
+    fn path_resolution() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub struct Thing;
+            pub trait Frob<T> {}
+            pub fn f<T: Frob<Thing>>(x: Thing, y: T, z: inner::Other) -> Missing {}
+            impl<U> Frob<U> for Thing {}
+            pub mod inner {
+                pub struct Other(super::Thing);
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let inner = root.clone_join("inner");
+        let thing = root.clone_join("Thing");
+        let frob = root.clone_join("Frob");
+        let other = inner.clone_join("Other");
+        let f = root.clone_join("f");
+        let path_of = |type_: &TypeId| match type_ {
+            TypeId::Path(PathType { path, .. }) => path.clone(),
+            _ => panic!("not a path: {:?}", type_),
+        };
+
+        let crate_ = db.get_crate(&root.crate_);
+
+        // the item survives, even though part of it didn't resolve
+        if let Some(SymbolItem::Function(function)) = crate_.get::<SymbolItem>(&f) {
+            let sig = &function.signature;
+            assert_eq!(path_of(&sig.args[0].type_), thing);
+            assert_eq!(path_of(&sig.args[1].type_), f.clone_join("T"));
+            assert_eq!(path_of(&sig.args[2].type_), other);
+            assert!(path_of(&sig.ret).as_unresolved().is_some());
+
+            let bound = &sig.generics.types[0].trait_bounds[0];
+            assert_eq!(bound.id, frob);
+            assert_eq!(
+                path_of(bound.params.type_bindings.values().next().unwrap()),
+                thing
+            );
+        } else {
+            panic!("no function f");
+        }
+
+        if let Some(TypeItem::Struct(struct_)) = crate_.get::<TypeItem>(&other) {
+            assert_eq!(path_of(&struct_.fields[0].type_), thing);
+        } else {
+            panic!("no struct Other");
+        }
+
+        let impl_ = &crate_.impls[0];
+        assert_eq!(path_of(&impl_.self_type), thing);
+        let trait_ = impl_.trait_.as_ref().unwrap();
+        assert_eq!(trait_.id, frob);
+        assert_eq!(
+            path_of(trait_.params.type_bindings.values().next().unwrap()),
+            root.clone_join("{impl_0}").clone_join("U")
+        );
+
+        let report = &crate_.resolution_report.unresolved;
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].item, f);
+        assert_eq!(report[0].namespace, NamespaceId::Type);
+        assert_eq!(report[0].path, UnresolvedPath::fake("Missing"));
+    }
+
+    #[test]
+    fn fn_sugar_resolution() {
+        spoor::init();
+        // lower a fake `core`, so `ops::function::Fn` is the real thing
+        let core = (*CORE_CRATE).clone();
+        let mut crates = Map::default();
+        crates.insert(core.clone(), CrateData::fake(core.clone()));
+        let db = Db::new(crates);
+
+        let items: syn::File = syn::parse_quote! {
+            pub mod ops {
+                pub mod function {
+                    pub trait Fn<Args> {
+                        type Output;
+                    }
+                }
+            }
+            pub struct Event;
+            pub struct Reply;
+            pub fn on<F: ops::function::Fn(Event) -> Reply>(f: F) {}
+        };
+        let root = walk_test_items_in(&db, &core, &items);
+
+        let crate_ = db.get_crate(&root.crate_);
+        let path_of = |type_: &TypeId| match type_ {
+            TypeId::Path(PathType { path, .. }) => path.clone(),
+            _ => panic!("not a path: {:?}", type_),
+        };
+
+        if let Some(SymbolItem::Function(function)) =
+            crate_.get::<SymbolItem>(&root.clone_join("on"))
+        {
+            let bound = &function.signature.generics.types[0].trait_bounds[0];
+            assert_eq!(bound.id, *FN);
+            let (inputs, output) = bound.as_fn_sugar().unwrap();
+            assert_eq!(path_of(&inputs[0]), root.clone_join("Event"));
+            assert_eq!(path_of(output), root.clone_join("Reply"));
+        } else {
+            panic!("no function on");
+        }
+        assert!(crate_.resolution_report.unresolved.is_empty());
+    }
+}
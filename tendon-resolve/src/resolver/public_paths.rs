@@ -0,0 +1,109 @@
+//! Public paths: how users of a crate name its items.
+//!
+//! Items are often defined deep in private modules and re-exported somewhere shorter,
+//! `rand_chacha::chacha::ChaChaRng` is imported as `rand_chacha::ChaChaRng`. We walk public
+//! bindings breadth-first from the crate root, so the first path found to an item is the shortest.
+//! Ties are broken alphabetically.
+
+use std::collections::VecDeque;
+use tendon_api::{
+    attributes::Visibility,
+    database::Crate,
+    identities::Identity,
+    scopes::{NamespaceId, Scope},
+    Map,
+};
+
+/// Find the shortest public path to every item in `crate_` that has one.
+pub(crate) fn public_paths(crate_: &Crate) -> Map<Identity, Identity> {
+    let mut result = Map::default();
+    let root = Identity::root(&crate_.id);
+    result.insert(root.clone(), root.clone());
+
+    // (scope, the public path to it)
+    let mut queue = VecDeque::new();
+    queue.push_back((root.clone(), root));
+
+    while let Some((scope_id, public)) = queue.pop_front() {
+        let scope = match crate_.get::<Scope>(&scope_id) {
+            Some(scope) => scope,
+            None => continue,
+        };
+        for &namespace_id in &NamespaceId::values() {
+            let mut bindings = scope
+                .iter_by(namespace_id)
+                .filter(|(_, binding)| binding.visibility == Visibility::Pub)
+                .filter(|(_, binding)| binding.identity.crate_ == crate_.id)
+                .collect::<Vec<_>>();
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (name, binding) in bindings {
+                if result.contains_key(&binding.identity) {
+                    continue;
+                }
+                let path = public.clone_join(name.clone());
+                result.insert(binding.identity.clone(), path.clone());
+                if namespace_id == NamespaceId::Scope {
+                    queue.push_back((binding.identity.clone(), path));
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walker::walk_test_items;
+    use tendon_api::database::Db;
+    use tendon_api::identities::Identity;
+
+    #[test]
+    fn shortest_public_paths() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            mod inner {
+                pub mod deep {
+                    pub struct Rng;
+                    pub struct Hidden;
+                    pub fn seed() {}
+                }
+                pub(crate) struct Private;
+            }
+            pub mod api {
+                pub use crate::inner::deep::{Rng as Renamed, Hidden, seed};
+            }
+            pub mod a {
+                pub mod b {
+                    pub struct Long;
+                }
+            }
+            pub use inner::deep::Rng;
+            use inner::deep::Hidden as Quiet;
+            pub use a::b as shortcut;
+        };
+        let root = walk_test_items(&db, &items);
+
+        let id = |path: &[&str]| Identity::new(&root.crate_, path);
+        let public = |path: &[&str]| db.public_path(&id(path)).cloned();
+
+        assert_eq!(public(&[]), Some(id(&[])));
+        assert_eq!(public(&["inner", "deep", "Rng"]), Some(id(&["Rng"])));
+        assert_eq!(
+            public(&["inner", "deep", "Hidden"]),
+            Some(id(&["api", "Hidden"]))
+        );
+        assert_eq!(
+            public(&["inner", "deep", "seed"]),
+            Some(id(&["api", "seed"]))
+        );
+        assert_eq!(public(&["a"]), Some(id(&["a"])));
+        assert_eq!(public(&["a", "b"]), Some(id(&["shortcut"])));
+        assert_eq!(public(&["a", "b", "Long"]), Some(id(&["shortcut", "Long"])));
+        assert_eq!(public(&["inner"]), None);
+        assert_eq!(public(&["inner", "deep"]), None);
+        assert_eq!(public(&["inner", "Private"]), None);
+    }
+}
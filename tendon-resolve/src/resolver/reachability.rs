@@ -0,0 +1,232 @@
+//! Public API reachability: which items users of a crate can get at.
+//!
+//! Items with a public path (see `Crate::public_paths`) are reachable. From those, we follow the
+//! public surface of each item: `pub` fields, signatures, bounds, and the impls of exposed
+//! types. Anything found that way without a public path can still be used, it just can't be named:
+//! a `pub struct` in a private module returned from a public function, say.
+//!
+//! A non-`pub` item found in the public surface is a private-in-public leak, which rustc warns
+//! about or rejects; those are reported separately.
+
+use tendon_api::{
+    attributes::{HasMetadata, Visibility},
+    database::Db,
+    identities::{Identity, PathType, TypeId},
+    items::{SymbolItem, TypeItem},
+    references::{impl_references, symbol_item_references, type_item_references, Surface},
+    Map, Set,
+};
+
+/// How users of a crate can get at an item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reachability {
+    /// Importable through a public path.
+    Reachable,
+    /// Nameable but not reachable by path: it appears in the public surface of a reachable item,
+    /// but can't be imported.
+    Exposed,
+    /// Not part of the public API.
+    Unreachable,
+}
+
+/// A non-`pub` item that appears in the public surface of another item.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PrivateLeak {
+    /// The leaked item.
+    pub item: Identity,
+    /// The item whose public surface mentions it.
+    pub through: Identity,
+}
+
+/// The reachability of every item in a `Db`.
+#[derive(Debug, Default)]
+pub struct ReachabilityReport {
+    /// Reachable and exposed items. Everything else is unreachable.
+    pub items: Map<Identity, Reachability>,
+    /// Private items leaked through the public API, sorted.
+    pub leaks: Vec<PrivateLeak>,
+}
+impl ReachabilityReport {
+    /// Look up the reachability of an item.
+    pub fn get(&self, item: &Identity) -> Reachability {
+        self.items
+            .get(item)
+            .cloned()
+            .unwrap_or(Reachability::Unreachable)
+    }
+}
+
+/// Find which items in every lowered crate are part of a public API.
+pub fn reachability(db: &Db) -> ReachabilityReport {
+    let mut report = ReachabilityReport::default();
+    let mut leaks = Set::default();
+    let mut queue = vec![];
+
+    for crate_ in db.crates() {
+        for item in crate_.public_paths.keys() {
+            report.items.insert(item.clone(), Reachability::Reachable);
+            queue.push(item.clone());
+        }
+    }
+
+    let impls = db
+        .crates()
+        .flat_map(|crate_| crate_.impls.iter())
+        .collect::<Vec<_>>();
+    let mut impl_done = vec![false; impls.len()];
+
+    loop {
+        while let Some(item) = queue.pop() {
+            let mut references = Set::default();
+            if let Some(type_) = db.get_item::<TypeItem>(&item) {
+                type_item_references(type_, Surface::Public, &mut references);
+            }
+            if let Some(symbol) = db.get_item::<SymbolItem>(&item) {
+                symbol_item_references(symbol, &mut references);
+            }
+            expose(db, &item, references, &mut report, &mut leaks, &mut queue);
+        }
+
+        // the impls of exposed types are exposed too, if their traits are
+        let mut progress = false;
+        for (impl_, done) in impls.iter().zip(impl_done.iter_mut()) {
+            if *done || !is_exposed(db, &report, &impl_.self_type) {
+                continue;
+            }
+            let trait_exposed = impl_.trait_.as_ref().map_or(true, |trait_| {
+                report.items.contains_key(&trait_.id)
+                    || db.get_item::<TypeItem>(&trait_.id).is_none()
+            });
+            if !trait_exposed {
+                continue;
+            }
+            *done = true;
+            progress = true;
+
+            let mut references = Set::default();
+            impl_references(impl_, Surface::Public, &mut references);
+            let owner = impl_.scope.clone_join(&impl_.metadata.name);
+            expose(db, &owner, references, &mut report, &mut leaks, &mut queue);
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    report.leaks = leaks.into_iter().collect();
+    report.leaks.sort();
+    report
+}
+
+/// Mark the items `through` refers to as exposed, if they aren't already reachable.
+fn expose(
+    db: &Db,
+    through: &Identity,
+    references: Set<Identity>,
+    report: &mut ReachabilityReport,
+    leaks: &mut Set<PrivateLeak>,
+    queue: &mut Vec<Identity>,
+) {
+    for item in references {
+        if &item == through {
+            continue;
+        }
+        let visibility = match visibility(db, &item) {
+            Some(visibility) => visibility,
+            // not an item: a generic parameter, a builtin, or in a crate that isn't lowered
+            None => continue,
+        };
+        if visibility != Visibility::Pub {
+            leaks.insert(PrivateLeak {
+                item: item.clone(),
+                through: through.clone(),
+            });
+        }
+        if !report.items.contains_key(&item) {
+            report.items.insert(item.clone(), Reachability::Exposed);
+            queue.push(item);
+        }
+    }
+}
+
+/// The visibility of a type or symbol item.
+fn visibility(db: &Db, item: &Identity) -> Option<Visibility> {
+    if let Some(type_) = db.get_item::<TypeItem>(item) {
+        match type_ {
+            TypeItem::TypeParam(_) | TypeItem::LifetimeParam(_) => None,
+            _ => Some(type_.metadata().visibility.clone()),
+        }
+    } else if let Some(symbol) = db.get_item::<SymbolItem>(item) {
+        match symbol {
+            SymbolItem::ConstParam(_) => None,
+            _ => Some(symbol.metadata().visibility.clone()),
+        }
+    } else {
+        None
+    }
+}
+
+/// If the type an impl is for is part of the public API.
+/// Impls for types that aren't items (`impl Trait for &u8`) count, as long as the trait does.
+fn is_exposed(db: &Db, report: &ReachabilityReport, self_type: &TypeId) -> bool {
+    match self_type {
+        TypeId::Path(PathType { path, .. }) => {
+            report.items.contains_key(path) || visibility(db, path).is_none()
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::walk_test_items;
+
+    #[test]
+    fn public_api_reachability() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub fn make() -> inner::Token {}
+            pub(crate) fn helper() -> inner::Unused {}
+            pub struct Api;
+            impl Api {
+                pub fn other(&self) -> inner::Other {}
+                fn private(&self) -> inner::Unused {}
+            }
+            mod inner {
+                pub struct Token {
+                    pub secret: Secret,
+                    hidden: Hidden,
+                }
+                pub(crate) struct Secret;
+                pub struct Hidden;
+                pub struct Other;
+                pub struct Unused;
+            }
+        };
+        let root = walk_test_items(&db, &items);
+        let inner = root.clone_join("inner");
+
+        let report = reachability(&db);
+        let get = |path: &[&str]| report.get(&Identity::new(&root.crate_, path));
+
+        assert_eq!(get(&["make"]), Reachability::Reachable);
+        assert_eq!(get(&["Api"]), Reachability::Reachable);
+        assert_eq!(get(&["helper"]), Reachability::Unreachable);
+        assert_eq!(get(&["inner", "Token"]), Reachability::Exposed);
+        assert_eq!(get(&["inner", "Secret"]), Reachability::Exposed);
+        assert_eq!(get(&["inner", "Other"]), Reachability::Exposed);
+        assert_eq!(get(&["inner", "Hidden"]), Reachability::Unreachable);
+        assert_eq!(get(&["inner", "Unused"]), Reachability::Unreachable);
+
+        assert_eq!(
+            report.leaks,
+            vec![PrivateLeak {
+                item: inner.clone_join("Secret"),
+                through: inner.clone_join("Token"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! `Self`.
+//!
+//! In a struct, enum or union, `Self` is the type itself; in an impl, it's the impl's self type;
+//! in a trait, it's the trait's implicit `Self` parameter. `Self::Assoc` becomes a projection
+//! through the trait, `<Self as Trait>::Assoc`, or through the supertrait that declares `Assoc`.
+//! This runs after path resolution, so the types it substitutes in are already resolved.
+
+use super::fold::{fold_type_children, Folder, Items, SelfContext};
+use lazy_static::lazy_static;
+use std::collections::VecDeque;
+use tendon_api::{
+    database::{Db, UnresolvedReference},
+    generics::Generics,
+    identities::{Identity, PathType, QSelfType, Substitutions, TraitId, TypeId},
+    items::{TraitItem, TypeItem},
+    paths::{Ident, UnresolvedPath},
+    scopes::NamespaceId,
+    Map,
+};
+
+lazy_static! {
+    static ref SELF_TYPE: Ident = "Self".into();
+}
+
+/// What `Self::Assoc` needs to know about a trait.
+struct TraitSummary {
+    generics: Generics,
+    types: Vec<Ident>,
+    supertraits: Vec<TraitId>,
+}
+impl TraitSummary {
+    fn new(trait_: &TraitItem) -> TraitSummary {
+        TraitSummary {
+            generics: trait_.generics.clone(),
+            types: trait_
+                .types
+                .iter()
+                .map(|t| t.metadata.name.clone())
+                .collect(),
+            supertraits: trait_.supertraits.clone(),
+        }
+    }
+}
+
+/// Replaces `Self` with what it stands for.
+pub(crate) struct SelfResolver<'a> {
+    db: &'a Db,
+    /// The traits of the crate being resolved, which are out of the crate while it's folded.
+    local_traits: Map<Identity, TraitSummary>,
+    item: Option<Identity>,
+    context: Option<SelfContext>,
+    /// `Self` paths that didn't make sense where they were used.
+    pub(crate) unresolved: Vec<UnresolvedReference>,
+}
+
+impl<'a> SelfResolver<'a> {
+    pub(crate) fn new(db: &'a Db, items: &Items) -> Self {
+        SelfResolver {
+            db,
+            local_traits: items
+                .types()
+                .filter_map(|(id, item)| match item {
+                    TypeItem::Trait(trait_) => Some((id, TraitSummary::new(trait_))),
+                    _ => None,
+                })
+                .collect(),
+            item: None,
+            context: None,
+            unresolved: vec![],
+        }
+    }
+
+    /// Substitute a `Self` or `Self::Assoc` path; `None` if this isn't one, or if it can't be
+    /// substituted.
+    fn substitute(&mut self, path: &UnresolvedPath) -> Option<TypeId> {
+        if path.rooted || path.path[0] != *SELF_TYPE {
+            return None;
+        }
+        let result = match (&self.context, &path.path[1..]) {
+            (Some(context), []) => Some(context.type_.clone()),
+            (Some(context), [assoc]) => {
+                // in a trait, only associated types of the trait and its supertraits are known to
+                // go through it; in a trait impl, all of them do.
+                let trait_ = match &context.trait_ {
+                    Some(trait_) if context.types.contains(assoc) || context.is_impl => {
+                        Some(trait_.clone())
+                    }
+                    Some(_) => self.find_in_supertraits(context, assoc),
+                    None => None,
+                };
+                trait_.map(|trait_| {
+                    TypeId::QSelf(QSelfType {
+                        self_: Box::new(context.type_.clone()),
+                        trait_,
+                        output_: assoc.clone(),
+                    })
+                })
+            }
+            _ => None,
+        };
+        if result.is_none() {
+            let reference = UnresolvedReference {
+                item: self.item.clone().expect("no item entered"),
+                namespace: NamespaceId::Type,
+                path: path.clone(),
+            };
+            if !self.unresolved.contains(&reference) {
+                self.unresolved.push(reference);
+            }
+        }
+        result
+    }
+
+    /// The supertrait bound that declares the associated type `assoc`, with its arguments in terms
+    /// of the current trait. Supertraits of supertraits are searched too, nearest first.
+    /// `None` if no supertrait declares `assoc`, or if more than one does.
+    fn find_in_supertraits(&self, context: &SelfContext, assoc: &Ident) -> Option<TraitId> {
+        let mut bounds: VecDeque<TraitId> = context.supertraits.iter().cloned().collect();
+        let mut expanded = vec![];
+        let mut found: Vec<TraitId> = vec![];
+
+        while let Some(bound) = bounds.pop_front() {
+            let local;
+            let summary = match self.local_traits.get(&bound.id) {
+                Some(summary) => summary,
+                None => match self.db.get_item::<TypeItem>(&bound.id) {
+                    Some(TypeItem::Trait(trait_)) => {
+                        local = TraitSummary::new(trait_);
+                        &local
+                    }
+                    _ => continue,
+                },
+            };
+            if summary.types.contains(assoc) {
+                if !found.contains(&bound) {
+                    found.push(bound);
+                }
+                continue;
+            }
+            if expanded.contains(&bound.id) {
+                continue;
+            }
+            expanded.push(bound.id.clone());
+
+            let mut substitutions =
+                Substitutions::for_generics(&bound.id, &summary.generics, &bound.params);
+            substitutions
+                .types
+                .insert(bound.id.clone_join(&*SELF_TYPE), context.type_.clone());
+            for supertrait in &summary.supertraits {
+                bounds.push_back(supertrait.map_children(
+                    &mut |type_| type_.substitute(&substitutions),
+                    &mut |lifetime| substitutions.substitute_lifetime(lifetime),
+                    &mut |const_| substitutions.substitute_const(const_),
+                ));
+            }
+        }
+
+        if found.len() == 1 {
+            found.pop()
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Folder for SelfResolver<'a> {
+    fn enter_item(&mut self, _scope: &Identity, item: &Identity) {
+        self.item = Some(item.clone());
+        self.context = None;
+    }
+
+    fn enter_self(&mut self, context: &SelfContext) {
+        self.context = Some(context.clone());
+    }
+
+    fn fold_type(&mut self, type_: &TypeId) -> TypeId {
+        if let TypeId::Path(PathType { path, params }) = type_ {
+            if let Some(path) = path.as_unresolved() {
+                if params.is_empty() {
+                    if let Some(result) = self.substitute(&path) {
+                        return result;
+                    }
+                }
+            }
+        }
+        fold_type_children(self, type_)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walker::walk_test_items;
+    use tendon_api::database::Db;
+    use tendon_api::identities::{GenericParams, PathType, QSelfType, TypeId};
+    use tendon_api::items::TypeItem;
+    use tendon_api::paths::{Ident, UnresolvedPath};
+
+    #[test]
+    fn self_resolution() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub struct Wrapper<T> {
+                next: *const Self,
+                x: T,
+            }
+            pub trait Container {
+                type Item;
+                fn get(&self) -> Self::Item;
+                fn make() -> Self;
+                fn bad() -> Self::Nope;
+            }
+            impl<T> Container for Wrapper<T> {
+                type Item = T;
+                fn get(&self) -> Self::Item {}
+                fn make() -> Self {}
+                fn bad() -> Self::Nope {}
+            }
+            impl<T> Wrapper<T> {
+                pub fn new(x: T) -> Self {}
+                pub fn what() -> Self::Huh {}
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let crate_ = db.get_crate(&root.crate_);
+        let wrapper = root.clone_join("Wrapper");
+        let container = root.clone_join("Container");
+        // each impl has its own `T`
+        let trait_impl_id = root.clone_join("{impl_0}");
+        let inherent_impl_id = root.clone_join("{impl_1}");
+
+        let wrapper_t = |owner: &tendon_api::identities::Identity| {
+            let mut params = GenericParams::empty();
+            params.type_bindings.insert(
+                Ident::positional(0),
+                TypeId::Path(PathType {
+                    path: owner.clone_join("T"),
+                    params: GenericParams::empty(),
+                }),
+            );
+            TypeId::Path(PathType {
+                path: wrapper.clone(),
+                params,
+            })
+        };
+
+        // in a type, `Self` is the type with its own parameters
+        if let Some(TypeItem::Struct(struct_)) = crate_.get::<TypeItem>(&wrapper) {
+            assert_match!(&struct_.fields[0].type_, TypeId::Pointer(ptr) => {
+                assert_eq!(&*ptr.type_, &wrapper_t(&wrapper));
+            });
+        } else {
+            panic!("no struct Wrapper");
+        }
+
+        // in a trait, `Self` is the implicit parameter
+        if let Some(TypeItem::Trait(trait_)) = crate_.get::<TypeItem>(&container) {
+            assert_eq!(trait_.self_param.trait_bounds[0].id, container);
+            let self_param = TypeId::Path(PathType {
+                path: container.clone_join("Self"),
+                params: GenericParams::empty(),
+            });
+            assert_match!(&trait_.methods[0].signature.ret, TypeId::QSelf(QSelfType { self_, trait_, output_ }) => {
+                assert_eq!(&**self_, &self_param);
+                assert_eq!(trait_.id, container);
+                assert_eq!(output_, &Ident::from("Item"));
+            });
+            assert_eq!(&trait_.methods[1].signature.ret, &self_param);
+        } else {
+            panic!("no trait Container");
+        }
+
+        // in an impl, `Self` is the self type
+        let trait_impl = &crate_.impls[0];
+        assert_match!(&trait_impl.methods[0].signature.ret, TypeId::QSelf(QSelfType { self_, trait_, .. }) => {
+            assert_eq!(&**self_, &wrapper_t(&trait_impl_id));
+            assert_eq!(trait_.id, container);
+        });
+        assert_eq!(
+            &trait_impl.methods[1].signature.ret,
+            &wrapper_t(&trait_impl_id)
+        );
+        assert_match!(&trait_impl.methods[2].signature.ret, TypeId::QSelf(_));
+        let inherent_impl = &crate_.impls[1];
+        assert_eq!(
+            &inherent_impl.methods[0].signature.ret,
+            &wrapper_t(&inherent_impl_id)
+        );
+
+        let report = &crate_.resolution_report.unresolved;
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].item, container);
+        assert_eq!(report[0].path, UnresolvedPath::fake("Self::Nope"));
+        assert_eq!(report[1].item, inherent_impl_id);
+        assert_eq!(report[1].path, UnresolvedPath::fake("Self::Huh"));
+    }
+
+    #[test]
+    fn supertrait_associated_types() {
+        spoor::init();
+        let db = Db::fake_db();
+
+        let items: syn::File = syn::parse_quote! {
+            pub struct Byte;
+            pub struct Holder<T>(T);
+            pub trait Base<T> {
+                type Out;
+            }
+            pub trait Middle<U>: Base<Holder<U>> {
+                type Mid;
+            }
+            pub trait Leaf: Middle<Byte> {
+                fn out(&self) -> Self::Out;
+                fn mid(&self) -> Self::Mid;
+            }
+            pub trait Other {
+                type Out;
+            }
+            pub trait Both: Base<Byte> + Other {
+                fn out(&self) -> Self::Out;
+            }
+        };
+        let root = walk_test_items(&db, &items);
+
+        let crate_ = db.get_crate(&root.crate_);
+        let leaf = root.clone_join("Leaf");
+        let leaf_self = TypeId::Path(PathType {
+            path: leaf.clone_join("Self"),
+            params: GenericParams::empty(),
+        });
+
+        if let Some(TypeItem::Trait(trait_)) = crate_.get::<TypeItem>(&leaf) {
+            // through two supertraits, with `U` substituted on the way
+            assert_match!(&trait_.methods[0].signature.ret, TypeId::QSelf(QSelfType { self_, trait_, output_ }) => {
+                assert_eq!(&**self_, &leaf_self);
+                assert_eq!(trait_.id, root.clone_join("Base"));
+                assert_match!(&trait_.params.type_bindings[&Ident::positional(0)], TypeId::Path(PathType { path, params }) => {
+                    assert_eq!(path, &root.clone_join("Holder"));
+                    assert_match!(&params.type_bindings[&Ident::positional(0)], TypeId::Path(PathType { path, .. }) => {
+                        assert_eq!(path, &root.clone_join("Byte"));
+                    });
+                });
+                assert_eq!(output_, &Ident::from("Out"));
+            });
+            assert_match!(&trait_.methods[1].signature.ret, TypeId::QSelf(QSelfType { trait_, .. }) => {
+                assert_eq!(trait_.id, root.clone_join("Middle"));
+            });
+        } else {
+            panic!("no trait Leaf");
+        }
+
+        // `Out` is ambiguous in `Both`
+        let report = &crate_.resolution_report.unresolved;
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].item, root.clone_join("Both"));
+        assert_eq!(report[0].path, UnresolvedPath::fake("Self::Out"));
+    }
+}
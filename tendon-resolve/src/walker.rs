@@ -97,7 +97,16 @@
 //! https://internals.rust-lang.org/t/relative-paths-and-rust-2018-use-statements/7875
 //! https://internals.rust-lang.org/t/up-to-date-documentation-on-macro-resolution-order/11877/5
 
+use crate::lower::{
+    imports::lower_use,
+    items::{
+        lower_const, lower_enum, lower_foreign_mod, lower_function_item, lower_impl, lower_static,
+        lower_struct, lower_trait, lower_type_alias, lower_union,
+    },
+    LowerError,
+};
 use hashbrown::hash_map::Entry as HEntry;
+use helpers::try_to_resolve;
 use lazy_static::lazy_static;
 use std::fs::File;
 use std::io::Read;
@@ -107,16 +116,18 @@ use tendon_api::attributes::{Metadata, Span, Visibility};
 use tendon_api::crates::CrateData;
 use tendon_api::database::{Crate, Db, NamespaceLookup};
 use tendon_api::identities::{CrateId, Identity};
-use tendon_api::paths::Ident;
+use tendon_api::items::{SymbolItem, TypeItem};
+use tendon_api::paths::{Ident, UnresolvedPath};
 use tendon_api::scopes::{NamespaceId, Priority, Scope};
 use tendon_api::tokens::Tokens;
 use tendon_api::{Map, Set};
 use textual_scope::TextualScope;
 use tracing::{error, trace, warn};
 
-mod helpers;
+pub(crate) mod helpers;
 mod textual_scope;
 
+#[derive(Clone)]
 pub(crate) struct LocationMetadata<'a> {
     pub(crate) source_file: PathBuf,
     pub(crate) macro_invocation: Option<Arc<Span>>,
@@ -144,6 +155,60 @@ lazy_static! {
     };
 }
 
+/// Walk `items` into the root of `TEST_CRATE_A`, with the std prelude, and complete the crate.
+/// Returns the crate root. Inline modules are walked too; `use` items can import anything above
+/// them.
+#[cfg(test)]
+pub(crate) fn walk_test_items(db: &Db, items: &syn::File) -> Identity {
+    walk_test_items_in(db, &tendon_api::identities::TEST_CRATE_A, items)
+}
+
+/// Like `walk_test_items`, into the crate `crate_id`.
+#[cfg(test)]
+pub(crate) fn walk_test_items_in(db: &Db, crate_id: &CrateId, items: &syn::File) -> Identity {
+    let mut walker = Walker::new(db, crate_id);
+    helpers::add_std_prelude(&mut walker, false).unwrap();
+    let root = walker.add_root_scope(Metadata::fake("{root}")).unwrap();
+    let loc = LocationMetadata {
+        module_path: root.clone(),
+        crate_data: db.crate_data(crate_id),
+        ..TEST_LOCATION_METADATA.clone()
+    };
+    insert_test_items(&mut walker, &loc, &items.items);
+    walker.complete();
+    root
+}
+
+#[cfg(test)]
+fn insert_test_items(walker: &mut Walker, loc: &LocationMetadata, items: &[syn::Item]) {
+    use crate::lower::attributes::lower_metadata;
+    use syn::spanned::Spanned;
+
+    for item in items {
+        if let syn::Item::Mod(mod_) = item {
+            let (_, items) = mod_.content.as_ref().expect("test modules must be inline");
+            let metadata = lower_metadata(
+                loc,
+                Ident::from(&mod_.ident),
+                &mod_.vis,
+                &mod_.attrs,
+                mod_.span(),
+            )
+            .unwrap();
+            let module_path = walker
+                .add(&loc.module_path, Scope::new(metadata, true))
+                .unwrap();
+            let loc = LocationMetadata {
+                module_path,
+                ..loc.clone()
+            };
+            insert_test_items(walker, &loc, items);
+        } else {
+            walker.insert_item(loc, item).unwrap();
+        }
+    }
+}
+
 quick_error! {
     // could break this out into sub-errors...
     #[derive(Debug)]
@@ -172,6 +237,11 @@ quick_error! {
         ModuleNotFound {
             display("couldn't find source file")
         }
+        Lower(err: LowerError) {
+            from()
+            cause(err)
+            display("lowering error during walking: {}", err)
+        }
         //NonPub {
         //    display("skipping non-pub item (will never be accessible)")
         //}
@@ -231,10 +301,10 @@ fn skip(kind: &str, path: Identity) {
 
 fn warn(cause: impl Into<WalkError>, span: &Span) {
     let cause = cause.into();
-    //if let WalkError::Lower(LowerError::CfgdOut) = cause {
-    //    // can just suppress this
-    //    return;
-    //}
+    if let WalkError::Lower(LowerError::CfgdOut) = cause {
+        // can just suppress this
+        return;
+    }
     warn!("[{:?}]: suppressing error: {}", span, cause);
 }
 
@@ -269,7 +339,8 @@ impl<'a> Walker<'a> {
 
     /// Complete the walker, inserting the parsed crate into the database.
     pub fn complete(self) {
-        let Walker { db, crate_, .. } = self;
+        let Walker { db, mut crate_, .. } = self;
+        crate::resolver::resolve_crate(db, &mut crate_);
         db.insert_crate(crate_);
     }
 
@@ -376,6 +447,125 @@ impl<'a> Walker<'a> {
             .map_err(|_| DatabaseError::BindingAlreadyPresent)
     }
 
+    /// Lower an item and add it to the module described by `loc`.
+    /// Items we don't handle yet are skipped.
+    pub(crate) fn insert_item(
+        &mut self,
+        loc: &LocationMetadata,
+        item: &syn::Item,
+    ) -> Result<(), WalkError> {
+        match item {
+            syn::Item::Fn(fn_) => {
+                let fn_ = lower_function_item(loc, fn_)?;
+                self.add(&loc.module_path, SymbolItem::Function(fn_))?;
+            }
+            syn::Item::Struct(struct_) => {
+                let struct_ = lower_struct(loc, struct_)?;
+                self.add(&loc.module_path, TypeItem::Struct(struct_))?;
+            }
+            syn::Item::Enum(enum_) => {
+                let enum_ = lower_enum(loc, enum_, &mut |_| None)?;
+                self.add(&loc.module_path, TypeItem::Enum(enum_))?;
+            }
+            syn::Item::Trait(trait_) => {
+                let trait_ = lower_trait(loc, trait_)?;
+                self.add(&loc.module_path, TypeItem::Trait(trait_))?;
+            }
+            syn::Item::Static(static_) => {
+                let static_ = lower_static(loc, static_)?;
+                self.add(&loc.module_path, SymbolItem::Static(static_))?;
+            }
+            syn::Item::Const(const_) => {
+                // references to other consts are evaluated once the whole crate is walked,
+                // see `resolver::const_values`.
+                let const_ = lower_const(loc, const_, &mut |_| None)?;
+                self.add(&loc.module_path, SymbolItem::Const(const_))?;
+            }
+            syn::Item::Type(type_) => {
+                let alias = lower_type_alias(loc, type_)?;
+                self.add(&loc.module_path, TypeItem::Alias(alias))?;
+            }
+            syn::Item::Union(union_) => {
+                let union_ = lower_union(loc, union_)?;
+                self.add(&loc.module_path, TypeItem::Union(union_))?;
+            }
+            syn::Item::TraitAlias(alias_) => {
+                skip("trait alias", loc.module_path.clone_join(&alias_.ident))
+            }
+            syn::Item::Impl(impl_) => {
+                let index = self
+                    .crate_
+                    .impls
+                    .iter()
+                    .filter(|other| other.scope == loc.module_path)
+                    .count();
+                let impl_ = lower_impl(loc, impl_, index, &mut |_| None)?;
+                self.crate_.impls.push(impl_);
+            }
+            syn::Item::ForeignMod(foreign_mod) => {
+                let (symbols, types) = lower_foreign_mod(loc, foreign_mod)?;
+                for symbol in symbols {
+                    self.add(&loc.module_path, symbol)?;
+                }
+                for type_ in types {
+                    self.add(&loc.module_path, type_)?;
+                }
+            }
+            syn::Item::Use(use_) => {
+                let use_ = lower_use(loc, use_)?;
+                for glob in &use_.glob_imports {
+                    trace!("skipping glob import {:?}", glob);
+                }
+                for (name, path) in &use_.imports {
+                    self.add_import(&loc.module_path, name, path, &use_.metadata)?;
+                }
+            }
+            syn::Item::Verbatim(_verbatim_) => skip("verbatim", loc.module_path.clone()),
+            _ => (), // do nothing
+        }
+        Ok(())
+    }
+
+    /// Bind `name` to what `path` names, in every namespace it resolves in, with the visibility
+    /// and doc flags of the `use` item.
+    /// Imports are resolved as they're inserted, so they can only name items that are already
+    /// present; anything else is skipped.
+    fn add_import(
+        &mut self,
+        module: &Identity,
+        name: &Ident,
+        path: &UnresolvedPath,
+        metadata: &Metadata,
+    ) -> Result<(), WalkError> {
+        let mut found = false;
+        for &namespace in NamespaceId::values().iter() {
+            let target = match try_to_resolve(self.db, &self.crate_, module, namespace, path) {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+            found = true;
+            self.add_binding_by(
+                module,
+                namespace,
+                name.clone(),
+                target,
+                metadata.visibility.clone(),
+                Priority::Explicit,
+            )?;
+            let binding = self
+                .crate_
+                .get_mut::<Scope>(module)
+                .and_then(|scope| scope.get_by_mut(namespace, name))
+                .expect("binding disappeared");
+            binding.doc_hidden = metadata.doc_hidden;
+            binding.doc_inline = metadata.doc_inline;
+        }
+        if !found {
+            skip("unresolved import", module.clone_join(name.clone()));
+        }
+        Ok(())
+    }
+
     /// Add the root scope.
     pub fn add_root_scope(&mut self, metadata: Metadata) -> Result<Identity, DatabaseError> {
         assert!(&metadata.name[..] == "{root}");
@@ -428,9 +618,12 @@ struct ScopeInProgress {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tendon_api::attributes::TypeMetadata;
+    use crate::macro_interp::apply_once;
+    use quote::quote;
+    use tendon_api::attributes::{DocInline, TypeMetadata};
+    use tendon_api::expressions::ConstValue;
     use tendon_api::identities::TEST_CRATE_A;
-    use tendon_api::items::{EnumItem, GenericParams, TypeItem};
+    use tendon_api::items::{DeclarativeMacroItem, EnumItem, TypeItem};
 
     #[test]
     fn crate_building() {
@@ -467,7 +660,7 @@ mod tests {
                 TypeItem::Enum(EnumItem {
                     metadata: Metadata::fake("C"),
                     type_metadata: TypeMetadata::default(),
-                    generic_params: GenericParams::default(),
+                    generics: Default::default(),
                     variants: vec![],
                 }),
             )
@@ -496,6 +689,204 @@ mod tests {
         assert_eq!(in_root.visibility, Visibility::InScope(root.clone()));
         assert_eq!(in_a_b.visibility, Visibility::Pub);
     }
+
+    #[test]
+    fn item_insertion() {
+        spoor::init();
+        let db = Db::fake_db();
+        let test_crate_a = (*TEST_CRATE_A).clone();
+
+        let mut walker = Walker::new(&db, &test_crate_a);
+        let root = walker.add_root_scope(Metadata::fake("{root}")).unwrap();
+        let loc = LocationMetadata {
+            module_path: root.clone(),
+            crate_data: db.crate_data(&test_crate_a),
+            ..TEST_LOCATION_METADATA.clone()
+        };
+
+        walker
+            .insert_item(
+                &loc,
+                &syn::parse_quote! {
+                    pub trait Thing: Clone {
+                        fn thing(&self);
+                    }
+                },
+            )
+            .unwrap();
+        walker
+            .insert_item(
+                &loc,
+                &syn::parse_quote!(
+                    pub fn f() {}
+                ),
+            )
+            .unwrap();
+        walker
+            .insert_item(
+                &loc,
+                &syn::parse_quote! {
+                    impl Thing for u8 {
+                        fn thing(&self) {}
+                    }
+                },
+            )
+            .unwrap();
+
+        let thing = root.clone_join("Thing");
+        if let Some(TypeItem::Trait(trait_)) = walker.crate_.get::<TypeItem>(&thing) {
+            assert_eq!(trait_.methods.len(), 1);
+            assert_eq!(trait_.supertraits.len(), 1);
+        } else {
+            panic!("trait not inserted");
+        }
+        assert!(walker
+            .crate_
+            .get_binding::<TypeItem>(&root, &"Thing".into())
+            .is_some());
+        assert!(walker
+            .crate_
+            .get_binding::<SymbolItem>(&root, &"f".into())
+            .is_some());
+        assert_eq!(walker.crate_.impls.len(), 1);
+        assert_eq!(walker.crate_.impls[0].methods.len(), 1);
+    }
+
+    #[test]
+    fn use_insertion() {
+        spoor::init();
+        let db = Db::fake_db();
+        let test_crate_a = (*TEST_CRATE_A).clone();
+
+        let mut walker = Walker::new(&db, &test_crate_a);
+        let root = walker.add_root_scope(Metadata::fake("{root}")).unwrap();
+        let inner = walker
+            .add(&root, Scope::new(Metadata::fake("inner"), true))
+            .unwrap();
+        let loc = LocationMetadata {
+            module_path: root.clone(),
+            crate_data: db.crate_data(&test_crate_a),
+            ..TEST_LOCATION_METADATA.clone()
+        };
+        let inner_loc = LocationMetadata {
+            module_path: inner.clone(),
+            ..loc.clone()
+        };
+
+        walker
+            .insert_item(
+                &inner_loc,
+                &syn::parse_quote!(
+                    pub struct Thing;
+                ),
+            )
+            .unwrap();
+        let items: syn::File = syn::parse_quote! {
+            #[doc(hidden)]
+            pub use inner::Thing as Hidden;
+            #[doc(inline)]
+            pub use inner::{Thing as Inlined, self as renamed};
+            #[doc(no_inline)]
+            pub use self::inner::Thing;
+            use inner::Missing;
+        };
+        for item in &items.items {
+            walker.insert_item(&loc, item).unwrap();
+        }
+
+        let thing = inner.clone_join("Thing");
+        let binding = |name: &str| {
+            walker
+                .crate_
+                .get_binding::<TypeItem>(&root, &name.into())
+                .unwrap()
+        };
+        let hidden = binding("Hidden");
+        assert_eq!(hidden.identity, thing);
+        assert_eq!(hidden.visibility, Visibility::Pub);
+        assert!(hidden.doc_hidden);
+        assert_eq!(hidden.doc_inline, None);
+        let inlined = binding("Inlined");
+        assert_eq!(inlined.identity, thing);
+        assert!(!inlined.doc_hidden);
+        assert_eq!(inlined.doc_inline, Some(DocInline::Inline));
+        assert_eq!(binding("Thing").doc_inline, Some(DocInline::NoInline));
+
+        let module = walker
+            .crate_
+            .get_binding::<Scope>(&root, &"renamed".into())
+            .unwrap();
+        assert_eq!(module.identity, inner);
+        assert_eq!(module.doc_inline, Some(DocInline::Inline));
+        assert!(walker
+            .crate_
+            .get_binding::<TypeItem>(&root, &"Missing".into())
+            .is_none());
+    }
+
+    #[test]
+    fn const_evaluation() {
+        spoor::init();
+        let db = Db::fake_db();
+        let test_crate_a = (*TEST_CRATE_A).clone();
+
+        let mut walker = Walker::new(&db, &test_crate_a);
+        let root = walker.add_root_scope(Metadata::fake("{root}")).unwrap();
+        let loc = LocationMetadata {
+            module_path: root.clone(),
+            crate_data: db.crate_data(&test_crate_a),
+            ..TEST_LOCATION_METADATA.clone()
+        };
+
+        // the consts from `test-crate`
+        let wacky_levels: syn::ItemMacro = syn::parse_quote! {
+            macro_rules! wacky_levels {
+                ($($name:ident),+ | $($type:ty),+ | $($expr:expr),+) => {
+                    $(
+                        pub const $name: $type = $expr;
+                    )+
+                }
+            }
+        };
+        let wacky_levels = DeclarativeMacroItem {
+            metadata: Metadata::fake("wacky_levels"),
+            tokens: Tokens::from(&wacky_levels),
+        };
+        let expanded = apply_once(&wacky_levels, quote!(M, N, O | i8, i32, i16 | 0, 1, 2)).unwrap();
+        let expanded: syn::File = syn::parse2(expanded).unwrap();
+        for item in &expanded.items {
+            walker.insert_item(&loc, item).unwrap();
+        }
+        walker
+            .insert_item(
+                &loc,
+                &syn::parse_quote!(
+                    pub const P: u64 = (N as u64 + 1) << 2;
+                ),
+            )
+            .unwrap();
+
+        // not walked yet when `Q` is lowered
+        let forward: syn::File = syn::parse_quote! {
+            pub const Q: u8 = R;
+            pub const R: u8 = 1;
+        };
+        for item in &forward.items {
+            walker.insert_item(&loc, item).unwrap();
+        }
+        walker.complete();
+
+        let value = |name: &str| match db.get_item::<SymbolItem>(&root.clone_join(name)) {
+            Some(SymbolItem::Const(const_)) => const_.evaluated.clone(),
+            _ => panic!("no const {}", name),
+        };
+        assert_eq!(value("M"), Some(ConstValue::Int(0, "i8".into())));
+        assert_eq!(value("N"), Some(ConstValue::Int(1, "i32".into())));
+        assert_eq!(value("O"), Some(ConstValue::Int(2, "i16".into())));
+        assert_eq!(value("P"), Some(ConstValue::Int(8, "u64".into())));
+        assert_eq!(value("Q"), Some(ConstValue::Int(1, "u8".into())));
+        assert_eq!(value("R"), Some(ConstValue::Int(1, "u8".into())));
+    }
 }
 
 /*
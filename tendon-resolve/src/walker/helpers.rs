@@ -455,13 +455,13 @@ mod tests {
     use tendon_api::crates::CrateData;
     use tendon_api::database::Db;
     use tendon_api::identities::{TEST_CRATE_A, TEST_CRATE_B, TEST_CRATE_C};
-    use tendon_api::items::{EnumItem, GenericParams};
+    use tendon_api::items::EnumItem;
 
     fn fake_type(name: &str) -> TypeItem {
         TypeItem::Enum(EnumItem {
             metadata: Metadata::fake(name),
             type_metadata: TypeMetadata::default(),
-            generic_params: GenericParams::default(),
+            generics: Default::default(),
             variants: vec![],
         })
     }